@@ -0,0 +1,39 @@
+use crate::fuzzy_eq::FuzzyEq;
+use crate::F;
+
+// Darkens the ambient term in creases and where bodies touch: rays cast
+// around the normal of a hit tell how much of the surroundings are open.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientOcclusion {
+    // rays cast from each hit
+    pub samples: usize,
+    // bodies farther than this do not occlude
+    pub radius: F,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        AmbientOcclusion {
+            samples: 16,
+            radius: 1.0,
+        }
+    }
+}
+
+impl AmbientOcclusion {
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_radius(mut self, radius: F) -> Self {
+        self.radius = radius;
+        self
+    }
+}
+
+impl FuzzyEq<AmbientOcclusion> for AmbientOcclusion {
+    fn fuzzy_eq(&self, other: AmbientOcclusion) -> bool {
+        self.samples == other.samples && self.radius.fuzzy_eq(other.radius)
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::matrix::Matrix;
+use crate::tuple::Tuple;
+use crate::world::World;
+use crate::F;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    // eases in and out of the keyframe, no sudden change of speed
+    Smooth,
+}
+
+impl Easing {
+    fn apply(&self, t: F) -> F {
+        match self {
+            Easing::Linear => t,
+            Easing::Smooth => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// One operation of a transform list, kept apart so that rotations are
+// interpolated by angle instead of blending matrices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransformStep {
+    Translate(Tuple),
+    Scale(Tuple),
+    RotateX(F),
+    RotateY(F),
+    RotateZ(F),
+}
+
+impl TransformStep {
+    pub fn matrix(&self) -> Matrix<4> {
+        match *self {
+            TransformStep::Translate(v) => Matrix::translation(v.x, v.y, v.z),
+            TransformStep::Scale(v) => Matrix::scaling(v.x, v.y, v.z),
+            TransformStep::RotateX(radians) => Matrix::rotation_x(radians),
+            TransformStep::RotateY(radians) => Matrix::rotation_y(radians),
+            TransformStep::RotateZ(radians) => Matrix::rotation_z(radians),
+        }
+    }
+}
+
+// the first step is applied first
+pub fn compose(steps: &[TransformStep]) -> Matrix<4> {
+    steps
+        .iter()
+        .fold(Matrix::identity(), |combined, step| step.matrix() * combined)
+}
+
+pub trait Interpolate: Clone {
+    // whether both values can be blended, e.g. transform lists of the same shape
+    fn matches(&self, _other: &Self) -> bool {
+        true
+    }
+    fn interpolate(&self, other: &Self, t: F) -> Self;
+}
+
+impl Interpolate for F {
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Tuple {
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+// Blends every coefficient, exact for translations and scalings. Rotations
+// are cut short rather than swept, which only shows for large angles.
+impl Interpolate for Matrix<4> {
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        let mut matrix = *self;
+        for row in 0..4 {
+            for column in 0..4 {
+                matrix[row][column] = self[row][column].interpolate(&other[row][column], t);
+            }
+        }
+        matrix
+    }
+}
+
+impl Interpolate for TransformStep {
+    fn matches(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        match (*self, *other) {
+            (TransformStep::Translate(a), TransformStep::Translate(b)) => {
+                TransformStep::Translate(a.interpolate(&b, t))
+            }
+            (TransformStep::Scale(a), TransformStep::Scale(b)) => TransformStep::Scale(a.interpolate(&b, t)),
+            (TransformStep::RotateX(a), TransformStep::RotateX(b)) => TransformStep::RotateX(a.interpolate(&b, t)),
+            (TransformStep::RotateY(a), TransformStep::RotateY(b)) => TransformStep::RotateY(a.interpolate(&b, t)),
+            (TransformStep::RotateZ(a), TransformStep::RotateZ(b)) => TransformStep::RotateZ(a.interpolate(&b, t)),
+            // tracks refuse steps that do not match, see Track::new
+            _ => *self,
+        }
+    }
+}
+
+impl<T: Interpolate> Interpolate for Vec<T> {
+    fn matches(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a.matches(b))
+    }
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        self.iter()
+            .zip(other.iter())
+            .map(|(a, b)| a.interpolate(b, t))
+            .collect()
+    }
+}
+
+// Where a camera stands and looks, see Camera::view_transform
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraView {
+    pub from: Tuple,
+    pub to: Tuple,
+    pub up: Tuple,
+}
+
+impl Interpolate for CameraView {
+    fn interpolate(&self, other: &Self, t: F) -> Self {
+        CameraView {
+            from: self.from.interpolate(&other.from, t),
+            to: self.to.interpolate(&other.to, t),
+            up: self.up.interpolate(&other.up, t),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    pub frame: usize,
+    pub value: T,
+    // how to move from this keyframe to the next one
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(frame: usize, value: T) -> Self {
+        Keyframe {
+            frame,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+// Values of one property over time, holding still before the first and after
+// the last keyframe.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Result<Self> {
+        if keyframes.is_empty() {
+            return Err(anyhow!("An animation track needs at least one keyframe"));
+        }
+        keyframes.sort_by_key(|keyframe| keyframe.frame);
+        for pair in keyframes.windows(2) {
+            if pair[0].frame == pair[1].frame {
+                return Err(anyhow!("Frame {} has more than one keyframe", pair[0].frame));
+            }
+            if !pair[0].value.matches(&pair[1].value) {
+                return Err(anyhow!(
+                    "Keyframes {} and {} cannot be interpolated, they need the same transforms in the same order",
+                    pair[0].frame,
+                    pair[1].frame
+                ));
+            }
+        }
+        Ok(Track { keyframes })
+    }
+
+    pub fn first_frame(&self) -> usize {
+        self.keyframes[0].frame
+    }
+
+    pub fn last_frame(&self) -> usize {
+        self.keyframes[self.keyframes.len() - 1].frame
+    }
+
+    // frames may be fractional, for anything happening between two frames
+    pub fn value_at(&self, frame: F) -> T {
+        let next = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.frame as F > frame);
+        match next {
+            None => self.keyframes[self.keyframes.len() - 1].value.clone(),
+            Some(0) => self.keyframes[0].value.clone(),
+            Some(index) => {
+                let from = &self.keyframes[index - 1];
+                let to = &self.keyframes[index];
+                let t = (frame - from.frame as F) / (to.frame - from.frame) as F;
+                from.value.interpolate(&to.value, from.easing.apply(t))
+            }
+        }
+    }
+}
+
+// Everything that moves in a scene, indexed like the bodies and lights of the
+// world and the names of the cameras.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animation {
+    pub frames: Option<RangeInclusive<usize>>,
+    pub bodies: Vec<(usize, Track<Vec<TransformStep>>)>,
+    pub lights: Vec<(usize, Track<Tuple>)>,
+    pub cameras: HashMap<String, Track<CameraView>>,
+}
+
+impl Animation {
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_none() && self.bodies.is_empty() && self.lights.is_empty() && self.cameras.is_empty()
+    }
+
+    // the explicit range if any, otherwise from the first to the last keyframe
+    pub fn frame_range(&self) -> Option<RangeInclusive<usize>> {
+        if let Some(frames) = &self.frames {
+            return Some(frames.clone());
+        }
+        let tracks = self
+            .bodies
+            .iter()
+            .map(|(_, track)| (track.first_frame(), track.last_frame()))
+            .chain(self.lights.iter().map(|(_, track)| (track.first_frame(), track.last_frame())))
+            .chain(self.cameras.values().map(|track| (track.first_frame(), track.last_frame())));
+        tracks.fold(None, |range, (first, last)| match range {
+            None => Some(first..=last),
+            Some(range) => Some(*range.start().min(&first)..=*range.end().max(&last)),
+        })
+    }
+
+    pub fn world_at(&self, world: &World, frame: F) -> World {
+        let mut world = world.clone();
+        for (index, track) in self.bodies.iter() {
+            world.bodies[*index] = world.bodies[*index].clone().with_transform(compose(&track.value_at(frame)));
+        }
+        for (index, track) in self.lights.iter() {
+            world.lights[*index].position = track.value_at(frame);
+        }
+        world
+    }
+
+    pub fn camera_at(&self, name: &str, camera: &Camera, frame: F) -> Camera {
+        match self.cameras.get(name) {
+            Some(track) => {
+                let view = track.value_at(frame);
+                camera.clone().view_transform(view.from, view.to, view.up)
+            }
+            None => camera.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{Body, Intersectable};
+    use crate::lights::PointLight;
+    use crate::sphere::Sphere;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn composing_steps_applies_the_first_one_first() {
+        let steps = [
+            TransformStep::RotateX(PI / 2.0),
+            TransformStep::Scale(Tuple::vector(5.0, 5.0, 5.0)),
+            TransformStep::Translate(Tuple::vector(10.0, 5.0, 7.0)),
+        ];
+
+        assert_eq!(compose(&steps) * Tuple::point(1.0, 0.0, 1.0), Tuple::point(15.0, 0.0, 7.0));
+    }
+
+    #[test]
+    fn tracks_interpolate_between_keyframes() {
+        let track = Track::new(vec![
+            Keyframe::new(10, 4.0).with_easing(Easing::Smooth),
+            Keyframe::new(1, 0.0),
+            Keyframe::new(5, 2.0),
+        ])
+        .unwrap();
+
+        assert_eq!(track.first_frame(), 1);
+        assert_eq!(track.last_frame(), 10);
+        assert_eq!(track.value_at(0.0), 0.0);
+        assert_eq!(track.value_at(3.0), 1.0);
+        assert_eq!(track.value_at(5.0), 2.0);
+        assert_eq!(track.value_at(12.0), 4.0);
+    }
+
+    #[test]
+    fn smooth_easing_starts_and_ends_slowly() {
+        let track = Track::new(vec![
+            Keyframe::new(0, 0.0).with_easing(Easing::Smooth),
+            Keyframe::new(10, 1.0),
+        ])
+        .unwrap();
+
+        assert!(track.value_at(1.0) < 0.1);
+        assert_eq!(track.value_at(5.0), 0.5);
+        assert!(track.value_at(9.0) > 0.9);
+    }
+
+    #[test]
+    fn rotations_are_interpolated_by_angle() {
+        let track = Track::new(vec![
+            Keyframe::new(0, vec![TransformStep::RotateY(0.0)]),
+            Keyframe::new(2, vec![TransformStep::RotateY(PI)]),
+        ])
+        .unwrap();
+
+        let halfway = compose(&track.value_at(1.0));
+
+        assert_eq!(halfway * Tuple::point(0.0, 0.0, 1.0), Tuple::point(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn tracks_refuse_keyframes_that_cannot_be_interpolated() {
+        let mismatched = Track::new(vec![
+            Keyframe::new(0, vec![TransformStep::RotateY(0.0)]),
+            Keyframe::new(2, vec![TransformStep::RotateX(PI)]),
+        ]);
+        assert_eq!(
+            mismatched.unwrap_err().to_string(),
+            "Keyframes 0 and 2 cannot be interpolated, they need the same transforms in the same order"
+        );
+
+        let duplicated = Track::new(vec![Keyframe::new(3, 0.0), Keyframe::new(3, 1.0)]);
+        assert_eq!(duplicated.unwrap_err().to_string(), "Frame 3 has more than one keyframe");
+        assert!(Track::<F>::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn animating_a_world_and_a_camera() {
+        let world = World::new(
+            vec![Body::from(Sphere::default())],
+            vec![PointLight::new(Tuple::point(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0))],
+        );
+        let camera = Camera::new(10, 10, PI / 2.0);
+        let mut animation = Animation::default();
+        animation.bodies.push((
+            0,
+            Track::new(vec![
+                Keyframe::new(1, vec![TransformStep::Translate(Tuple::vector(0.0, 0.0, 0.0))]),
+                Keyframe::new(3, vec![TransformStep::Translate(Tuple::vector(4.0, 0.0, 0.0))]),
+            ])
+            .unwrap(),
+        ));
+        animation.lights.push((
+            0,
+            Track::new(vec![
+                Keyframe::new(2, Tuple::point(0.0, 10.0, 0.0)),
+                Keyframe::new(4, Tuple::point(0.0, 20.0, 0.0)),
+            ])
+            .unwrap(),
+        ));
+        animation.cameras.insert(
+            "main".into(),
+            Track::new(vec![Keyframe::new(
+                1,
+                CameraView {
+                    from: Tuple::point(0.0, 0.0, -5.0),
+                    to: Tuple::point(0.0, 0.0, 0.0),
+                    up: Tuple::vector(0.0, 1.0, 0.0),
+                },
+            )])
+            .unwrap(),
+        );
+
+        assert_eq!(animation.frame_range(), Some(1..=4));
+        let frame = animation.world_at(&world, 2.0);
+        assert_eq!(frame.bodies[0].transform(), Matrix::translation(2.0, 0.0, 0.0));
+        assert_eq!(frame.lights[0].position, Tuple::point(0.0, 10.0, 0.0));
+        assert_eq!(world.bodies[0].transform(), Matrix::identity());
+        assert_eq!(
+            animation.camera_at("main", &camera, 2.0).transform,
+            camera.clone().view_transform(
+                Tuple::point(0.0, 0.0, -5.0),
+                Tuple::point(0.0, 0.0, 0.0),
+                Tuple::vector(0.0, 1.0, 0.0)
+            ).transform
+        );
+        assert_eq!(animation.camera_at("other", &camera, 2.0).transform, Matrix::identity());
+
+        animation.frames = Some(1..=48);
+        assert_eq!(animation.frame_range(), Some(1..=48));
+    }
+}
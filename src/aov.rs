@@ -0,0 +1,195 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::body::Intersectable;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::material::Illuminated;
+use crate::ray::Ray;
+use crate::world::World;
+use crate::F;
+
+// Auxiliary outputs: images of what the camera rays hit, rendered alongside
+// the shaded one for compositing, denoising and debugging. Pixels where the
+// rays reach nothing are black.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    // distance from the camera to the hit, in world units
+    Depth,
+    // shading normal, facing the eye, in world space
+    Normal,
+    // color of the surface before lighting
+    Albedo,
+    // index of the body in the world plus 1, 0 being no body
+    ObjectId,
+    // what the reflection adds to the shaded color
+    Reflection,
+}
+
+impl Aov {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "object_id",
+            Aov::Reflection => "reflection",
+        }
+    }
+
+    // the linear value seen along the ray
+    pub fn color_at(&self, world: &World, ray: Ray) -> Color {
+        let xs = world.intersect(ray);
+        let hit = match xs.hit() {
+            Some(hit) => hit,
+            None => return Color::black(),
+        };
+        let c = hit.get_computed();
+        match self {
+            Aov::Depth => {
+                let depth = hit.t * ray.direction.magnitude();
+                Color::new(depth, depth, depth)
+            }
+            Aov::Normal => Color::new(c.normalv.x, c.normalv.y, c.normalv.z),
+            Aov::Albedo => hit.body.material().albedo(&hit.body, c.over_point),
+            Aov::ObjectId => {
+                // the hit carries the body frozen at the time of the ray
+                let id = world
+                    .bodies
+                    .iter()
+                    .position(|body| body.at_time(ray.time) == hit.body)
+                    .map_or(0, |index| index + 1) as F;
+                Color::new(id, id, id)
+            }
+            Aov::Reflection => world.reflect_color_at(&hit.body.material(), &c, world.reflection_limit),
+        }
+    }
+
+    // Turns the linear values into something to look at, for image formats
+    // limited to [0, 1]: depth goes from white up close to dark grey at the
+    // farthest hit, normals from [-1, 1] to [0, 1] and each object gets its
+    // own hue. Pixels without a hit stay black.
+    pub fn displayable(&self, canvas: &Canvas) -> Canvas {
+        let mut displayable = canvas.clone();
+        let farthest = (0..canvas.height)
+            .flat_map(|y| (0..canvas.width).map(move |x| canvas.color_at(x, y).red))
+            .fold(0.0, F::max);
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                let color = canvas.color_at(x, y);
+                let color = match self {
+                    Aov::Depth if color.red > 0.0 => {
+                        let shade = 1.0 - color.red / (farthest * 1.25);
+                        Color::new(shade, shade, shade)
+                    }
+                    // misses have no normal, they stay black
+                    Aov::Normal if color != Color::black() => (color + Color::white()) * 0.5,
+                    Aov::ObjectId if color.red > 0.0 => hue(color.red * 0.618_033_988_75),
+                    _ => color,
+                };
+                displayable.write_pixel(x, y, color);
+            }
+        }
+        displayable
+    }
+}
+
+// fully saturated color, turn being the position on the color wheel
+fn hue(turn: F) -> Color {
+    let channel = |offset: F| {
+        let distance = ((turn + offset).fract() * 6.0 - 3.0).abs();
+        (distance - 1.0).clamp(0.0, 1.0)
+    };
+    Color::new(channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0))
+}
+
+impl fmt::Display for Aov {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Aov {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "depth" => Ok(Aov::Depth),
+            "normal" => Ok(Aov::Normal),
+            "albedo" => Ok(Aov::Albedo),
+            "object_id" => Ok(Aov::ObjectId),
+            "reflection" => Ok(Aov::Reflection),
+            _ => Err(anyhow!("Unknown output '{}'", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+    use crate::lights::PointLight;
+    use crate::material::{Material, Phong};
+    use crate::matrix::Matrix;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+    use crate::tuple::Tuple;
+
+    fn test_world() -> World {
+        let red = Phong::default().with_color(Color::new(1.0, 0.0, 0.0)).with_diffuse(0.5);
+        let mirror = Phong::default().with_reflective(0.5);
+        World::new(
+            vec![
+                Body::from(Plane::default().with_material(Material::from(mirror))),
+                Body::from(Sphere::default().with_transform(Matrix::translation(0.0, 2.0, 0.0)).with_material(Material::from(red))),
+            ],
+            vec![PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::white())],
+        )
+    }
+
+    #[test]
+    fn outputs_describe_the_hit() {
+        let w = test_world();
+        let at_the_sphere = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let under_the_sphere = Ray::new(Tuple::point(0.0, 0.9, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let at_nothing = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, -1.0));
+
+        assert_eq!(Aov::Depth.color_at(&w, at_the_sphere), Color::new(4.0, 4.0, 4.0));
+        assert_eq!(Aov::Normal.color_at(&w, at_the_sphere), Color::new(0.0, 0.0, -1.0));
+        assert_eq!(Aov::Albedo.color_at(&w, at_the_sphere), Color::new(0.5, 0.0, 0.0));
+        assert_eq!(Aov::ObjectId.color_at(&w, at_the_sphere), Color::new(2.0, 2.0, 2.0));
+        assert_eq!(Aov::ObjectId.color_at(&w, under_the_sphere), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(Aov::Reflection.color_at(&w, at_the_sphere), Color::black());
+        // the floor reflects the dark bottom of the sphere
+        let seen_in_the_floor = w.color_at(Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0)));
+        assert_eq!(seen_in_the_floor, Color::new(0.1, 0.0, 0.0));
+        assert_eq!(Aov::Reflection.color_at(&w, under_the_sphere), seen_in_the_floor * 0.5);
+        for aov in [Aov::Depth, Aov::Normal, Aov::Albedo, Aov::ObjectId, Aov::Reflection] {
+            assert_eq!(aov.color_at(&w, at_nothing), Color::black());
+            assert_eq!(aov.name().parse::<Aov>().unwrap(), aov);
+        }
+    }
+
+    #[test]
+    fn outputs_can_be_made_displayable() {
+        let mut canvas = Canvas::new(3, 1);
+        canvas.write_pixel(0, 0, Color::new(2.0, 2.0, 2.0));
+        canvas.write_pixel(1, 0, Color::new(4.0, 4.0, 4.0));
+
+        let depth = Aov::Depth.displayable(&canvas);
+        assert_eq!(depth.color_at(0, 0), Color::new(0.6, 0.6, 0.6));
+        assert_eq!(depth.color_at(1, 0), Color::new(0.2, 0.2, 0.2));
+        assert_eq!(depth.color_at(2, 0), Color::black());
+
+        let ids = Aov::ObjectId.displayable(&canvas);
+        assert_ne!(ids.color_at(0, 0), ids.color_at(1, 0));
+        assert_eq!(ids.color_at(2, 0), Color::black());
+
+        canvas.write_pixel(0, 0, Color::new(0.0, 1.0, 0.0));
+        let normal = Aov::Normal.displayable(&canvas);
+        assert_eq!(normal.color_at(0, 0), Color::new(0.5, 1.0, 0.5));
+        assert_eq!(normal.color_at(2, 0), Color::black());
+    }
+}
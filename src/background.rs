@@ -0,0 +1,211 @@
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::sphere::spherical_map;
+use crate::texture::Texture;
+use crate::tuple::Tuple;
+use crate::F;
+
+// What a ray sees when it doesn't hit any body
+#[derive(Clone, Debug, PartialEq)]
+pub enum Background {
+    Solid(Color),
+    Gradient { bottom: Color, top: Color },
+    Equirectangular(Texture),
+    CubeMap(CubeMap),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::black())
+    }
+}
+
+impl Background {
+    pub fn color_at(&self, direction: Tuple) -> Color {
+        let direction = direction.normalize();
+        match *self {
+            Background::Solid(color) => color,
+            Background::Gradient { bottom, top } => {
+                let fraction = (direction.y + 1.0) / 2.0;
+                bottom + (top - bottom) * fraction
+            }
+            Background::Equirectangular(ref texture) => {
+                let (u, v) = spherical_map(direction);
+                texture.color_at_uv(u, v)
+            }
+            Background::CubeMap(ref cube_map) => cube_map.color_at(direction),
+        }
+    }
+}
+
+impl FuzzyEq<Background> for Background {
+    fn fuzzy_eq(&self, other: Background) -> bool {
+        match (self, other) {
+            (Background::Solid(color), Background::Solid(other)) => color.fuzzy_eq(other),
+            (
+                Background::Gradient { bottom, top },
+                Background::Gradient { bottom: other_bottom, top: other_top },
+            ) => bottom.fuzzy_eq(other_bottom) && top.fuzzy_eq(other_top),
+            (Background::Equirectangular(texture), Background::Equirectangular(other)) => {
+                *texture == other
+            }
+            (Background::CubeMap(cube_map), Background::CubeMap(other)) => *cube_map == other,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeMap {
+    pub left: Texture,
+    pub front: Texture,
+    pub right: Texture,
+    pub back: Texture,
+    pub up: Texture,
+    pub down: Texture,
+}
+
+impl CubeMap {
+    pub fn new(
+        left: Texture,
+        front: Texture,
+        right: Texture,
+        back: Texture,
+        up: Texture,
+        down: Texture,
+    ) -> Self {
+        CubeMap {
+            left,
+            front,
+            right,
+            back,
+            up,
+            down,
+        }
+    }
+
+    pub fn color_at(&self, direction: Tuple) -> Color {
+        let abs_x = direction.x.abs();
+        let abs_y = direction.y.abs();
+        let abs_z = direction.z.abs();
+        let coord = abs_x.max(abs_y).max(abs_z);
+        // project the direction onto the unit cube, every face goes from -1 to 1
+        let point = direction / coord;
+        let face_uv = |a: F, b: F| ((a + 1.0).rem_euclid(2.0) / 2.0, (b + 1.0).rem_euclid(2.0) / 2.0);
+
+        if coord == direction.x {
+            let (u, v) = face_uv(-point.z, point.y);
+            self.right.color_at_uv(u, v)
+        } else if coord == -direction.x {
+            let (u, v) = face_uv(point.z, point.y);
+            self.left.color_at_uv(u, v)
+        } else if coord == direction.y {
+            let (u, v) = face_uv(point.x, -point.z);
+            self.up.color_at_uv(u, v)
+        } else if coord == -direction.y {
+            let (u, v) = face_uv(point.x, point.z);
+            self.down.color_at_uv(u, v)
+        } else if coord == direction.z {
+            let (u, v) = face_uv(point.x, point.y);
+            self.front.color_at_uv(u, v)
+        } else {
+            let (u, v) = face_uv(-point.x, point.y);
+            self.back.color_at_uv(u, v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    fn single_color_texture(color: Color) -> Texture {
+        let mut canvas = Canvas::new(2, 2);
+        for x in 0..2 {
+            for y in 0..2 {
+                canvas.write_pixel(x, y, color);
+            }
+        }
+        Texture::new(canvas)
+    }
+
+    #[test]
+    fn default_background_is_black() {
+        let background = Background::default();
+
+        assert_eq!(background.color_at(Tuple::vector(0.0, 1.0, 0.0)), Color::black());
+    }
+
+    #[test]
+    fn solid_background_is_the_same_in_every_direction() {
+        let color = Color::new(0.2, 0.3, 0.4);
+        let background = Background::Solid(color);
+
+        assert_eq!(background.color_at(Tuple::vector(0.0, 1.0, 0.0)), color);
+        assert_eq!(background.color_at(Tuple::vector(1.0, -3.0, 2.0)), color);
+    }
+
+    #[test]
+    fn gradient_background_goes_from_bottom_to_top() {
+        let background = Background::Gradient {
+            bottom: Color::white(),
+            top: Color::blue(),
+        };
+
+        assert_eq!(background.color_at(Tuple::vector(0.0, -1.0, 0.0)), Color::white());
+        assert_eq!(background.color_at(Tuple::vector(0.0, 1.0, 0.0)), Color::blue());
+        assert_eq!(
+            background.color_at(Tuple::vector(0.0, 0.0, 5.0)),
+            Color::new(0.5, 0.5, 1.0)
+        );
+    }
+
+    #[test]
+    fn equirectangular_background_uses_spherical_mapping() {
+        let mut canvas = Canvas::new(4, 2);
+        canvas.write_pixel(0, 0, Color::red());
+        canvas.write_pixel(3, 1, Color::green());
+        let background = Background::Equirectangular(Texture::new(canvas));
+
+        assert_eq!(background.color_at(Tuple::vector(0.0, 1.0, -0.01)), Color::red());
+        assert_eq!(background.color_at(Tuple::vector(-0.01, -1.0, -0.01)), Color::green());
+    }
+
+    #[test]
+    fn cube_map_picks_the_face_the_direction_points_to() {
+        let cube_map = CubeMap::new(
+            single_color_texture(Color::new(1.0, 0.0, 0.0)),
+            single_color_texture(Color::new(0.0, 1.0, 0.0)),
+            single_color_texture(Color::new(0.0, 0.0, 1.0)),
+            single_color_texture(Color::new(1.0, 1.0, 0.0)),
+            single_color_texture(Color::new(0.0, 1.0, 1.0)),
+            single_color_texture(Color::new(1.0, 0.0, 1.0)),
+        );
+        let background = Background::CubeMap(cube_map);
+        let expectations = [
+            (Tuple::vector(-1.0, 0.1, 0.5), Color::new(1.0, 0.0, 0.0)),
+            (Tuple::vector(0.2, 0.1, 1.0), Color::new(0.0, 1.0, 0.0)),
+            (Tuple::vector(1.0, -0.5, 0.3), Color::new(0.0, 0.0, 1.0)),
+            (Tuple::vector(0.2, 0.3, -1.0), Color::new(1.0, 1.0, 0.0)),
+            (Tuple::vector(0.2, 1.0, 0.4), Color::new(0.0, 1.0, 1.0)),
+            (Tuple::vector(-0.3, -1.0, 0.4), Color::new(1.0, 0.0, 1.0)),
+        ];
+        for (direction, expected) in expectations {
+            assert_eq!(background.color_at(direction), expected);
+        }
+    }
+
+    #[test]
+    fn cube_map_face_uv_mapping() {
+        let mut canvas = Canvas::new(2, 2);
+        // top left of the front face
+        canvas.write_pixel(0, 0, Color::red());
+        let front = Texture::new(canvas);
+        let other = single_color_texture(Color::black());
+        let cube_map = CubeMap::new(other.clone(), front, other.clone(), other.clone(), other.clone(), other);
+
+        assert_eq!(cube_map.color_at(Tuple::vector(-0.9, 0.9, 1.0)), Color::red());
+        assert_eq!(cube_map.color_at(Tuple::vector(0.9, 0.9, 1.0)), Color::black());
+    }
+}
@@ -32,12 +32,12 @@ fn main(){
     });
 
     let floor_sphere = Sphere::new(
-        floor_material,
+        floor_material.clone(),
         Matrix::scaling(10.0, 0.01, 10.0),
     );
   
     let left_wall_sphere = Sphere::new(
-      Material::from(floor_material),
+      Material::from(floor_material.clone()),
       Matrix::translation(0.0, 0.0, 5.0)
         * Matrix::rotation_y(-PI / 4.0)
         * Matrix::rotation_x(PI / 2.0)
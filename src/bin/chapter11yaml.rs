@@ -1,79 +1,222 @@
 use anyhow::{anyhow, Context, Result};
-use itertools::Itertools;
-use rayon::prelude::*;
-use rustic_rt::canvas::Canvas;
 use std::fs::{read_to_string, write};
-use std::sync::Mutex;
+use std::time::Instant;
+use rustic_rt::aov::Aov;
+use rustic_rt::camera::Camera;
+use rustic_rt::canvas::to_exr::*;
+use rustic_rt::canvas::to_hdr::*;
 use rustic_rt::canvas::to_png::*;
+use rustic_rt::canvas::to_ppm::*;
+use rustic_rt::filter::Filter;
+use rustic_rt::render::render_with_aovs;
+use rustic_rt::tone_mapping::{ToneMap, ToneMapping};
 use rustic_rt::world_loader::yaml;
 use rustic_rt::world_loader::WorldLoader;
 
 use indicatif::ProgressBar;
 
+struct Options {
+  source_file: String,
+  format: String,
+  operator: Option<ToneMapping>,
+  exposure: Option<f64>,
+  srgb: Option<bool>,
+  filters: Vec<Filter>,
+  aovs: Vec<Aov>,
+}
+
+impl Options {
+  // command line post-processing overrides the tone mapping of the scene and
+  // runs its filters after the ones of the scene
+  fn configure(&self, camera: &Camera) -> Camera {
+    let mut camera = camera.clone().with_tone_map(self.tone_map(camera.tone_map));
+    for filter in self.filters.iter() {
+      camera = camera.with_filter(*filter);
+    }
+    for aov in self.aovs.iter() {
+      camera = camera.with_aov(*aov);
+    }
+    camera
+  }
+
+  fn tone_map(&self, scene_tone_map: ToneMap) -> ToneMap {
+    let mut tone_map = scene_tone_map;
+    if let Some(operator) = self.operator {
+      tone_map = tone_map.with_operator(operator);
+    }
+    if let Some(exposure) = self.exposure {
+      tone_map = tone_map.with_exposure(exposure);
+    }
+    if let Some(srgb) = self.srgb {
+      tone_map = tone_map.with_srgb(srgb);
+    }
+    tone_map
+  }
+}
+
+fn parse_options(args: &[String]) -> Option<Options> {
+  let mut options = Options {
+    source_file: args.first()?.clone(),
+    format: String::from("png"),
+    operator: None,
+    exposure: None,
+    srgb: None,
+    filters: Vec::new(),
+    aovs: Vec::new(),
+  };
+  let mut remaining = args[1..].iter();
+  while let Some(option) = remaining.next() {
+    match option.as_str() {
+      "--format" => match remaining.next()?.as_str() {
+        format @ ("png" | "ppm" | "hdr" | "exr") => options.format = format.into(),
+        _ => return None,
+      },
+      "--tone-map" => {
+        options.operator = Some(match remaining.next()?.as_str() {
+          "clamp" => ToneMapping::Clamp,
+          "reinhard" => ToneMapping::Reinhard,
+          "filmic" => ToneMapping::Filmic,
+          "aces" => ToneMapping::Aces,
+          _ => return None,
+        })
+      }
+      "--exposure" => options.exposure = Some(remaining.next()?.parse().ok()?),
+      "--srgb" => {
+        options.srgb = Some(match remaining.next()?.as_str() {
+          "on" => true,
+          "off" => false,
+          _ => return None,
+        })
+      }
+      "--filter" => options.filters.push(remaining.next()?.parse().ok()?),
+      "--aov" => options.aovs.push(remaining.next()?.parse().ok()?),
+      _ => return None,
+    }
+  }
+  Some(options)
+}
+
 fn main() -> Result<()> {
   let args: Vec<String> = std::env::args().collect();
 
-  if args.len() != 2 {
+  let options = parse_options(&args[1..]);
+
+  if options.is_none() {
     println!(
       r#"
 The Raytracer Challenge Rust Renderer
 (c) 2023 Robin Kerjean
 
-Usage: {} <scene.yaml>
+Usage: {} <scene.yaml> [--format png|ppm|hdr|exr] [--tone-map clamp|reinhard|filmic|aces]
+          [--exposure <stops>] [--srgb on|off] [--filter <filter>]... [--aov <output>]...
+
+Filters are grayscale, sepia, invert, posterize:<levels>,
+adjust:<brightness>,<contrast>,<saturation> and color_matrix:<9 numbers>.
+
+Tone mapping and filters are added to the ones of the scene cameras and are
+ignored for hdr and exr output, which keep linear colors. Png and ppm output
+is sRGB encoded unless the scene or --srgb off says otherwise.
+
+Outputs are depth, normal, albedo, object_id and reflection. Each is written
+next to the render, like <camera>_depth.png. Hdr and exr outputs keep the raw
+values, the other formats make them displayable.
+
+Scenes with an animation or keyframes are rendered once per frame, into
+numbered files like <camera>_0001.png.
     "#,
       args[0]
     );
     return Err(anyhow!(
-      "Expected 1 argument but got {}: {:?}.",
+      "Expected a scene file followed by options but got {}: {:?}.",
       args.len() - 1,
       args
     ));
   }
+  let options = options.unwrap();
 
+  let load_start = Instant::now();
   let yaml_loader = yaml::Loader::default();
-  let source_file = &args[1];
+  let source_file = &options.source_file;
   let source =
     read_to_string(source_file).context(format!("Could not read world file {}", source_file))?;
-  let (world, cameras) = yaml_loader.load_world(source).context(format!(
+  let (world, cameras, animation) = yaml_loader.load_animation(source).context(format!(
     "Tried parsing {} as YAML world definition, but failed",
     source_file
   ))?;
 
   println!(
-    "Going to render {} camera perspectives for a world with {} lights and {} bodies.",
+    "Loaded {} in {:.3}s. Going to render {} camera perspectives for a world with {} lights and {} bodies.",
+    source_file,
+    load_start.elapsed().as_secs_f64(),
     cameras.len(),
     world.lights.len(),
     world.bodies.len()
   );
 
+  // a still image is rendered once, an animation once per frame
+  let frames: Vec<Option<usize>> = match animation.frame_range() {
+    Some(range) => range.map(Some).collect(),
+    None => vec![None],
+  };
+  if frames.len() > 1 {
+    println!("Animating frames {} to {}.", frames[0].unwrap(), frames[frames.len() - 1].unwrap());
+  }
+
   for (name, camera) in cameras.iter() {
-    let canvas_mutex = Mutex::new(Canvas::new(camera.hsize, camera.vsize));
+    for frame in frames.iter() {
+      let (world, camera, name) = match frame {
+        Some(frame) => (
+          animation.world_at(&world, *frame as f64),
+          animation.camera_at(name, camera, *frame as f64),
+          format!("{}_{:04}", name, frame),
+        ),
+        None => (world.clone(), camera.clone(), name.clone()),
+      };
+      let camera = &options.configure(&camera);
+      let pixel_count = camera.pixel_count();
+
+      println!("Raytracing {} with {} pixels...", name, pixel_count);
+      let progress = ProgressBar::new(pixel_count as u64);
+      progress.set_draw_rate(5);
 
-    let pixel_count = camera.hsize * camera.vsize;
+      let (canvas, aovs, mut stats) = render_with_aovs(&world, camera, || progress.inc(1));
 
-    println!("Raytracing {} with {} pixels...", name, pixel_count);
-    let progress = ProgressBar::new(pixel_count as u64);
-    progress.set_draw_rate(5);
+      progress.finish();
 
-    (0..camera.hsize) // x
-      .cartesian_product(0..camera.vsize) // y
-      .par_bridge()
-      .for_each(|(x, y)| {
-        let color = world.color_at(camera.ray_for_pixel(x, y));
-        let mut canvas = canvas_mutex.lock().unwrap();
-        canvas.write_pixel(x, y, color);
-        progress.inc(1);
-      });
+      let extension = options.format.as_str();
+      println!("Writing ./{}.{}", name, extension);
 
-    progress.finish();
+      let encode_start = Instant::now();
+      let image = match extension {
+        "ppm" => camera.post_process(&canvas).to_binary_ppm(),
+        "hdr" => canvas.to_hdr(),
+        "exr" => canvas.to_exr(),
+        _ => camera.post_process(&canvas).to_png(),
+      };
+      stats.add_phase("post-process and encode", encode_start.elapsed());
+      let write_start = Instant::now();
+      write(format!("./{}.{}", name, extension), image)
+        .context(format!("Could not write {}.{} to disk.", name, extension))?;
+      stats.add_phase("write", write_start.elapsed());
 
-    println!("Writing ./{}.png", name);
+      if !aovs.is_empty() {
+        let aovs_start = Instant::now();
+        for (aov, canvas) in aovs {
+          let image = match extension {
+            "ppm" => aov.displayable(&canvas).to_binary_ppm(),
+            "hdr" => canvas.to_hdr(),
+            "exr" => canvas.to_exr(),
+            _ => aov.displayable(&canvas).to_png(),
+          };
+          println!("Writing ./{}_{}.{}", name, aov, extension);
+          write(format!("./{}_{}.{}", name, aov, extension), image)
+            .context(format!("Could not write {}_{}.{} to disk.", name, aov, extension))?;
+        }
+        stats.add_phase("write auxiliary outputs", aovs_start.elapsed());
+      }
 
-    let canvas = canvas_mutex.lock().unwrap();
-    let png = canvas.to_png();
-    drop(canvas);
-    write(format!("./{}.png", name), png)
-      .context(format!("Could not write {}.png to disk.", name))?;
+      println!("{}", stats);
+    }
   }
 
   println!("Everything done.");
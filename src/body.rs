@@ -5,6 +5,7 @@ use crate::plane::Plane;
 // use crate::intersections::*;
 use crate::ray::*;
 use crate::material::Material;
+use crate::media::Medium;
 use crate::sphere::*;
 use crate::intersections::*;
 use crate::tuple::*;
@@ -12,12 +13,22 @@ use crate::tuple::*;
 pub trait Intersectable {
 	fn material(&self) -> Material;
 	fn normal_at_in_object_space(&self, point: Tuple) -> Tuple;
+	// maps a point on the surface to texture space, both u and v in [0, 1)
+	fn uv_at_in_object_space(&self, point: Tuple) -> (F, F);
+	// direction in which u grows on the surface, used to orient normal maps
+	fn tangent_at_in_object_space(&self, point: Tuple) -> Tuple;
 	fn intersect_in_object_space(&self, object_space_ray: Ray) -> Vec<(F, Body)>;
 	fn transform(&self) -> Matrix<4>;
+	// the body where it stands at a time of the shutter interval, from 0 for
+	// its transform to 1 for its end transform
+	fn at_time(&self, time: F) -> Body;
 	fn intersect(&self, ray: Ray) -> Intersections
 	{
-        let object_space_ray = ray.transform(self.transform().inverse());
-		let ts = self.intersect_in_object_space(object_space_ray);
+		// the hits carry the body frozen at the time of the ray, so that their
+		// normals and texture coordinates match where it was hit
+		let body = self.at_time(ray.time);
+		let object_space_ray = ray.transform(body.transform().inverse());
+		let ts = body.intersect_in_object_space(object_space_ray);
 		Intersections::new(ts.into_iter().map(|(t, body)| {
 			Intersection::new(t, ray, body)
 		}).collect())
@@ -30,9 +41,17 @@ pub trait Intersectable {
         world_normal.w = 0.0;
         world_normal.normalize() 
 	}
+
+	fn tangent_at(&self, point: Tuple, normalv: Tuple) -> Tuple{
+        let object_point = self.transform().inverse() * point;
+        let mut world_tangent = self.transform() * self.tangent_at_in_object_space(object_point);
+        world_tangent.w = 0.0;
+        // make it perpendicular to the normal again, non uniform scaling skews it
+        (world_tangent - normalv * world_tangent.dot(normalv)).normalize()
+	}
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Body{
 	Sphere(Sphere),
 	Plane(Plane),
@@ -50,6 +69,28 @@ impl From<Plane> for Body {
 	}
 }
 
+impl Body {
+	pub fn with_transform(self, transform: Matrix<4>) -> Self {
+		match self {
+			Body::Sphere(sphere) => Body::from(sphere.with_transform(transform)),
+			Body::Plane(plane) => Body::from(plane.with_transform(transform)),
+		}
+	}
+	pub fn with_end_transform(self, end_transform: Matrix<4>) -> Self {
+		match self {
+			Body::Sphere(sphere) => Body::from(sphere.with_end_transform(end_transform)),
+			Body::Plane(plane) => Body::from(plane.with_end_transform(end_transform)),
+		}
+	}
+	// smoke filling the body, only spheres are bounded and can hold some
+	pub fn medium(&self) -> Option<Medium> {
+		match self {
+			Body::Sphere(sphere) => sphere.medium,
+			Body::Plane(_) => None,
+		}
+	}
+}
+
 impl Intersectable for Body{
 	fn intersect_in_object_space(&self, object_space_ray: Ray) -> Vec<(F, Body)> {
 		match  *self {
@@ -63,6 +104,18 @@ impl Intersectable for Body{
 			Body::Plane(ref plane) => plane.normal_at_in_object_space(object_space_point),
 		}
 	}
+	fn uv_at_in_object_space(&self, object_space_point: Tuple) -> (F, F) {
+		match *self {
+			Body::Sphere(ref sphere) => sphere.uv_at_in_object_space(object_space_point),
+			Body::Plane(ref plane) => plane.uv_at_in_object_space(object_space_point),
+		}
+	}
+	fn tangent_at_in_object_space(&self, object_space_point: Tuple) -> Tuple {
+		match *self {
+			Body::Sphere(ref sphere) => sphere.tangent_at_in_object_space(object_space_point),
+			Body::Plane(ref plane) => plane.tangent_at_in_object_space(object_space_point),
+		}
+	}
 	fn material(&self) -> Material {
 		match  *self {
 			Body::Sphere(ref sphere) => sphere.material(),
@@ -75,10 +128,16 @@ impl Intersectable for Body{
 			Body::Plane(ref plane) => plane.transform(),
 		}
 	}
+	fn at_time(&self, time: F) -> Body {
+		match *self {
+			Body::Sphere(ref sphere) => sphere.at_time(time),
+			Body::Plane(ref plane) => plane.at_time(time),
+		}
+	}
 }
 impl FuzzyEq<Body> for Body {
 	fn fuzzy_eq(&self, other: Body) -> bool {
-	  match (*self, other) {
+	  match (self, &other) {
 		(Body::Sphere(ref sphere), Body::Sphere(ref other)) => sphere.fuzzy_eq(other),
 		(Body::Plane(ref plane), Body::Plane(ref other)) => plane.fuzzy_eq(other),
 		_ => false,
@@ -98,10 +157,24 @@ mod tests{
 		let s = Sphere::default();
 
 		let r = Ray::new(Tuple::point(1.0, 1.0, 1.0), Tuple::vector(0.0, 0.0, 1.0));
-		let i = Intersection::new(3.5, r, Body::from(s));
+		let i = Intersection::new(3.5, r, Body::from(s.clone()));
 		assert_eq!(i.t, 3.5);
 		assert_eq!(i.body, Body::from(s));
 	}
+	#[test]
+	fn	a_moving_body_is_hit_where_it_is_at_the_time_of_the_ray()
+	{
+		let s = Sphere::default().with_end_transform(Matrix::translation(4.0, 0.0, 0.0));
+		let r = Ray::new(Tuple::point(2.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+		let at_start = Body::from(s.clone()).intersect(r);
+		let halfway = Body::from(s).intersect(r.with_time(0.5));
+		assert_eq!(at_start.len(), 0);
+		assert_eq!(halfway.len(), 2);
+		assert_eq!(halfway[0].t, 4.0);
+		assert_eq!(halfway[0].body.transform(), Matrix::translation(2.0, 0.0, 0.0));
+		assert_eq!(halfway[0].get_computed().normalv, Tuple::vector(0.0, 0.0, -1.0));
+	}
 	// #[test]
 	// fn	aggregating_intersections()
 	// {
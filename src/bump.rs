@@ -0,0 +1,150 @@
+use crate::body::{Body, Intersectable};
+use crate::fuzzy_eq::FuzzyEq;
+use crate::noise::perlin;
+use crate::tuple::Tuple;
+use crate::F;
+
+// step used to estimate the slope of the height field
+const DELTA: F = 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BumpKind {
+    // irregular bumps, from orange peel to hammered metal depending on scale
+    Noise,
+    // concentric waves around the y axis of the body, like water ripples
+    Ripples,
+}
+
+// Fakes small surface details by tilting the normal along the slope of a
+// procedural height field defined in object space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bump {
+    pub kind: BumpKind,
+    pub strength: F,
+    pub scale: F,
+}
+
+impl Default for Bump {
+    fn default() -> Self {
+        Bump {
+            kind: BumpKind::Noise,
+            strength: 0.5,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Bump {
+    pub fn with_kind(mut self, kind: BumpKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_strength(mut self, strength: F) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: F) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    fn height_at(&self, point: Tuple) -> F {
+        match self.kind {
+            BumpKind::Noise => perlin(point),
+            BumpKind::Ripples => (point.x.powi(2) + point.z.powi(2)).sqrt().sin(),
+        }
+    }
+
+    // slope of the height field, in object space
+    fn gradient_at(&self, object_point: Tuple) -> Tuple {
+        let point = object_point * self.scale;
+        let dx = Tuple::vector(DELTA, 0.0, 0.0);
+        let dy = Tuple::vector(0.0, DELTA, 0.0);
+        let dz = Tuple::vector(0.0, 0.0, DELTA);
+        Tuple::vector(
+            self.height_at(point + dx) - self.height_at(point - dx),
+            self.height_at(point + dy) - self.height_at(point - dy),
+            self.height_at(point + dz) - self.height_at(point - dz),
+        ) / (2.0 * DELTA)
+    }
+
+    pub fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        let object_point = body.transform().inverse() * position;
+        // the gradient is transformed like a normal to get back to world space
+        let mut gradient = body.transform().inverse().transpose() * self.gradient_at(object_point);
+        gradient.w = 0.0;
+        let tangential = gradient - normalv * gradient.dot(normalv);
+        (normalv - tangential * self.strength).normalize()
+    }
+}
+
+impl FuzzyEq<Bump> for Bump {
+    fn fuzzy_eq(&self, other: Bump) -> bool {
+        self.kind == other.kind
+            && self.strength.fuzzy_eq(other.strength)
+            && self.scale.fuzzy_eq(other.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn zero_strength_keeps_the_normal() {
+        let bump = Bump::default().with_strength(0.0);
+        let body = Body::from(Sphere::default());
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+
+        let perturbed = bump.perturb_normal(&body, Tuple::point(0.3, 0.2, -0.9), normalv);
+
+        assert_eq!(perturbed, normalv);
+    }
+
+    #[test]
+    fn perturbed_normal_stays_normalized_and_changes() {
+        let bump = Bump::default().with_strength(1.0).with_scale(4.0);
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.3, 0.2, -0.9327379);
+        let normalv = body.normal_at(position);
+
+        let perturbed = bump.perturb_normal(&body, position, normalv);
+
+        assert!(perturbed.magnitude().fuzzy_eq(1.0));
+        assert_ne!(perturbed, normalv);
+        assert!(perturbed.dot(normalv) > 0.0);
+    }
+
+    #[test]
+    fn ripples_tilt_the_normal_away_from_the_crest() {
+        let bump = Bump::default().with_kind(BumpKind::Ripples).with_strength(1.0);
+        let body = Body::from(Plane::default());
+        let normalv = Tuple::vector(0.0, 1.0, 0.0);
+
+        // sin(r) rises between 0 and PI/2, so the normal leans towards the center
+        let perturbed = bump.perturb_normal(&body, Tuple::point(1.0, 0.0, 0.0), normalv);
+
+        assert!(perturbed.x < 0.0);
+        assert!(perturbed.y > 0.0);
+        assert!(perturbed.z.fuzzy_eq(0.0));
+    }
+
+    #[test]
+    fn bumps_follow_the_body_transform() {
+        let bump = Bump::default().with_strength(1.0).with_scale(3.0);
+        let body = Body::from(Plane::default());
+        let moved_body =
+            Body::from(Plane::default().with_transform(Matrix::translation(5.0, 0.0, 0.0)));
+        let normalv = Tuple::vector(0.0, 1.0, 0.0);
+
+        let perturbed = bump.perturb_normal(&body, Tuple::point(0.3, 0.0, 0.4), normalv);
+        let moved = bump.perturb_normal(&moved_body, Tuple::point(5.3, 0.0, 0.4), normalv);
+
+        assert_eq!(perturbed, moved);
+    }
+}
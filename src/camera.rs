@@ -1,7 +1,15 @@
 use crate::F;
+use crate::aov::Aov;
+use crate::canvas::Canvas;
+use crate::filter::{apply_filters, Filter};
 use crate::fuzzy_eq::FuzzyEq;
+use crate::integrator::Integrator;
 use crate::matrix::Matrix;
+use crate::motion_blur::MotionBlur;
+use crate::outline::Outline;
 use crate::ray::Ray;
+use crate::stereo::Stereo;
+use crate::tone_mapping::ToneMap;
 use crate::tuple::Tuple;
 
 #[derive(Clone, Debug)]
@@ -10,9 +18,19 @@ pub struct Camera {
 	pub hsize: usize,
 	pub vsize: usize,
 	pub field_of_view: F,
+	pub tone_map: ToneMap,
+	pub filters: Vec<Filter>,
+	pub outline: Option<Outline>,
+	pub stereo: Option<Stereo>,
+	pub motion_blur: Option<MotionBlur>,
+	pub integrator: Integrator,
+	// extra images rendered along with this camera, see render_aovs
+	pub aovs: Vec<Aov>,
 	half_width: F,
 	half_height: F,
 	pixel_size: F,
+	// moves the image plane sideways, for off-axis stereo eyes
+	horizontal_shift: F,
 }
 
 impl Camera{
@@ -37,15 +55,90 @@ impl Camera{
 			vsize,
 			field_of_view,
 			transform: Matrix::identity(),
+			tone_map: ToneMap::default(),
+			filters: Vec::new(),
+			outline: None,
+			stereo: None,
+			motion_blur: None,
+			integrator: Integrator::default(),
+			aovs: Vec::new(),
 			half_height,
 			half_width,
 			pixel_size,
+			horizontal_shift: 0.0,
 		}
 	}
 	pub fn with_transform(mut self, transform : Matrix<4>) -> Self {
 		self.transform = transform;
 		self
 	}
+	// keeps the field of view, orientation and post-processing at another resolution
+	pub fn with_size(self, hsize: usize, vsize: usize) -> Self {
+		let resized = Camera::new(hsize, vsize, self.field_of_view);
+		Camera {
+			hsize,
+			vsize,
+			half_width: resized.half_width,
+			half_height: resized.half_height,
+			pixel_size: resized.pixel_size,
+			..self
+		}
+	}
+	pub fn with_tone_map(mut self, tone_map: ToneMap) -> Self {
+		self.tone_map = tone_map;
+		self
+	}
+	pub fn with_filter(mut self, filter: Filter) -> Self {
+		self.filters.push(filter);
+		self
+	}
+	pub fn with_outline(mut self, outline: Outline) -> Self {
+		self.outline = Some(outline);
+		self
+	}
+	pub fn with_stereo(mut self, stereo: Stereo) -> Self {
+		self.stereo = Some(stereo);
+		self
+	}
+	pub fn with_motion_blur(mut self, motion_blur: MotionBlur) -> Self {
+		self.motion_blur = Some(motion_blur);
+		self
+	}
+	pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+		self.integrator = integrator;
+		self
+	}
+	pub fn with_aov(mut self, aov: Aov) -> Self {
+		if !self.aovs.contains(&aov) {
+			self.aovs.push(aov);
+		}
+		self
+	}
+	// Cameras for the left and right eyes. The eyes sit on the horizontal axis
+	// of this camera and their image planes are shifted rather than rotated, so
+	// both frame the same plane at the convergence distance without any
+	// vertical parallax.
+	pub fn stereo_pair(&self, interocular: F, convergence: F) -> (Camera, Camera) {
+		(self.eye(interocular / 2.0, convergence), self.eye(-interocular / 2.0, convergence))
+	}
+	// pixels traced to render this camera, both eyes included
+	pub fn pixel_count(&self) -> usize {
+		let views = if self.stereo.is_some() { 2 } else { 1 };
+		self.hsize * self.vsize * views
+	}
+	// positive offsets are towards the left of the image
+	fn eye(&self, offset: F, convergence: F) -> Camera {
+		Camera {
+			transform: Matrix::translation(-offset, 0.0, 0.0) * self.transform,
+			horizontal_shift: self.horizontal_shift - offset / convergence,
+			stereo: None,
+			..self.clone()
+		}
+	}
+	// turns the linear render into the final image: tone mapping, then filters
+	pub fn post_process(&self, canvas: &Canvas) -> Canvas {
+		apply_filters(&self.filters, &self.tone_map.apply(canvas))
+	}
 	pub fn view_transform(mut self, from: Tuple, to: Tuple, up: Tuple) -> Self {
 		let forward = (to - from).normalize();
 		let upn = up.normalize();
@@ -66,7 +159,7 @@ impl Camera{
 	pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
 		let offset_x = (0.5 + x as f64) * self.pixel_size;
 		let offset_y = (0.5 + y as f64) * self.pixel_size;
-		let world_x = self.half_width - offset_x;
+		let world_x = self.half_width - offset_x + self.horizontal_shift;
 		let world_y = self.half_height - offset_y;
 
 		let inverse_view_transform = self.transform.inverse();
@@ -77,6 +170,14 @@ impl Camera{
 		let ray = Ray::new(ray_origin, ray_direction);
 
 		ray
+	}
+	// the rays whose colors are averaged for the pixel, one per shutter sample
+	pub fn rays_for_pixel(&self, x: usize, y: usize) -> Vec<Ray> {
+		let ray = self.ray_for_pixel(x, y);
+		match self.motion_blur {
+			Some(ref motion_blur) => motion_blur.times(x, y).map(|time| ray.with_time(time)).collect(),
+			None => vec![ray],
+		}
 	}	
 }
 
@@ -86,12 +187,21 @@ impl FuzzyEq<Camera> for Camera {
       && self.vsize == other.vsize
       && self.hsize == other.hsize
       && self.field_of_view.fuzzy_eq(other.field_of_view)
+      && self.tone_map.fuzzy_eq(other.tone_map)
+      && self.filters.fuzzy_eq(other.filters)
+      && self.outline.fuzzy_eq(other.outline)
+      && self.stereo.fuzzy_eq(other.stereo)
+      && self.motion_blur.fuzzy_eq(other.motion_blur)
+      && self.integrator.fuzzy_eq(other.integrator)
+      && self.aovs == other.aovs
   }
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::color::Color;
+	use crate::tone_mapping::ToneMapping;
 	use std::f64::consts::PI;
 	#[test]
   fn constructing_a_camera() {
@@ -175,6 +285,59 @@ mod tests {
     assert!(c.pixel_size.fuzzy_eq(0.01));
   }
 
+  #[test]
+  fn resizing_a_camera_keeps_its_orientation() {
+    let transform = Matrix::rotation_y(PI / 4.0) * Matrix::translation(0.0, -2.0, 5.0);
+    let c = Camera::new(400, 250, PI / 2.0).with_transform(transform).with_size(200, 125);
+
+    assert_eq!(c.hsize, 200);
+    assert_eq!(c.vsize, 125);
+    assert!(c.pixel_size.fuzzy_eq(0.01));
+    assert_eq!(c.transform, transform);
+  }
+
+  #[test]
+  fn post_processing_tone_maps_then_filters() {
+    let mut canvas = Canvas::new(1, 1);
+    canvas.write_pixel(0, 0, Color::new(3.0, 1.0, 0.0));
+    let c = Camera::new(1, 1, PI / 2.0)
+      .with_tone_map(ToneMap::default().with_operator(ToneMapping::Reinhard).with_srgb(false))
+      .with_filter(Filter::Invert)
+      .with_filter(Filter::Posterize(3));
+
+    let image = c.with_size(4, 4).post_process(&canvas);
+
+    // (0.75, 0.5, 0.0) inverted to (0.25, 0.5, 1.0), then rounded to halves
+    assert_eq!(image.color_at(0, 0), Color::new(0.5, 0.5, 1.0));
+  }
+
+  #[test]
+  fn stereo_eyes_sit_on_the_horizontal_axis_of_the_camera() {
+    let c = Camera::new(201, 101, PI / 2.0).view_transform(
+      Tuple::point(0.0, 0.0, -5.0),
+      Tuple::point(0.0, 0.0, 0.0),
+      Tuple::vector(0.0, 1.0, 0.0),
+    );
+
+    let (left, right) = c.stereo_pair(0.5, 5.0);
+    let left_ray = left.ray_for_pixel(100, 50);
+    let right_ray = right.ray_for_pixel(100, 50);
+
+    // the left of the image is towards -x when looking down +z
+    assert_eq!(left_ray.origin, Tuple::point(-0.25, 0.0, -5.0));
+    assert_eq!(right_ray.origin, Tuple::point(0.25, 0.0, -5.0));
+    // both centers meet at the convergence distance
+    assert_eq!(left_ray.position(5.0 / left_ray.direction.z), Tuple::point(0.0, 0.0, 0.0));
+    assert_eq!(right_ray.position(5.0 / right_ray.direction.z), Tuple::point(0.0, 0.0, 0.0));
+    // and every other pixel too, without vertical parallax
+    let left_corner = left.ray_for_pixel(0, 0);
+    let right_corner = right.ray_for_pixel(0, 0);
+    assert_eq!(
+      left_corner.position(5.0 / left_corner.direction.z),
+      right_corner.position(5.0 / right_corner.direction.z)
+    );
+  }
+
   #[test]
   fn view_transform_for_the_default_orientation() {
     let from = Tuple::point(0.0, 0.0, 0.0);
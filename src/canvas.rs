@@ -1,6 +1,11 @@
 pub mod to_rgba32;
 pub mod to_ppm;
 pub mod to_png;
+pub mod from_png;
+pub mod from_ppm;
+pub mod compare;
+pub mod to_hdr;
+pub mod to_exr;
 
 use std::vec::Vec;
 use crate::color::Color;
@@ -10,7 +15,7 @@ use crate::color::Color;
 // 	fn	height(&self) -> usize;
 // }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct	Canvas{
 	pub width : usize,
 	pub height : usize,
@@ -57,6 +62,9 @@ impl Canvas{
 mod tests{
 	use super::*;
 	use super::to_ppm::ToPPM;
+	use super::to_png::ToPNG;
+	use super::from_png::FromPNG;
+	use super::from_ppm::FromPPM;
 	#[test]
 	fn	creating_canvas()
 	{
@@ -127,4 +135,91 @@ mod tests{
 
 		assert_eq!(actual_result, expected);
 	}
-}
\ No newline at end of file
+	#[test]
+	fn	reading_back_a_png()
+	{
+		let mut canvas = Canvas::new(3, 2);
+		canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+		canvas.write_pixel(1, 0, Color::new(0.0, 1.0, 0.0));
+		canvas.write_pixel(2, 1, Color::new(0.2, 0.4, 0.6));
+
+		let decoded = Canvas::from_png(&canvas.to_png()).unwrap();
+
+		assert_eq!(decoded.width, 3);
+		assert_eq!(decoded.height, 2);
+		assert_eq!(decoded.color_at(0, 0), Color::new(1.0, 0.0, 0.0));
+		assert_eq!(decoded.color_at(1, 0), Color::new(0.0, 1.0, 0.0));
+		assert_eq!(decoded.color_at(2, 1), Color::new(51.0 / 255.0, 102.0 / 255.0, 153.0 / 255.0));
+		assert_eq!(decoded.color_at(1, 1), Color::black());
+	}
+	#[test]
+	fn	reading_garbage_as_png_fails()
+	{
+		assert!(Canvas::from_png(b"definitely not a png").is_err());
+	}
+	#[test]
+	fn	constructing_binary_ppm()
+	{
+		let mut can = Canvas::new(2, 1);
+		can.write_pixel(0, 0, Color::new(1.5, 0.0, 0.5));
+		can.write_pixel(1, 0, Color::new(0.0, 0.2, 1.0));
+		let mut expected = String::from("P6\n2 1\n255\n").into_bytes();
+		expected.extend([255, 0, 128, 0, 51, 255]);
+
+		assert_eq!(can.to_binary_ppm(), expected);
+	}
+	#[test]
+	fn	reading_back_ppm()
+	{
+		let mut canvas = Canvas::new(5, 3);
+		canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+		canvas.write_pixel(2, 1, Color::new(0.2, 0.4, 0.6));
+		canvas.write_pixel(4, 2, Color::new(0.0, 0.0, 1.0));
+
+		for ppm in [canvas.to_ppm(), canvas.to_binary_ppm()] {
+			let decoded = Canvas::from_ppm(&ppm).unwrap();
+			assert_eq!(decoded.width, 5);
+			assert_eq!(decoded.height, 3);
+			assert_eq!(decoded.color_at(0, 0), Color::new(1.0, 0.0, 0.0));
+			assert_eq!(decoded.color_at(2, 1), Color::new(51.0 / 255.0, 102.0 / 255.0, 153.0 / 255.0));
+			assert_eq!(decoded.color_at(4, 2), Color::new(0.0, 0.0, 1.0));
+			assert_eq!(decoded.color_at(1, 1), Color::black());
+		}
+	}
+	#[test]
+	fn	reading_ppm_with_comments_and_wide_samples()
+	{
+		let ppm = b"P3\n# made by hand\n1 2 # size\n1000\n1000 0 500\n0 250 0\n";
+		let decoded = Canvas::from_ppm(ppm).unwrap();
+		assert_eq!(decoded.color_at(0, 0), Color::new(1.0, 0.0, 0.5));
+		assert_eq!(decoded.color_at(0, 1), Color::new(0.0, 0.25, 0.0));
+
+		let mut binary = b"P6 1 1 65535\n".to_vec();
+		binary.extend([0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+		let decoded = Canvas::from_ppm(&binary).unwrap();
+		assert_eq!(decoded.color_at(0, 0), Color::new(1.0, 32768.0 / 65535.0, 0.0));
+	}
+	#[test]
+	fn	reading_malformed_ppm_fails_with_clear_errors()
+	{
+		let error = |data: &[u8]| Canvas::from_ppm(data).unwrap_err().to_string();
+
+		assert_eq!(error(b""), "Empty PPM file");
+		assert_eq!(error(b"P5 1 1 255 a"), "Unsupported PPM magic number 'P5', expected P3 or P6");
+		assert_eq!(error(b"P3 4"), "PPM header ends before the height");
+		assert_eq!(error(b"P3 x 1 255"), "Invalid width in PPM: 'x'");
+		assert_eq!(error(b"P3 0 1 255"), "Invalid PPM size 0x1");
+		assert_eq!(error(b"P3 1 1 70000"), "PPM maximum color value must be between 1 and 65535, got 70000");
+		assert_eq!(error(b"P3 1 1 255 1 2"), "PPM pixel data is truncated: expected 3 samples, found 2");
+		assert_eq!(error(b"P6 2 1 255\n\x01\x02\x03"), "PPM pixel data is truncated: expected 6 bytes, found 3");
+		assert_eq!(error(b"P3 1 1 255 1 2 300"), "PPM sample 300 exceeds the maximum color value 255");
+		assert_eq!(
+			error(b"P3\n100000 100000\n255\n0 0 0"),
+			"PPM pixel data is truncated: expected 30000000000 samples, found 3"
+		);
+		assert_eq!(
+			error(b"P6\n4294967296 4294967296\n255\n\x01\x02\x03"),
+			"PPM size 4294967296x4294967296 is too large"
+		);
+	}
+}
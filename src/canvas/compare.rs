@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+
+use crate::color::Color;
+use super::Canvas;
+
+// Summary of how far two images are from each other, errors are measured
+// per channel in linear color units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct	Comparison {
+	pub max_error: f64,
+	pub mean_error: f64,
+	// peak signal to noise ratio in dB, with 1.0 as the peak, infinite for identical images
+	pub psnr: f64,
+}
+
+pub trait	Compare {
+	// absolute difference of every channel of every pixel
+	fn	difference(&self, other: &Self) -> Result<Canvas>;
+	fn	compare(&self, other: &Self) -> Result<Comparison>;
+	// black where the images match, through red and yellow to white on the worst pixel
+	fn	heat_map(&self, other: &Self) -> Result<Canvas>;
+}
+
+fn	heat_color(t: f64) -> Color
+{
+	let t = t.clamp(0.0, 1.0) * 3.0;
+	Color::new(t.min(1.0), (t - 1.0).clamp(0.0, 1.0), (t - 2.0).clamp(0.0, 1.0))
+}
+
+fn	largest_channel(color: Color) -> f64
+{
+	color.red.max(color.green).max(color.blue)
+}
+
+impl Compare for Canvas
+{
+	fn	difference(&self, other: &Self) -> Result<Canvas>
+	{
+		if self.width != other.width || self.height != other.height {
+			return Err(anyhow!(
+				"Cannot compare a {}x{} image with a {}x{} one",
+				self.width,
+				self.height,
+				other.width,
+				other.height
+			));
+		}
+		let mut difference = Canvas::new(self.width, self.height);
+		for (pixel, (a, b)) in difference
+			.pixels
+			.iter_mut()
+			.zip(self.pixels.iter().zip(other.pixels.iter()))
+		{
+			*pixel = Color::new(
+				(a.red - b.red).abs(),
+				(a.green - b.green).abs(),
+				(a.blue - b.blue).abs(),
+			);
+		}
+		Ok(difference)
+	}
+
+	fn	compare(&self, other: &Self) -> Result<Comparison>
+	{
+		let difference = self.difference(other)?;
+		let sample_count = (difference.pixels.len() * 3) as f64;
+		let mut max_error: f64 = 0.0;
+		let mut sum = 0.0;
+		let mut squared_sum = 0.0;
+		for pixel in difference.pixels.iter() {
+			for error in [pixel.red, pixel.green, pixel.blue] {
+				max_error = max_error.max(error);
+				sum += error;
+				squared_sum += error * error;
+			}
+		}
+		let mean_squared_error = squared_sum / sample_count;
+		Ok(Comparison {
+			max_error,
+			mean_error: sum / sample_count,
+			psnr: -10.0 * mean_squared_error.log10(),
+		})
+	}
+
+	fn	heat_map(&self, other: &Self) -> Result<Canvas>
+	{
+		let difference = self.difference(other)?;
+		let max_error = difference
+			.pixels
+			.iter()
+			.map(|pixel| largest_channel(*pixel))
+			.fold(0.0, f64::max);
+		let mut heat_map = Canvas::new(self.width, self.height);
+		if max_error > 0.0 {
+			for (heat, pixel) in heat_map.pixels.iter_mut().zip(difference.pixels.iter()) {
+				*heat = heat_color(largest_channel(*pixel) / max_error);
+			}
+		}
+		Ok(heat_map)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn	identical_images_have_no_error()
+	{
+		let mut canvas = Canvas::new(3, 2);
+		canvas.write_pixel(1, 1, Color::new(0.3, 0.6, 0.9));
+
+		let comparison = canvas.compare(&canvas.clone()).unwrap();
+
+		assert_eq!(comparison.max_error, 0.0);
+		assert_eq!(comparison.mean_error, 0.0);
+		assert!(comparison.psnr.is_infinite());
+		assert_eq!(canvas.heat_map(&canvas).unwrap(), Canvas::new(3, 2));
+	}
+
+	#[test]
+	fn	comparing_different_images()
+	{
+		let a = Canvas::new(2, 1);
+		let mut b = Canvas::new(2, 1);
+		b.write_pixel(0, 0, Color::new(0.6, 0.0, 0.0));
+		b.write_pixel(1, 0, Color::new(0.0, 0.0, -0.3));
+
+		let difference = a.difference(&b).unwrap();
+		assert_eq!(difference.color_at(1, 0), Color::new(0.0, 0.0, 0.3));
+
+		let comparison = a.compare(&b).unwrap();
+		assert_eq!(comparison.max_error, 0.6);
+		assert!((comparison.mean_error - 0.15).abs() < 1e-9);
+		// mse = (0.36 + 0.09) / 6 = 0.075
+		assert!((comparison.psnr - 11.249).abs() < 0.001);
+
+		let heat_map = a.heat_map(&b).unwrap();
+		assert_eq!(heat_map.color_at(0, 0), Color::new(1.0, 1.0, 1.0));
+		assert_eq!(heat_map.color_at(1, 0), Color::new(1.0, 0.5, 0.0));
+	}
+
+	#[test]
+	fn	comparing_images_of_different_sizes_fails()
+	{
+		let error = Canvas::new(2, 1).compare(&Canvas::new(1, 2)).unwrap_err();
+
+		assert_eq!(error.to_string(), "Cannot compare a 2x1 image with a 1x2 one");
+	}
+}
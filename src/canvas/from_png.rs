@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+
+use crate::color::Color;
+use super::Canvas;
+
+pub trait	FromPNG {
+	fn	from_png(data: &[u8]) -> Result<Self>
+	where
+		Self: std::marker::Sized;
+}
+
+impl FromPNG for Canvas
+{
+	fn	from_png(data: &[u8]) -> Result<Self>
+	{
+		let mut decoder = png::Decoder::new(data);
+		// palettes and bit depths below 8 are expanded to plain 8 bit channels
+		decoder.set_transformations(png::Transformations::EXPAND);
+		let mut reader = decoder.read_info()?;
+		let mut buffer = vec![0; reader.output_buffer_size()];
+		let info = reader.next_frame(&mut buffer)?;
+		let bytes = &buffer[..info.buffer_size()];
+
+		let channels = match info.color_type {
+			png::ColorType::Grayscale => 1,
+			png::ColorType::GrayscaleAlpha => 2,
+			png::ColorType::Rgb => 3,
+			png::ColorType::Rgba => 4,
+			png::ColorType::Indexed => {
+				return Err(anyhow!("Indexed PNG was not expanded to RGB while decoding"))
+			}
+		};
+		let (bytes_per_sample, max_value) = match info.bit_depth {
+			png::BitDepth::Sixteen => (2, 65535.0),
+			_ => (1, 255.0),
+		};
+
+		let width = info.width as usize;
+		let height = info.height as usize;
+		let mut canvas = Canvas::new(width, height);
+		let sample = |index: usize| -> f64 {
+			let offset = index * bytes_per_sample;
+			if bytes_per_sample == 2 {
+				u16::from_be_bytes([bytes[offset], bytes[offset + 1]]) as f64 / max_value
+			} else {
+				bytes[offset] as f64 / max_value
+			}
+		};
+		for y in 0..height {
+			for x in 0..width {
+				let first = (y * width + x) * channels;
+				let color = if channels < 3 {
+					let grey = sample(first);
+					Color::new(grey, grey, grey)
+				} else {
+					Color::new(sample(first), sample(first + 1), sample(first + 2))
+				};
+				canvas.write_pixel(x, y, color);
+			}
+		}
+		Ok(canvas)
+	}
+}
@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Result};
+
+use crate::color::Color;
+use super::Canvas;
+
+// Reads both the ASCII (P3) and the binary (P6) flavours of PPM.
+pub trait	FromPPM {
+	fn	from_ppm(data: &[u8]) -> Result<Self>
+	where
+		Self: std::marker::Sized;
+}
+
+struct	HeaderReader<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> HeaderReader<'a>
+{
+	fn	skip_whitespace_and_comments(&mut self)
+	{
+		while self.position < self.data.len() {
+			match self.data[self.position] {
+				b'#' => {
+					while self.position < self.data.len() && self.data[self.position] != b'\n' {
+						self.position += 1;
+					}
+				}
+				byte if byte.is_ascii_whitespace() => self.position += 1,
+				_ => break,
+			}
+		}
+	}
+
+	fn	next_token(&mut self) -> Option<&'a [u8]>
+	{
+		self.skip_whitespace_and_comments();
+		let start = self.position;
+		while self.position < self.data.len() && !self.data[self.position].is_ascii_whitespace() {
+			self.position += 1;
+		}
+		if start == self.position {
+			None
+		} else {
+			Some(&self.data[start..self.position])
+		}
+	}
+
+	fn	next_number(&mut self, name: &str) -> Result<usize>
+	{
+		let token = self
+			.next_token()
+			.ok_or_else(|| anyhow!("PPM header ends before the {}", name))?;
+		parse_number(token, name)
+	}
+}
+
+fn	parse_number(token: &[u8], name: &str) -> Result<usize>
+{
+	std::str::from_utf8(token)
+		.ok()
+		.and_then(|text| text.parse::<usize>().ok())
+		.ok_or_else(|| anyhow!("Invalid {} in PPM: '{}'", name, String::from_utf8_lossy(token)))
+}
+
+impl FromPPM for Canvas
+{
+	fn	from_ppm(data: &[u8]) -> Result<Self>
+	{
+		let mut reader = HeaderReader { data, position: 0 };
+		let binary = match reader.next_token() {
+			Some(b"P3") => false,
+			Some(b"P6") => true,
+			Some(magic) => {
+				return Err(anyhow!(
+					"Unsupported PPM magic number '{}', expected P3 or P6",
+					String::from_utf8_lossy(magic)
+				))
+			}
+			None => return Err(anyhow!("Empty PPM file")),
+		};
+		let width = reader.next_number("width")?;
+		let height = reader.next_number("height")?;
+		let max_value = reader.next_number("maximum color value")?;
+		if width == 0 || height == 0 {
+			return Err(anyhow!("Invalid PPM size {}x{}", width, height));
+		}
+		if max_value == 0 || max_value > 65535 {
+			return Err(anyhow!(
+				"PPM maximum color value must be between 1 and 65535, got {}",
+				max_value
+			));
+		}
+
+		let sample_count = width
+			.checked_mul(height)
+			.and_then(|pixels| pixels.checked_mul(3))
+			.ok_or_else(|| anyhow!("PPM size {}x{} is too large", width, height))?;
+		let samples: Vec<usize> = if binary {
+			// exactly one whitespace byte separates the header from the raster
+			let start = reader.position + 1;
+			let bytes_per_sample = if max_value > 255 { 2 } else { 1 };
+			let raster = data.get(start..).unwrap_or(&[]);
+			let byte_count = sample_count
+				.checked_mul(bytes_per_sample)
+				.ok_or_else(|| anyhow!("PPM size {}x{} is too large", width, height))?;
+			if raster.len() < byte_count {
+				return Err(anyhow!(
+					"PPM pixel data is truncated: expected {} bytes, found {}",
+					byte_count,
+					raster.len()
+				));
+			}
+			raster
+				.chunks(bytes_per_sample)
+				.take(sample_count)
+				.map(|bytes| bytes.iter().fold(0, |value, byte| (value << 8) | *byte as usize))
+				.collect()
+		} else {
+			// grows with the samples actually read, the header may lie
+			let mut samples = Vec::new();
+			for index in 0..sample_count {
+				let token = reader.next_token().ok_or_else(|| anyhow!(
+					"PPM pixel data is truncated: expected {} samples, found {}",
+					sample_count,
+					index
+				))?;
+				samples.push(parse_number(token, "sample")?);
+			}
+			samples
+		};
+
+		if let Some(sample) = samples.iter().find(|sample| **sample > max_value) {
+			return Err(anyhow!(
+				"PPM sample {} exceeds the maximum color value {}",
+				sample,
+				max_value
+			));
+		}
+		let mut canvas = Canvas::new(width, height);
+		let scale = max_value as f64;
+		for (index, pixel) in samples.chunks(3).enumerate() {
+			let color = Color::new(
+				pixel[0] as f64 / scale,
+				pixel[1] as f64 / scale,
+				pixel[2] as f64 / scale,
+			);
+			canvas.write_pixel(index % width, index / width, color);
+		}
+		Ok(canvas)
+	}
+}
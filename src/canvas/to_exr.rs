@@ -0,0 +1,130 @@
+use super::Canvas;
+
+// OpenEXR, uncompressed scanlines with 32 bit float channels
+pub trait	ToEXR {
+	fn	to_exr(&self) -> Vec<u8>;
+}
+
+const MAGIC_NUMBER: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const VERSION: [u8; 4] = [2, 0, 0, 0];
+const PIXEL_TYPE_FLOAT: i32 = 2;
+
+fn	write_attribute(header: &mut Vec<u8>, name: &str, attribute_type: &str, value: &[u8])
+{
+	header.extend(name.as_bytes());
+	header.push(0);
+	header.extend(attribute_type.as_bytes());
+	header.push(0);
+	header.extend((value.len() as i32).to_le_bytes());
+	header.extend(value);
+}
+
+fn	write_box(value: &mut Vec<u8>, width: usize, height: usize)
+{
+	value.extend(0i32.to_le_bytes());
+	value.extend(0i32.to_le_bytes());
+	value.extend((width as i32 - 1).to_le_bytes());
+	value.extend((height as i32 - 1).to_le_bytes());
+}
+
+impl ToEXR for Canvas
+{
+	fn	to_exr(&self) -> Vec<u8>
+	{
+		let mut data: Vec<u8> = Vec::new();
+		data.extend(MAGIC_NUMBER);
+		data.extend(VERSION);
+
+		// channels have to be listed, and stored, in alphabetical order
+		let mut channels = Vec::new();
+		for name in ["B", "G", "R"] {
+			channels.extend(name.as_bytes());
+			channels.push(0);
+			channels.extend(PIXEL_TYPE_FLOAT.to_le_bytes());
+			channels.extend([0, 0, 0, 0]); // pLinear + reserved
+			channels.extend(1i32.to_le_bytes()); // x sampling
+			channels.extend(1i32.to_le_bytes()); // y sampling
+		}
+		channels.push(0);
+		write_attribute(&mut data, "channels", "chlist", &channels);
+		write_attribute(&mut data, "compression", "compression", &[0]);
+		let mut window = Vec::new();
+		write_box(&mut window, self.width, self.height);
+		write_attribute(&mut data, "dataWindow", "box2i", &window);
+		write_attribute(&mut data, "displayWindow", "box2i", &window);
+		write_attribute(&mut data, "lineOrder", "lineOrder", &[0]);
+		write_attribute(&mut data, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+		write_attribute(&mut data, "screenWindowCenter", "v2f", &[0; 8]);
+		write_attribute(&mut data, "screenWindowWidth", "float", &1f32.to_le_bytes());
+		data.push(0);
+
+		// one scanline per block: y, byte count, then every channel of the line
+		let line_size = self.width * 3 * 4;
+		let block_size = 4 + 4 + line_size;
+		let table_end = data.len() + self.height * 8;
+		for y in 0..self.height {
+			data.extend(((table_end + y * block_size) as u64).to_le_bytes());
+		}
+		for y in 0..self.height {
+			data.extend((y as i32).to_le_bytes());
+			data.extend((line_size as i32).to_le_bytes());
+			for x in 0..self.width {
+				data.extend((self.color_at(x, y).blue as f32).to_le_bytes());
+			}
+			for x in 0..self.width {
+				data.extend((self.color_at(x, y).green as f32).to_le_bytes());
+			}
+			for x in 0..self.width {
+				data.extend((self.color_at(x, y).red as f32).to_le_bytes());
+			}
+		}
+		data
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::color::Color;
+
+	fn	read_f32(data: &[u8], offset: usize) -> f32
+	{
+		f32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+	}
+
+	fn	find(data: &[u8], needle: &[u8]) -> Option<usize>
+	{
+		data.windows(needle.len()).position(|window| window == needle)
+	}
+
+	#[test]
+	fn	exr_starts_with_magic_number_and_version()
+	{
+		let exr = Canvas::new(2, 2).to_exr();
+
+		assert_eq!(&exr[..4], &[0x76, 0x2f, 0x31, 0x01]);
+		assert_eq!(&exr[4..8], &[2, 0, 0, 0]);
+		assert!(find(&exr, b"channels\0chlist\0").is_some());
+		assert!(find(&exr, b"compression\0compression\0\x01\0\0\0\0").is_some());
+		assert!(find(&exr, b"dataWindow\0box2i\0").is_some());
+	}
+
+	#[test]
+	fn	exr_keeps_float_pixels()
+	{
+		let mut canvas = Canvas::new(3, 2);
+		canvas.write_pixel(2, 1, Color::new(7.5, -0.25, 0.125));
+		let exr = canvas.to_exr();
+
+		let header_end = find(&exr, b"screenWindowWidth\0float\0").unwrap() + 24 + 4 + 4 + 1;
+		let second_line = u64::from_le_bytes(exr[header_end + 8..header_end + 16].try_into().unwrap()) as usize;
+
+		assert_eq!(i32::from_le_bytes(exr[second_line..second_line + 4].try_into().unwrap()), 1);
+		assert_eq!(i32::from_le_bytes(exr[second_line + 4..second_line + 8].try_into().unwrap()), 36);
+		let pixels = second_line + 8;
+		assert_eq!(read_f32(&exr, pixels + 2 * 4), 0.125);
+		assert_eq!(read_f32(&exr, pixels + (3 + 2) * 4), -0.25);
+		assert_eq!(read_f32(&exr, pixels + (6 + 2) * 4), 7.5);
+		assert_eq!(exr.len(), second_line + 8 + 36);
+	}
+}
@@ -0,0 +1,194 @@
+use super::Canvas;
+use crate::color::Color;
+
+// Radiance RGBE (.hdr): one shared exponent per pixel keeps the full range
+// of the rendered colors instead of clamping them to [0, 1].
+pub trait	ToHDR {
+	fn	to_hdr(&self) -> Vec<u8>;
+}
+
+// runs shorter than this are cheaper to store as literal bytes
+const MIN_RUN_LENGTH: usize = 4;
+
+fn	color_to_rgbe(color: Color) -> [u8; 4]
+{
+	let red = color.red.max(0.0);
+	let green = color.green.max(0.0);
+	let blue = color.blue.max(0.0);
+	let brightest = red.max(green).max(blue);
+	if brightest < 1e-32 {
+		return [0, 0, 0, 0];
+	}
+	// brightest = mantissa * 2^exponent with mantissa in [0.5, 1)
+	let mut exponent = brightest.log2().floor() as i32 + 1;
+	let mut mantissa = brightest / 2f64.powi(exponent);
+	if mantissa >= 1.0 {
+		exponent += 1;
+		mantissa /= 2.0;
+	}
+	let scale = mantissa * 256.0 / brightest;
+	[
+		(red * scale) as u8,
+		(green * scale) as u8,
+		(blue * scale) as u8,
+		(exponent + 128).clamp(0, 255) as u8,
+	]
+}
+
+fn	write_run_length_encoded(data: &[u8], output: &mut Vec<u8>)
+{
+	let mut current = 0;
+	while current < data.len() {
+		// look for the next run that is long enough to be worth it
+		let mut run_start = current;
+		let mut run_length = 0;
+		let mut previous_run_length = 0;
+		while run_length < MIN_RUN_LENGTH && run_start < data.len() {
+			run_start += run_length;
+			previous_run_length = run_length;
+			run_length = 1;
+			while run_start + run_length < data.len()
+				&& run_length < 127
+				&& data[run_start] == data[run_start + run_length]
+			{
+				run_length += 1;
+			}
+		}
+		// a short run right before the long one is still written as a run
+		if previous_run_length > 1 && previous_run_length == run_start - current {
+			output.push(128 + previous_run_length as u8);
+			output.push(data[current]);
+			current = run_start;
+		}
+		while current < run_start {
+			let literal_length = (run_start - current).min(128);
+			output.push(literal_length as u8);
+			output.extend(&data[current..current + literal_length]);
+			current += literal_length;
+		}
+		if run_length >= MIN_RUN_LENGTH {
+			output.push(128 + run_length as u8);
+			output.push(data[run_start]);
+			current += run_length;
+		}
+	}
+}
+
+impl ToHDR for Canvas
+{
+	fn	to_hdr(&self) -> Vec<u8>
+	{
+		let mut data: Vec<u8> = Vec::new();
+		data.extend(String::from("#?RADIANCE\n").into_bytes());
+		data.extend(String::from("FORMAT=32-bit_rle_rgbe\n\n").into_bytes());
+		data.extend(format!("-Y {} +X {}\n", self.height, self.width).into_bytes());
+
+		// run length encoding is only defined for these widths
+		let encode = (8..32768).contains(&self.width);
+		for y in 0..self.height {
+			let row: Vec<[u8; 4]> = (0..self.width)
+				.map(|x| color_to_rgbe(self.color_at(x, y)))
+				.collect();
+			if !encode {
+				row.iter().for_each(|rgbe| data.extend(rgbe));
+				continue;
+			}
+			data.extend([2, 2, (self.width >> 8) as u8, (self.width & 255) as u8]);
+			for component in 0..4 {
+				let channel: Vec<u8> = row.iter().map(|rgbe| rgbe[component]).collect();
+				write_run_length_encoded(&channel, &mut data);
+			}
+		}
+		data
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn	rgbe_to_color(rgbe: [u8; 4]) -> Color
+	{
+		if rgbe[3] == 0 {
+			return Color::black();
+		}
+		let scale = 2f64.powi(rgbe[3] as i32 - 128 - 8);
+		Color::new(rgbe[0] as f64 * scale, rgbe[1] as f64 * scale, rgbe[2] as f64 * scale)
+	}
+
+	fn	decode_run_length_encoded(data: &[u8], length: usize) -> (Vec<u8>, usize)
+	{
+		let mut decoded = Vec::new();
+		let mut index = 0;
+		while decoded.len() < length {
+			let count = data[index] as usize;
+			if count > 128 {
+				decoded.extend(std::iter::repeat_n(data[index + 1], count - 128));
+				index += 2;
+			} else {
+				decoded.extend(&data[index + 1..index + 1 + count]);
+				index += 1 + count;
+			}
+		}
+		(decoded, index)
+	}
+
+	#[test]
+	fn	rgbe_keeps_values_above_one()
+	{
+		let color = Color::new(12.5, 0.75, 0.0);
+		let decoded = rgbe_to_color(color_to_rgbe(color));
+
+		assert!((decoded.red - 12.5).abs() < 0.1);
+		assert!((decoded.green - 0.75).abs() < 0.1);
+		assert_eq!(decoded.blue, 0.0);
+		assert_eq!(color_to_rgbe(Color::black()), [0, 0, 0, 0]);
+		assert_eq!(color_to_rgbe(Color::new(1.0, 0.5, -3.0)), [128, 64, 0, 129]);
+	}
+
+	#[test]
+	fn	hdr_header()
+	{
+		let canvas = Canvas::new(5, 3);
+		let hdr = canvas.to_hdr();
+		let expected = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 3 +X 5\n";
+
+		assert_eq!(&hdr[..expected.len()], expected.as_bytes());
+		// too narrow for run length encoding, 4 bytes per pixel
+		assert_eq!(hdr.len(), expected.len() + 5 * 3 * 4);
+	}
+
+	#[test]
+	fn	hdr_scanlines_are_run_length_encoded()
+	{
+		let mut canvas = Canvas::new(40, 2);
+		for x in 0..40 {
+			canvas.write_pixel(x, 0, Color::new(4.0, 0.5, 0.25));
+			canvas.write_pixel(x, 1, Color::new(x as f64 / 10.0, 1.0, 20.0));
+		}
+		let hdr = canvas.to_hdr();
+		let header_length = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 40\n".len();
+		let mut index = header_length;
+
+		for y in 0..2 {
+			assert_eq!(&hdr[index..index + 4], &[2, 2, 0, 40]);
+			index += 4;
+			let mut channels = Vec::new();
+			for _ in 0..4 {
+				let (channel, used) = decode_run_length_encoded(&hdr[index..], 40);
+				channels.push(channel);
+				index += used;
+			}
+			let decoded: Vec<[u8; 4]> = (0..40)
+				.map(|x| [channels[0][x], channels[1][x], channels[2][x], channels[3][x]])
+				.collect();
+			let expected: Vec<[u8; 4]> = (0..40)
+				.map(|x| color_to_rgbe(canvas.color_at(x, y)))
+				.collect();
+			assert_eq!(decoded, expected);
+		}
+		assert_eq!(index, hdr.len());
+		// the constant first row compresses well
+		assert!(hdr.len() < header_length + 2 * 40 * 4);
+	}
+}
@@ -13,6 +13,8 @@ pub trait ToPPM {
 		return header;
 	}
 	fn	to_ppm(&self) -> Vec<u8>;
+	// P6: same header but raw bytes instead of text, about 4 times smaller
+	fn	to_binary_ppm(&self) -> Vec<u8>;
 }
 
 //Oh my god! It's even worse than before...
@@ -77,6 +79,18 @@ where
 		// ppm.extend(pixel_data);
 		// return ppm;
 	}
+	fn	to_binary_ppm(&self) -> Vec<u8>
+	{
+		let mut header = self.create_ppm_header();
+		header[1] = b'6';
+		let pixel_data = self
+		.to_rgba32()
+		.into_iter()
+		.enumerate()
+		.filter(|(i, _)| (i + 1) % 4 != 0)
+		.map(|(_, byte)| byte);
+		header.into_iter().chain(pixel_data).collect()
+	}
 }
 
 //This is quite litterally the worst code i wrote in more than a year
@@ -0,0 +1,284 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::F;
+
+// Rec. 709 weights, how bright each primary looks to the eye
+const LUMINANCE: [F; 3] = [0.2126, 0.7152, 0.0722];
+
+const SEPIA: [[F; 3]; 3] = [
+    [0.393, 0.769, 0.189],
+    [0.349, 0.686, 0.168],
+    [0.272, 0.534, 0.131],
+];
+
+// Post-processing applied to display colors, once the render has been tone
+// mapped. Filters are applied one after the other, in the order they are given.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Grayscale,
+    Sepia,
+    Invert,
+    // number of levels kept per channel
+    Posterize(usize),
+    // each row gives one output channel as a mix of the input red, green and blue
+    ColorMatrix([[F; 3]; 3]),
+    // brightness is added, contrast scales around mid gray, saturation 0 is gray
+    Adjust {
+        brightness: F,
+        contrast: F,
+        saturation: F,
+    },
+}
+
+fn luminance(color: Color) -> F {
+    color.red * LUMINANCE[0] + color.green * LUMINANCE[1] + color.blue * LUMINANCE[2]
+}
+
+fn multiply(matrix: &[[F; 3]; 3], color: Color) -> Color {
+    let row = |row: &[F; 3]| row[0] * color.red + row[1] * color.green + row[2] * color.blue;
+    Color::new(row(&matrix[0]), row(&matrix[1]), row(&matrix[2]))
+}
+
+impl Filter {
+    pub fn color_at(&self, color: Color) -> Color {
+        let filtered = match self {
+            Filter::Grayscale => {
+                let gray = luminance(color);
+                Color::new(gray, gray, gray)
+            }
+            Filter::Sepia => multiply(&SEPIA, color),
+            Filter::Invert => Color::new(1.0, 1.0, 1.0) - color.clamp(0.0, 1.0),
+            Filter::Posterize(levels) => {
+                let steps = (levels.max(&2) - 1) as F;
+                let posterize = |value: F| (value.clamp(0.0, 1.0) * steps).round() / steps;
+                Color::new(posterize(color.red), posterize(color.green), posterize(color.blue))
+            }
+            Filter::ColorMatrix(matrix) => multiply(matrix, color),
+            Filter::Adjust {
+                brightness,
+                contrast,
+                saturation,
+            } => {
+                let adjust = |value: F| (value + brightness - 0.5) * contrast + 0.5;
+                let adjusted = Color::new(adjust(color.red), adjust(color.green), adjust(color.blue));
+                let gray = luminance(adjusted);
+                Color::new(gray, gray, gray) * (1.0 - saturation) + adjusted * *saturation
+            }
+        };
+        filtered.clamp(0.0, 1.0)
+    }
+
+    pub fn apply(&self, canvas: &Canvas) -> Canvas {
+        apply_filters(std::slice::from_ref(self), canvas)
+    }
+}
+
+pub fn apply_filters(filters: &[Filter], canvas: &Canvas) -> Canvas {
+    let mut filtered = canvas.clone();
+    for y in 0..canvas.height {
+        for x in 0..canvas.width {
+            let color = filters
+                .iter()
+                .fold(canvas.color_at(x, y), |color, filter| filter.color_at(color));
+            filtered.write_pixel(x, y, color);
+        }
+    }
+    filtered
+}
+
+// Parses the short form used on the command line: a name, optionally followed
+// by a colon and comma separated numbers, e.g. `posterize:4` or `adjust:0,1.2,0.8`.
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(description: &str) -> Result<Self> {
+        let (name, arguments) = match description.split_once(':') {
+            Some((name, arguments)) => (name, arguments),
+            None => (description, ""),
+        };
+        let numbers = arguments
+            .split(',')
+            .filter(|argument| !argument.is_empty())
+            .map(|argument| {
+                argument
+                    .trim()
+                    .parse::<F>()
+                    .map_err(|_| anyhow!("Invalid number '{}' in filter '{}'", argument, description))
+            })
+            .collect::<Result<Vec<F>>>()?;
+        let expect = |count: usize| -> Result<()> {
+            if numbers.len() == count {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "Filter '{}' expects {} numbers but got {}",
+                    name,
+                    count,
+                    numbers.len()
+                ))
+            }
+        };
+        match name {
+            "grayscale" => expect(0).map(|_| Filter::Grayscale),
+            "sepia" => expect(0).map(|_| Filter::Sepia),
+            "invert" => expect(0).map(|_| Filter::Invert),
+            "posterize" => expect(1).map(|_| Filter::Posterize(numbers[0].max(2.0) as usize)),
+            "color_matrix" => expect(9).map(|_| {
+                Filter::ColorMatrix([
+                    [numbers[0], numbers[1], numbers[2]],
+                    [numbers[3], numbers[4], numbers[5]],
+                    [numbers[6], numbers[7], numbers[8]],
+                ])
+            }),
+            "adjust" => expect(3).map(|_| Filter::Adjust {
+                brightness: numbers[0],
+                contrast: numbers[1],
+                saturation: numbers[2],
+            }),
+            _ => Err(anyhow!("Unknown filter '{}'", name)),
+        }
+    }
+}
+
+impl FuzzyEq<Filter> for Filter {
+    fn fuzzy_eq(&self, other: Filter) -> bool {
+        match (self, other) {
+            (Filter::Posterize(levels), Filter::Posterize(other_levels)) => *levels == other_levels,
+            (Filter::ColorMatrix(matrix), Filter::ColorMatrix(other_matrix)) => matrix
+                .iter()
+                .flatten()
+                .zip(other_matrix.iter().flatten())
+                .all(|(a, b)| a.fuzzy_eq(*b)),
+            (
+                Filter::Adjust {
+                    brightness,
+                    contrast,
+                    saturation,
+                },
+                Filter::Adjust {
+                    brightness: other_brightness,
+                    contrast: other_contrast,
+                    saturation: other_saturation,
+                },
+            ) => {
+                brightness.fuzzy_eq(other_brightness)
+                    && contrast.fuzzy_eq(other_contrast)
+                    && saturation.fuzzy_eq(other_saturation)
+            }
+            _ => *self == other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_fuzzy_eq;
+
+    #[test]
+    fn grayscale_keeps_luminance() {
+        let color = Filter::Grayscale.color_at(Color::new(1.0, 0.5, 0.0));
+
+        assert_fuzzy_eq!(color, Color::new(0.5702, 0.5702, 0.5702));
+    }
+
+    #[test]
+    fn sepia_tints_gray_brown() {
+        let color = Filter::Sepia.color_at(Color::new(0.5, 0.5, 0.5));
+
+        assert_fuzzy_eq!(color, Color::new(0.6755, 0.6015, 0.4685));
+        assert!(color.red > color.green && color.green > color.blue);
+    }
+
+    #[test]
+    fn invert_and_posterize() {
+        assert_fuzzy_eq!(
+            Filter::Invert.color_at(Color::new(0.25, 1.5, 0.0)),
+            Color::new(0.75, 0.0, 1.0)
+        );
+        assert_fuzzy_eq!(
+            Filter::Posterize(3).color_at(Color::new(0.2, 0.3, 0.8)),
+            Color::new(0.0, 0.5, 1.0)
+        );
+    }
+
+    #[test]
+    fn color_matrix_swaps_channels() {
+        let swap = Filter::ColorMatrix([[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]);
+
+        assert_fuzzy_eq!(swap.color_at(Color::new(0.1, 0.2, 0.3)), Color::new(0.3, 0.2, 0.1));
+    }
+
+    #[test]
+    fn brightness_contrast_saturation() {
+        let identity = Filter::Adjust {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        };
+        let color = Color::new(0.2, 0.4, 0.6);
+        assert_fuzzy_eq!(identity.color_at(color), color);
+
+        let brighter = Filter::Adjust {
+            brightness: 0.1,
+            contrast: 2.0,
+            saturation: 1.0,
+        };
+        assert_fuzzy_eq!(brighter.color_at(color), Color::new(0.1, 0.5, 0.9));
+
+        let desaturated = Filter::Adjust {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 0.0,
+        };
+        assert_fuzzy_eq!(desaturated.color_at(color), Filter::Grayscale.color_at(color));
+    }
+
+    #[test]
+    fn filters_are_chained_in_order() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+
+        let posterized_then_gray = apply_filters(&[Filter::Posterize(2), Filter::Grayscale], &canvas);
+        let gray_then_posterized = apply_filters(&[Filter::Grayscale, Filter::Posterize(2)], &canvas);
+
+        assert_fuzzy_eq!(posterized_then_gray.color_at(0, 0), Color::new(0.2126, 0.2126, 0.2126));
+        assert_fuzzy_eq!(gray_then_posterized.color_at(0, 0), Color::black());
+        assert_eq!(Filter::Invert.apply(&canvas).color_at(0, 0), Color::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn parsing_filters() {
+        assert_eq!("sepia".parse::<Filter>().unwrap(), Filter::Sepia);
+        assert_eq!("posterize:4".parse::<Filter>().unwrap(), Filter::Posterize(4));
+        assert_eq!(
+            "adjust:0.1, 1.2,0.8".parse::<Filter>().unwrap(),
+            Filter::Adjust {
+                brightness: 0.1,
+                contrast: 1.2,
+                saturation: 0.8
+            }
+        );
+        assert_eq!(
+            "color_matrix:1,0,0,0,1,0,0,0,1".parse::<Filter>().unwrap(),
+            Filter::ColorMatrix([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+        );
+        assert_eq!(
+            "blur".parse::<Filter>().unwrap_err().to_string(),
+            "Unknown filter 'blur'"
+        );
+        assert_eq!(
+            "posterize".parse::<Filter>().unwrap_err().to_string(),
+            "Filter 'posterize' expects 1 numbers but got 0"
+        );
+        assert_eq!(
+            "adjust:a,1,1".parse::<Filter>().unwrap_err().to_string(),
+            "Invalid number 'a' in filter 'adjust:a,1,1'"
+        );
+    }
+}
@@ -113,7 +113,7 @@ macro_rules! assert_fuzzy_ne {
     ($left:expr, $right:expr $(,)?) => {{
         match (&$left, $right) {
             (left_val, right_val) => {
-                if left_val.fuzzy_eq(right_val) {
+                if left_val.fuzzy_eq(right_val.clone()) {
                     panic!(
                         "asserting fuzzy in-equality. {:?} is fuzzy equal to {:?}",
                         left_val, right_val
@@ -0,0 +1,201 @@
+use crate::ambient_occlusion::AmbientOcclusion;
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::ray::Ray;
+use crate::sampling::Rng;
+use crate::stats::RenderStats;
+use crate::world::World;
+use crate::F;
+
+// How the light seen along camera rays is computed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Integrator {
+    // direct light, ambient term and mirror reflections, see World::color_at
+    #[default]
+    Whitted,
+    PathTracer(PathTracer),
+    // how open the surroundings of the hits are, in shades of grey
+    AmbientOcclusion(AmbientOcclusion),
+}
+
+impl From<AmbientOcclusion> for Integrator {
+    fn from(value: AmbientOcclusion) -> Self {
+        Integrator::AmbientOcclusion(value)
+    }
+}
+
+impl From<PathTracer> for Integrator {
+    fn from(value: PathTracer) -> Self {
+        Integrator::PathTracer(value)
+    }
+}
+
+impl Integrator {
+    pub fn color_at(&self, world: &World, ray: Ray, stats: &mut RenderStats) -> Color {
+        match *self {
+            Integrator::Whitted => world.color_at_with_stats(ray, stats),
+            Integrator::PathTracer(ref path_tracer) => path_tracer.color_at(world, ray, stats),
+            Integrator::AmbientOcclusion(ref ambient_occlusion) => {
+                let xs = world.intersect_with_stats(ray, stats);
+                let openness = match xs.hit() {
+                    Some(hit) => {
+                        let c = hit.get_computed();
+                        world.openness_at(c.over_point, c.normalv, ambient_occlusion, ray.time, stats)
+                    }
+                    None => 1.0,
+                };
+                Color::new(openness, openness, openness)
+            }
+        }
+    }
+}
+
+// Monte Carlo estimate of the global illumination: paths bouncing around the
+// world are averaged, so that light reaches the bodies through the others
+// (colour bleeding, indirect light) instead of through the ambient term.
+// Noise goes down as the samples go up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathTracer {
+    // paths traced per camera ray
+    pub samples: usize,
+    // bounces before russian roulette may end a path
+    pub min_bounces: usize,
+    // bounces after which a path ends whatever its contribution
+    pub max_bounces: usize,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer {
+            samples: 16,
+            min_bounces: 3,
+            max_bounces: 16,
+        }
+    }
+}
+
+impl PathTracer {
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_bounces(mut self, min_bounces: usize, max_bounces: usize) -> Self {
+        self.min_bounces = min_bounces;
+        self.max_bounces = max_bounces.max(1);
+        self
+    }
+
+    pub fn color_at(&self, world: &World, ray: Ray, stats: &mut RenderStats) -> Color {
+        let mut rng = Rng::from_ray(ray);
+        let sum = (0..self.samples).fold(Color::black(), |sum, _| {
+            sum + world.path_trace_with_stats(ray, self, &mut rng, stats)
+        });
+        sum * (1.0 / self.samples as F)
+    }
+}
+
+impl FuzzyEq<Integrator> for Integrator {
+    fn fuzzy_eq(&self, other: Integrator) -> bool {
+        self == &other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::Background;
+    use crate::body::Body;
+    use crate::lights::PointLight;
+    use crate::material::{Lambert, Material, Phong};
+    use crate::matrix::Matrix;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+    use crate::tuple::Tuple;
+
+    #[test]
+    fn path_tracing_is_repeatable() {
+        let light = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::white());
+        let w = World::new(vec![Body::from(Sphere::default()), Body::from(Plane::default())], vec![light]);
+        let r = Ray::new(Tuple::point(0.0, 0.5, -5.0), Tuple::vector(0.0, -0.2, 1.0).normalize());
+        let integrator = Integrator::from(PathTracer::default().with_samples(4));
+
+        let color = integrator.color_at(&w, r, &mut RenderStats::default());
+
+        assert_eq!(integrator.color_at(&w, r, &mut RenderStats::default()), color);
+    }
+
+    #[test]
+    fn path_tracing_bleeds_colour_onto_neighbours() {
+        // a white floor lit from straight above, next to a red wall: the floor
+        // turns red only when the light bounces off the wall
+        let light = PointLight::new(Tuple::point(0.0, 10.0, 0.0), Color::white());
+        let floor = Plane::default().with_material(Material::from(Lambert::default().with_ambient(0.0)));
+        let wall = Plane::default()
+            .with_transform(Matrix::translation(1.0, 0.0, 0.0) * Matrix::rotation_z(std::f64::consts::PI / 2.0))
+            .with_material(Material::from(Lambert::default().with_color(Color::new(1.0, 0.0, 0.0)).with_ambient(0.0)));
+        let w = World::new(vec![Body::from(floor), Body::from(wall)], vec![light]);
+        let r = Ray::new(Tuple::point(0.5, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+
+        let whitted = Integrator::Whitted.color_at(&w, r, &mut RenderStats::default());
+        let mut stats = RenderStats::default();
+        let path_traced = Integrator::from(PathTracer::default().with_samples(64)).color_at(&w, r, &mut stats);
+
+        assert_eq!(whitted.red, whitted.green);
+        assert!(path_traced.red > path_traced.green + 0.1);
+        assert!(path_traced.green >= whitted.green - 0.01);
+        assert_eq!(stats.primary_rays, 0);
+        assert_eq!(stats.reflection_rays, 0);
+        assert!(stats.bounce_rays >= 64);
+        assert!(stats.max_depth >= 1);
+    }
+
+    #[test]
+    fn path_tracing_keeps_the_diffuse_light_of_reflective_surfaces() {
+        // a half mirror floor under a white sky: half of the sky is mirrored
+        // and the whole of it is spread by the diffuse part
+        let floor = Plane::default().with_material(Material::from(Phong::default().with_diffuse(0.4).with_reflective(0.5)));
+        let w = World::new(vec![Body::from(floor)], vec![]).with_background(Background::Solid(Color::white()));
+        let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+
+        let color = Integrator::from(PathTracer::default().with_samples(256)).color_at(&w, r, &mut RenderStats::default());
+
+        assert!((color.red - (0.5 + 0.4)).abs() < 0.03);
+    }
+
+    #[test]
+    fn path_tracing_keeps_emissive_planes_when_sampling_emitters() {
+        // planes are never sampled as lights, their emission must still be
+        // found by the diffuse bounces
+        let floor = Plane::default().with_material(Material::from(Lambert::default().with_ambient(0.0)));
+        let ceiling = Plane::default()
+            .with_transform(Matrix::translation(0.0, 2.0, 0.0))
+            .with_material(Material::from(Lambert::default().with_color(Color::black()).with_emission(Color::white())));
+        let w = World::new(vec![Body::from(floor), Body::from(ceiling)], vec![]);
+        let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let integrator = Integrator::from(PathTracer::default().with_samples(16));
+
+        let unsampled = integrator.color_at(&w, r, &mut RenderStats::default());
+        let sampled = integrator.color_at(&w.with_emitter_samples(16), r, &mut RenderStats::default());
+
+        assert!(unsampled.red > 0.5);
+        assert_eq!(sampled, unsampled);
+    }
+
+    #[test]
+    fn ambient_occlusion_renders_shades_of_grey() {
+        let ball = Sphere::default().with_transform(Matrix::translation(0.0, 1.0, 0.0));
+        let w = World::new(vec![Body::from(ball), Body::from(Plane::default())], vec![]);
+        let integrator = Integrator::from(AmbientOcclusion::default().with_samples(32));
+        let color_at = |origin: Tuple, direction: Tuple| integrator.color_at(&w, Ray::new(origin, direction), &mut RenderStats::default());
+
+        let sky = color_at(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        let open_floor = color_at(Tuple::point(10.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let under_the_ball = color_at(Tuple::point(1.2, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+
+        assert_eq!(sky, Color::white());
+        assert_eq!(open_floor, Color::white());
+        assert!(under_the_ball.red < 0.9);
+        assert_eq!(under_the_ball.red, under_the_ball.blue);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::body::*;
+use crate::material::Perturbed;
 use crate::ray::Ray;
 use core::ops::Index;
 
@@ -6,7 +7,7 @@ use crate::EPSILON;
 use crate::computed_intersection::ComputedIntersection;
 use crate::F;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Intersection {
 	pub t: F,
 	pub ray: Ray,
@@ -25,8 +26,10 @@ impl Intersection {
 		if inside{
 			normalv = -normalv;
 		}
-    let reflectv = self.ray.direction.reflect(normalv);
+    // the point is offset along the geometric normal, bumps only affect shading
     let over_point = position + (normalv * EPSILON);
+    normalv = self.body.material().perturb_normal(&self.body, position, normalv);
+    let reflectv = self.ray.direction.reflect(normalv);
 		ComputedIntersection::new(self, position, over_point, normalv, eyev, inside, reflectv)
 	}
 }
@@ -53,7 +56,7 @@ impl Intersections {
 	pub fn hit(&self) -> Option<Intersection> {
 		for intersection in self.data.iter(){
 			if intersection.t > 0.0 {
-				return  Some(*intersection);
+				return  Some(intersection.clone());
 			}
 		}
 		None
@@ -100,10 +103,10 @@ use crate::world::World;
     let s = Sphere::default();
 
     let r = Ray::new(Tuple::point(1.0, 1.0, 1.0), Tuple::vector(0.0, 0.0, 1.0));
-    let i1 = Intersection::new(1.0, r, Body::from(s));
+    let i1 = Intersection::new(1.0, r, Body::from(s.clone()));
     let i2 = Intersection::new(2.0, r, Body::from(s));
 
-    let xs = Intersections::new(vec![i2, i1]);
+    let xs = Intersections::new(vec![i2, i1.clone()]);
 
     assert_eq!(xs.hit(), Some(i1));
   }
@@ -114,10 +117,10 @@ use crate::world::World;
     let s = Sphere::default();
 
     let r = Ray::new(Tuple::point(1.0, 1.0, 1.0), Tuple::vector(0.0, 0.0, 1.0));
-    let i1 = Intersection::new(-1.0, r, Body::from(s));
+    let i1 = Intersection::new(-1.0, r, Body::from(s.clone()));
     let i2 = Intersection::new(1.0, r, Body::from(s));
 
-    let xs = Intersections::new(vec![i2, i1]);
+    let xs = Intersections::new(vec![i2.clone(), i1]);
 
     assert_eq!(xs.hit(), Some(i2));
   }
@@ -128,7 +131,7 @@ use crate::world::World;
     let s = Sphere::default();
 
     let r = Ray::new(Tuple::point(1.0, 1.0, 1.0), Tuple::vector(0.0, 0.0, 1.0));
-    let i1 = Intersection::new(-2.0, r, Body::from(s));
+    let i1 = Intersection::new(-2.0, r, Body::from(s.clone()));
     let i2 = Intersection::new(-1.0, r, Body::from(s));
 
     let xs = Intersections::new(vec![i2, i1]);
@@ -193,6 +196,23 @@ use crate::world::World;
     assert_eq!(c.reflectv, Tuple::vector(0.0, (2.0 as F).sqrt() / 2.0, (2.0 as F).sqrt() / 2.0));
   }
 
+  #[test]
+  fn bumps_perturb_the_normal_and_the_reflection_vector() {
+    let material = Material::from(
+      Phong::default().with_bump(crate::bump::Bump::default().with_strength(1.0).with_scale(3.0)),
+    );
+    let shape = Plane::default().with_material(material);
+    let r = Ray::new(Tuple::point(0.3, 1.0, -1.0),
+      Tuple::vector(0.0, -(2.0 as F).sqrt() / 2.0, (2.0 as F).sqrt() / 2.0));
+    let i = Intersection::new((2.0 as F).sqrt(), r, shape.into());
+
+    let c = i.get_computed();
+
+    assert_ne!(c.normalv, Tuple::vector(0.0, 1.0, 0.0));
+    assert_eq!(c.reflectv, r.direction.reflect(c.normalv));
+    assert_eq!(c.over_point, c.point + Tuple::vector(0.0, EPSILON, 0.0));
+  }
+
   fn create_default_world() -> World {
     let light = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
     let material = Phong {
@@ -213,7 +233,7 @@ use crate::world::World;
     let world = create_default_world();
     let r = Ray::new(Tuple::point(0.0, 0.0, 0.0),
       Tuple::vector(0.0, 0.0, 1.0));
-    let shape = world.bodies[1];
+    let shape = world.bodies[1].clone();
     let i = Intersection::new(1.0, r, shape.into());
     let c = i.get_computed();
     let color = world.reflect_color_at(&i.body.material(), &c, 1);
@@ -229,7 +249,7 @@ use crate::world::World;
     );
     let s1 = Body::from(Sphere::default().with_material(reflective_material));
     let world = World::new(
-      vec![s1],
+      vec![s1.clone()],
       vec![PointLight::new(
         Tuple::point(10.0, 10.0, 10.0),
         Color::white(),
@@ -14,6 +14,25 @@ pub mod computed_intersection;
 pub mod material;
 pub mod pattern;
 pub mod world_loader;
+pub mod texture;
+pub mod background;
+pub mod noise;
+pub mod bump;
+pub mod normal_map;
+pub mod tone_mapping;
+pub mod render;
+pub mod filter;
+pub mod outline;
+pub mod stereo;
+pub mod animation;
+pub mod motion_blur;
+pub mod stats;
+pub mod sampling;
+pub mod integrator;
+pub mod photon_map;
+pub mod media;
+pub mod ambient_occlusion;
+pub mod aov;
 
 #[macro_use]
 mod fuzzy_eq;
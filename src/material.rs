@@ -1,11 +1,13 @@
 use crate::body::Body;
+use crate::bump::Bump;
 use crate::color::*;
 use crate::fuzzy_eq::FuzzyEq;
 use crate::lights::PointLight;
+use crate::normal_map::NormalMap;
 use crate::pattern::Pattern;
 use crate::pattern::Stencil;
 use crate::tuple::*;
-use crate::F;
+use crate::{EPSILON, F};
 
 pub trait Illuminated {
     fn lighting(
@@ -17,15 +19,54 @@ pub trait Illuminated {
         normalv: Tuple,
         in_shadow: bool,
     ) -> Color;
+    // share of the light bounced evenly in all directions, what the lighting
+    // shows of a white light straight above, less ambient and specular
+    fn albedo(&self, body: &Body, position: Tuple) -> Color;
 }
 
 pub trait Reflective {
     fn reflectiveness(&self) -> F;
+    fn fresnel(&self) -> bool {
+        false
+    }
+    // 0 for perfect mirrors, up to 1 for reflections blurred over the whole
+    // hemisphere
+    fn roughness(&self) -> F {
+        0.0
+    }
+    // share of the reflected color seen at an angle, cos_theta being the dot
+    // product of the eye and normal vectors
+    fn reflectance(&self, cos_theta: F) -> F {
+        if self.fresnel() {
+            schlick(self.reflectiveness(), cos_theta)
+        } else {
+            self.reflectiveness()
+        }
+    }
+}
+
+// Schlick approximation of the Fresnel reflectance, r0 being the head-on one
+pub fn schlick(r0: F, cos_theta: F) -> F {
+    r0 + (1.0 - r0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+// Light given off by the surface itself, added whatever the lights and shadows
+pub trait Emissive {
+    fn emission(&self) -> Color;
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+// Small surface details that only change the normal used for shading
+pub trait Perturbed {
+    fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple;
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Material {
     Phong(Phong),
+    Toon(Toon),
+    Lambert(Lambert),
+    BlinnPhong(BlinnPhong),
+    Pbr(Pbr),
 }
 
 impl From<Phong> for Material {
@@ -33,6 +74,30 @@ impl From<Phong> for Material {
         Material::Phong(value)
     }
 }
+
+impl From<Toon> for Material {
+    fn from(value: Toon) -> Self {
+        Material::Toon(value)
+    }
+}
+
+impl From<Lambert> for Material {
+    fn from(value: Lambert) -> Self {
+        Material::Lambert(value)
+    }
+}
+
+impl From<BlinnPhong> for Material {
+    fn from(value: BlinnPhong) -> Self {
+        Material::BlinnPhong(value)
+    }
+}
+
+impl From<Pbr> for Material {
+    fn from(value: Pbr) -> Self {
+        Material::Pbr(value)
+    }
+}
 impl Default for Material {
     fn default() -> Self {
         Material::from(Phong::default())
@@ -51,20 +116,559 @@ impl Illuminated for Material {
     ) -> Color {
         match *self {
             Material::Phong(ref m) => m.lighting(body, light, position, eyev, normalv, in_shadow),
+            Material::Toon(ref m) => m.lighting(body, light, position, eyev, normalv, in_shadow),
+            Material::Lambert(ref m) => m.lighting(body, light, position, eyev, normalv, in_shadow),
+            Material::BlinnPhong(ref m) => m.lighting(body, light, position, eyev, normalv, in_shadow),
+            Material::Pbr(ref m) => m.lighting(body, light, position, eyev, normalv, in_shadow),
+        }
+    }
+    fn albedo(&self, body: &Body, position: Tuple) -> Color {
+        match *self {
+            Material::Phong(ref m) => m.albedo(body, position),
+            Material::Toon(ref m) => m.albedo(body, position),
+            Material::Lambert(ref m) => m.albedo(body, position),
+            Material::BlinnPhong(ref m) => m.albedo(body, position),
+            Material::Pbr(ref m) => m.albedo(body, position),
+        }
+    }
+}
+
+impl Reflective for Material {
+    fn reflectiveness(&self) -> F {
+        match *self {
+        Material::Phong(ref m) => m.reflective,
+        Material::Toon(ref m) => m.reflective,
+        Material::Lambert(ref m) => m.reflectiveness(),
+        Material::BlinnPhong(ref m) => m.reflectiveness(),
+        Material::Pbr(ref m) => m.reflectiveness(),
+        }
+    }
+    fn fresnel(&self) -> bool {
+        match *self {
+            Material::Phong(ref m) => m.fresnel,
+            Material::Toon(ref m) => m.fresnel,
+            Material::Lambert(ref m) => m.fresnel,
+            Material::BlinnPhong(ref m) => m.fresnel,
+            Material::Pbr(ref m) => m.fresnel,
+        }
+    }
+    fn roughness(&self) -> F {
+        match *self {
+            Material::Phong(ref m) => m.roughness,
+            Material::Toon(ref m) => m.roughness,
+            Material::Lambert(ref m) => m.roughness,
+            Material::BlinnPhong(ref m) => m.roughness,
+            Material::Pbr(ref m) => m.roughness,
+        }
+    }
+}
+
+impl Emissive for Material {
+    fn emission(&self) -> Color {
+        match *self {
+            Material::Phong(ref m) => m.emission,
+            Material::Toon(ref m) => m.emission,
+            Material::Lambert(ref m) => m.emission,
+            Material::BlinnPhong(ref m) => m.emission,
+            Material::Pbr(ref m) => m.emission,
+        }
+    }
+}
+
+impl Perturbed for Material {
+    fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        match *self {
+            Material::Phong(ref m) => m.perturb_normal(body, position, normalv),
+            // flat shading is the point, small details would only add noise
+            Material::Toon(_) => normalv,
+            Material::Lambert(ref m) => m.perturb_normal(body, position, normalv),
+            Material::BlinnPhong(ref m) => m.perturb_normal(body, position, normalv),
+            Material::Pbr(ref m) => m.perturb_normal(body, position, normalv),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phong {
+    pub color: Color,
+    pub pattern: Option<Pattern>,
+    pub ambient: F,
+    pub diffuse: F,
+    pub specular: F,
+    pub shine: F,
+    pub reflective: F,
+    // reflect more at grazing angles, reflective being the head-on share
+    pub fresnel: bool,
+    // spreads reflected rays in a cone, 0 being a perfect mirror
+    pub roughness: F,
+    pub emission: Color,
+    pub bump: Option<Bump>,
+    pub normal_map: Option<NormalMap>,
+}
+
+impl Default for Phong {
+    fn default() -> Self {
+        Phong {
+            color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shine: 200.0,
+            reflective: 0.0,
+            fresnel: false,
+            roughness: 0.0,
+            emission: Color::black(),
+            bump: None,
+            normal_map: None,
+        }
+    }
+}
+
+
+impl Illuminated for Phong {
+    fn lighting(
+        &self,
+        body: &Body,
+        light: PointLight,
+        position: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        in_shadow: bool,
+    ) -> Color {
+        let diffuse: Color;
+        let specular: Color;
+        let mut color = self.color;
+        if let Some(ref pattern) = self.pattern {
+            color = pattern.color_at(position, body);
+        }
+        let effective_color = color * light.intensity;
+        let lightv = (light.position - position).normalize();
+        let ambient = effective_color * self.ambient;
+        if in_shadow {
+            return ambient;
+        }
+        let light_dot_normal = lightv.dot(normalv);
+        if light_dot_normal < 0.0 {
+            diffuse = Color::black();
+            specular = Color::black();
+        } else {
+            diffuse = effective_color * self.diffuse * light_dot_normal;
+            let reflectv = -lightv.reflect(normalv);
+            let reflect_dot_eye = reflectv.dot(eyev);
+            if reflect_dot_eye <= 0.0 {
+                specular = Color::black();
+            } else {
+                let factor = reflect_dot_eye.powf(self.shine);
+                specular = light.intensity * self.specular * factor;
+            }
+        }
+        ambient + diffuse + specular
+    }
+    fn albedo(&self, body: &Body, position: Tuple) -> Color {
+        match self.pattern {
+            Some(ref pattern) => pattern.color_at(position, body) * self.diffuse,
+            None => self.color * self.diffuse,
+        }
+    }
+}
+
+impl Perturbed for Phong {
+    fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        let mut normalv = normalv;
+        if let Some(ref normal_map) = self.normal_map {
+            normalv = normal_map.perturb_normal(body, position, normalv);
+        }
+        if let Some(bump) = self.bump {
+            normalv = bump.perturb_normal(body, position, normalv);
+        }
+        normalv
+    }
+}
+
+impl Phong {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_ambient(mut self, ambient: F) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: F) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: F) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_shininess(mut self, shininess: F) -> Self {
+        self.shine = shininess;
+        self
+    }
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+    pub fn with_reflective(mut self, reflective: F) -> Self {
+        self.reflective = reflective;
+        self
+    }
+    pub fn with_fresnel(mut self, fresnel: bool) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+    pub fn with_roughness(mut self, roughness: F) -> Self {
+        self.roughness = roughness;
+        self
+    }
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+    pub fn with_bump(mut self, bump: Bump) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+}
+
+impl FuzzyEq<Phong> for Phong {
+    fn fuzzy_eq(&self, other: Phong) -> bool {
+        self.color.fuzzy_eq(other.color)
+            && self.ambient.fuzzy_eq(other.ambient)
+            && self.diffuse.fuzzy_eq(other.diffuse)
+            && self.specular.fuzzy_eq(other.specular)
+            && self.shine.fuzzy_eq(other.shine)
+            && self.pattern.fuzzy_eq(other.pattern)
+            && self.bump.fuzzy_eq(other.bump)
+            && self.normal_map.fuzzy_eq(other.normal_map)
+            && self.emission.fuzzy_eq(other.emission)
+            && self.fresnel == other.fresnel
+            && self.roughness.fuzzy_eq(other.roughness)
+    }
+}
+
+// Cel shading: the diffuse light is cut into a few flat bands and the
+// specular highlight is either fully on or off.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toon {
+    pub color: Color,
+    pub pattern: Option<Pattern>,
+    pub ambient: F,
+    pub diffuse: F,
+    pub bands: usize,
+    pub specular: F,
+    pub shine: F,
+    pub reflective: F,
+    // reflect more at grazing angles, reflective being the head-on share
+    pub fresnel: bool,
+    // spreads reflected rays in a cone, 0 being a perfect mirror
+    pub roughness: F,
+    pub emission: Color,
+}
+
+impl Default for Toon {
+    fn default() -> Self {
+        Toon {
+            color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            bands: 3,
+            specular: 0.9,
+            shine: 200.0,
+            reflective: 0.0,
+            fresnel: false,
+            roughness: 0.0,
+            emission: Color::black(),
+        }
+    }
+}
+
+impl Illuminated for Toon {
+    fn lighting(
+        &self,
+        body: &Body,
+        light: PointLight,
+        position: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        in_shadow: bool,
+    ) -> Color {
+        let mut color = self.color;
+        if let Some(ref pattern) = self.pattern {
+            color = pattern.color_at(position, body);
         }
+        let effective_color = color * light.intensity;
+        let lightv = (light.position - position).normalize();
+        let ambient = effective_color * self.ambient;
+        let light_dot_normal = lightv.dot(normalv);
+        if in_shadow || light_dot_normal <= 0.0 {
+            return ambient;
+        }
+        // any lit point gets at least the first band, fully lit ones the last
+        let bands = self.bands.max(1) as F;
+        let band = (light_dot_normal * bands).ceil() / bands;
+        let diffuse = effective_color * self.diffuse * band;
+        let reflect_dot_eye = (-lightv.reflect(normalv)).dot(eyev);
+        let specular = if reflect_dot_eye > 0.0 && reflect_dot_eye.powf(self.shine) > 0.5 {
+            light.intensity * self.specular
+        } else {
+            Color::black()
+        };
+        ambient + diffuse + specular
+    }
+    fn albedo(&self, body: &Body, position: Tuple) -> Color {
+        match self.pattern {
+            Some(ref pattern) => pattern.color_at(position, body) * self.diffuse,
+            None => self.color * self.diffuse,
+        }
+    }
+}
+
+impl Toon {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    pub fn with_ambient(mut self, ambient: F) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: F) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    pub fn with_bands(mut self, bands: usize) -> Self {
+        self.bands = bands;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: F) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_shininess(mut self, shininess: F) -> Self {
+        self.shine = shininess;
+        self
+    }
+
+    pub fn with_reflective(mut self, reflective: F) -> Self {
+        self.reflective = reflective;
+        self
+    }
+
+    pub fn with_fresnel(mut self, fresnel: bool) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+    pub fn with_roughness(mut self, roughness: F) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+}
+
+impl FuzzyEq<Toon> for Toon {
+    fn fuzzy_eq(&self, other: Toon) -> bool {
+        self.color.fuzzy_eq(other.color)
+            && self.pattern.fuzzy_eq(other.pattern)
+            && self.ambient.fuzzy_eq(other.ambient)
+            && self.diffuse.fuzzy_eq(other.diffuse)
+            && self.bands == other.bands
+            && self.specular.fuzzy_eq(other.specular)
+            && self.shine.fuzzy_eq(other.shine)
+            && self.reflective.fuzzy_eq(other.reflective)
+            && self.emission.fuzzy_eq(other.emission)
+            && self.fresnel == other.fresnel
+            && self.roughness.fuzzy_eq(other.roughness)
+    }
+}
+
+// Matte surfaces, like chalk or unfinished wood: diffuse light only, the
+// same from whichever side they are seen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lambert {
+    pub color: Color,
+    pub pattern: Option<Pattern>,
+    pub ambient: F,
+    pub diffuse: F,
+    pub reflective: F,
+    // reflect more at grazing angles, reflective being the head-on share
+    pub fresnel: bool,
+    // spreads reflected rays in a cone, 0 being a perfect mirror
+    pub roughness: F,
+    pub emission: Color,
+    pub bump: Option<Bump>,
+    pub normal_map: Option<NormalMap>,
+}
+
+impl Default for Lambert {
+    fn default() -> Self {
+        Lambert {
+            color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            reflective: 0.0,
+            fresnel: false,
+            roughness: 0.0,
+            emission: Color::black(),
+            bump: None,
+            normal_map: None,
+        }
+    }
+}
+
+impl Illuminated for Lambert {
+    fn lighting(
+        &self,
+        body: &Body,
+        light: PointLight,
+        position: Tuple,
+        _eyev: Tuple,
+        normalv: Tuple,
+        in_shadow: bool,
+    ) -> Color {
+        let mut color = self.color;
+        if let Some(ref pattern) = self.pattern {
+            color = pattern.color_at(position, body);
+        }
+        let effective_color = color * light.intensity;
+        let lightv = (light.position - position).normalize();
+        let ambient = effective_color * self.ambient;
+        let light_dot_normal = lightv.dot(normalv);
+        if in_shadow || light_dot_normal < 0.0 {
+            return ambient;
+        }
+        ambient + effective_color * self.diffuse * light_dot_normal
+    }
+    fn albedo(&self, body: &Body, position: Tuple) -> Color {
+        match self.pattern {
+            Some(ref pattern) => pattern.color_at(position, body) * self.diffuse,
+            None => self.color * self.diffuse,
+        }
+    }
+}
+
+impl Reflective for Lambert {
+    fn reflectiveness(&self) -> F {
+        self.reflective
+    }
+    fn fresnel(&self) -> bool {
+        self.fresnel
+    }
+    fn roughness(&self) -> F {
+        self.roughness
+    }
+}
+
+impl Perturbed for Lambert {
+    fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        let mut normalv = normalv;
+        if let Some(ref normal_map) = self.normal_map {
+            normalv = normal_map.perturb_normal(body, position, normalv);
+        }
+        if let Some(bump) = self.bump {
+            normalv = bump.perturb_normal(body, position, normalv);
+        }
+        normalv
+    }
+}
+
+impl Lambert {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    pub fn with_ambient(mut self, ambient: F) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: F) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    pub fn with_reflective(mut self, reflective: F) -> Self {
+        self.reflective = reflective;
+        self
+    }
+
+    pub fn with_fresnel(mut self, fresnel: bool) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+    pub fn with_roughness(mut self, roughness: F) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_bump(mut self, bump: Bump) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
     }
 }
 
-impl Reflective for Material {
-    fn reflectiveness(&self) -> F {
-        match *self {
-        Material::Phong(ref m) => m.reflective
-        }
+impl FuzzyEq<Lambert> for Lambert {
+    fn fuzzy_eq(&self, other: Lambert) -> bool {
+        self.color.fuzzy_eq(other.color)
+            && self.pattern.fuzzy_eq(other.pattern)
+            && self.ambient.fuzzy_eq(other.ambient)
+            && self.diffuse.fuzzy_eq(other.diffuse)
+            && self.reflective.fuzzy_eq(other.reflective)
+            && self.emission.fuzzy_eq(other.emission)
+            && self.fresnel == other.fresnel
+            && self.roughness.fuzzy_eq(other.roughness)
+            && self.bump.fuzzy_eq(other.bump)
+            && self.normal_map.fuzzy_eq(other.normal_map)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Phong {
+// Phong with the specular term taken from the half vector between the light
+// and the eye. Highlights stay round at grazing angles instead of being cut
+// off, and need about four times the Phong shininess for the same size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlinnPhong {
     pub color: Color,
     pub pattern: Option<Pattern>,
     pub ambient: F,
@@ -72,11 +676,18 @@ pub struct Phong {
     pub specular: F,
     pub shine: F,
     pub reflective: F,
+    // reflect more at grazing angles, reflective being the head-on share
+    pub fresnel: bool,
+    // spreads reflected rays in a cone, 0 being a perfect mirror
+    pub roughness: F,
+    pub emission: Color,
+    pub bump: Option<Bump>,
+    pub normal_map: Option<NormalMap>,
 }
 
-impl Default for Phong {
+impl Default for BlinnPhong {
     fn default() -> Self {
-        Phong {
+        BlinnPhong {
             color: Color::new(1.0, 1.0, 1.0),
             pattern: None,
             ambient: 0.1,
@@ -84,12 +695,16 @@ impl Default for Phong {
             specular: 0.9,
             shine: 200.0,
             reflective: 0.0,
+            fresnel: false,
+            roughness: 0.0,
+            emission: Color::black(),
+            bump: None,
+            normal_map: None,
         }
     }
 }
 
-
-impl Illuminated for Phong {
+impl Illuminated for BlinnPhong {
     fn lighting(
         &self,
         body: &Body,
@@ -99,43 +714,71 @@ impl Illuminated for Phong {
         normalv: Tuple,
         in_shadow: bool,
     ) -> Color {
-        let diffuse: Color;
-        let specular: Color;
         let mut color = self.color;
-        if let Some(pattern) = self.pattern {
+        if let Some(ref pattern) = self.pattern {
             color = pattern.color_at(position, body);
         }
         let effective_color = color * light.intensity;
         let lightv = (light.position - position).normalize();
         let ambient = effective_color * self.ambient;
-        if in_shadow {
+        let light_dot_normal = lightv.dot(normalv);
+        if in_shadow || light_dot_normal < 0.0 {
             return ambient;
         }
-        let light_dot_normal = lightv.dot(normalv);
-        if light_dot_normal < 0.0 {
-            diffuse = Color::black();
-            specular = Color::black();
+        let diffuse = effective_color * self.diffuse * light_dot_normal;
+        let halfv = (lightv + eyev).normalize();
+        let half_dot_normal = halfv.dot(normalv);
+        let specular = if half_dot_normal <= 0.0 {
+            Color::black()
         } else {
-            diffuse = effective_color * self.diffuse * light_dot_normal;
-            let reflectv = -lightv.reflect(normalv);
-            let reflect_dot_eye = reflectv.dot(eyev);
-            if reflect_dot_eye <= 0.0 {
-                specular = Color::black();
-            } else {
-                let factor = reflect_dot_eye.powf(self.shine);
-                specular = light.intensity * self.specular * factor;
-            }
-        }
+            light.intensity * self.specular * half_dot_normal.powf(self.shine)
+        };
         ambient + diffuse + specular
     }
+    fn albedo(&self, body: &Body, position: Tuple) -> Color {
+        match self.pattern {
+            Some(ref pattern) => pattern.color_at(position, body) * self.diffuse,
+            None => self.color * self.diffuse,
+        }
+    }
 }
 
-impl Phong {
+impl Reflective for BlinnPhong {
+    fn reflectiveness(&self) -> F {
+        self.reflective
+    }
+    fn fresnel(&self) -> bool {
+        self.fresnel
+    }
+    fn roughness(&self) -> F {
+        self.roughness
+    }
+}
+
+impl Perturbed for BlinnPhong {
+    fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        let mut normalv = normalv;
+        if let Some(ref normal_map) = self.normal_map {
+            normalv = normal_map.perturb_normal(body, position, normalv);
+        }
+        if let Some(bump) = self.bump {
+            normalv = bump.perturb_normal(body, position, normalv);
+        }
+        normalv
+    }
+}
+
+impl BlinnPhong {
     pub fn with_color(mut self, color: Color) -> Self {
         self.color = color;
         self
     }
 
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
     pub fn with_ambient(mut self, ambient: F) -> Self {
         self.ambient = ambient;
         self
@@ -155,24 +798,274 @@ impl Phong {
         self.shine = shininess;
         self
     }
-    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
-        self.pattern = Some(pattern);
-        self
-    }
+
     pub fn with_reflective(mut self, reflective: F) -> Self {
         self.reflective = reflective;
         self
     }
+
+    pub fn with_fresnel(mut self, fresnel: bool) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+    pub fn with_roughness(mut self, roughness: F) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_bump(mut self, bump: Bump) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
 }
 
-impl FuzzyEq<Phong> for Phong {
-    fn fuzzy_eq(&self, other: Phong) -> bool {
+impl FuzzyEq<BlinnPhong> for BlinnPhong {
+    fn fuzzy_eq(&self, other: BlinnPhong) -> bool {
         self.color.fuzzy_eq(other.color)
+            && self.pattern.fuzzy_eq(other.pattern)
             && self.ambient.fuzzy_eq(other.ambient)
             && self.diffuse.fuzzy_eq(other.diffuse)
             && self.specular.fuzzy_eq(other.specular)
             && self.shine.fuzzy_eq(other.shine)
-            && self.pattern.fuzzy_eq(other.pattern)
+            && self.reflective.fuzzy_eq(other.reflective)
+            && self.emission.fuzzy_eq(other.emission)
+            && self.fresnel == other.fresnel
+            && self.roughness.fuzzy_eq(other.roughness)
+            && self.bump.fuzzy_eq(other.bump)
+            && self.normal_map.fuzzy_eq(other.normal_map)
+    }
+}
+
+// Metallic/roughness material, lit with a Cook-Torrance microfacet BRDF: GGX
+// distribution, Smith geometry term and Schlick Fresnel. Patterns can drive
+// the base color and the roughness, the latter from the mean of the pattern
+// channels so grayscale maps work as is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pbr {
+    pub color: Color,
+    pub color_pattern: Option<Pattern>,
+    pub metallic: F,
+    pub roughness: F,
+    pub roughness_pattern: Option<Pattern>,
+    pub ambient: F,
+    pub reflective: F,
+    // reflect more at grazing angles, reflective being the head-on share
+    pub fresnel: bool,
+    pub emission: Color,
+    pub bump: Option<Bump>,
+    pub normal_map: Option<NormalMap>,
+}
+
+impl Default for Pbr {
+    fn default() -> Self {
+        Pbr {
+            color: Color::new(1.0, 1.0, 1.0),
+            color_pattern: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            roughness_pattern: None,
+            ambient: 0.1,
+            reflective: 0.0,
+            fresnel: false,
+            emission: Color::black(),
+            bump: None,
+            normal_map: None,
+        }
+    }
+}
+
+// reflectance of dielectrics seen head-on, about right for plastic or glass
+const DIELECTRIC_REFLECTANCE: F = 0.04;
+// perfectly smooth surfaces give infinitely thin highlights
+const MIN_ROUGHNESS: F = 0.02;
+
+impl Pbr {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_color_pattern(mut self, pattern: Pattern) -> Self {
+        self.color_pattern = Some(pattern);
+        self
+    }
+
+    pub fn with_metallic(mut self, metallic: F) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: F) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_roughness_pattern(mut self, pattern: Pattern) -> Self {
+        self.roughness_pattern = Some(pattern);
+        self
+    }
+
+    pub fn with_ambient(mut self, ambient: F) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_reflective(mut self, reflective: F) -> Self {
+        self.reflective = reflective;
+        self
+    }
+
+    pub fn with_fresnel(mut self, fresnel: bool) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_bump(mut self, bump: Bump) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    pub fn color_at(&self, position: Tuple, body: &Body) -> Color {
+        match self.color_pattern {
+            Some(ref pattern) => pattern.color_at(position, body),
+            None => self.color,
+        }
+    }
+
+    pub fn roughness_at(&self, position: Tuple, body: &Body) -> F {
+        let roughness = match self.roughness_pattern {
+            Some(ref pattern) => {
+                let color = pattern.color_at(position, body);
+                (color.red + color.green + color.blue) / 3.0
+            }
+            None => self.roughness,
+        };
+        roughness.clamp(MIN_ROUGHNESS, 1.0)
+    }
+
+    // Schlick approximation, f0 being the reflectance seen head-on
+    pub fn fresnel_color(f0: Color, cos_theta: F) -> Color {
+        let factor = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+        f0 + (Color::white() - f0) * factor
+    }
+
+    // GGX normal distribution, alpha being the squared roughness
+    fn distribution(normal_dot_half: F, alpha: F) -> F {
+        let alpha2 = alpha * alpha;
+        let denominator = normal_dot_half * normal_dot_half * (alpha2 - 1.0) + 1.0;
+        alpha2 / (std::f64::consts::PI * denominator * denominator)
+    }
+
+    // Smith shadowing and masking, with the Schlick-GGX term of each direction
+    fn geometry(normal_dot_view: F, normal_dot_light: F, roughness: F) -> F {
+        let k = (roughness + 1.0).powi(2) / 8.0;
+        let schlick_ggx = |cos: F| cos / (cos * (1.0 - k) + k);
+        schlick_ggx(normal_dot_view) * schlick_ggx(normal_dot_light)
+    }
+}
+
+impl Illuminated for Pbr {
+    fn lighting(
+        &self,
+        body: &Body,
+        light: PointLight,
+        position: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        in_shadow: bool,
+    ) -> Color {
+        let color = self.color_at(position, body);
+        let ambient = color * light.intensity * self.ambient;
+        let lightv = (light.position - position).normalize();
+        let normal_dot_light = lightv.dot(normalv);
+        if in_shadow || normal_dot_light <= 0.0 {
+            return ambient;
+        }
+        let roughness = self.roughness_at(position, body);
+        let normal_dot_view = eyev.dot(normalv).max(EPSILON);
+        let halfv = (lightv + eyev).normalize();
+        let normal_dot_half = halfv.dot(normalv).max(0.0);
+        let view_dot_half = halfv.dot(eyev).max(0.0);
+
+        let dielectric = Color::new(DIELECTRIC_REFLECTANCE, DIELECTRIC_REFLECTANCE, DIELECTRIC_REFLECTANCE);
+        let f0 = dielectric + (color - dielectric) * self.metallic;
+        let fresnel = Pbr::fresnel_color(f0, view_dot_half);
+        let specular = fresnel
+            * (Pbr::distribution(normal_dot_half, roughness * roughness)
+                * Pbr::geometry(normal_dot_view, normal_dot_light, roughness)
+                / (4.0 * normal_dot_view * normal_dot_light));
+        // light that is not reflected enters the surface, metals absorb it
+        let diffuse = (Color::white() - fresnel) * color * (1.0 - self.metallic);
+        // the BRDF is scaled by PI so that the light intensity is the
+        // irradiance of a surface facing it, a white matte one then shows 1.0
+        // like with the other materials
+        ambient
+            + (diffuse + specular * std::f64::consts::PI) * light.intensity * normal_dot_light
+    }
+    fn albedo(&self, body: &Body, position: Tuple) -> Color {
+        self.color_at(position, body) * (1.0 - self.metallic)
+    }
+}
+
+impl Reflective for Pbr {
+    fn reflectiveness(&self) -> F {
+        self.reflective
+    }
+    fn fresnel(&self) -> bool {
+        self.fresnel
+    }
+    // the microfacets spreading the highlights blur the reflections as much
+    fn roughness(&self) -> F {
+        self.roughness
+    }
+}
+
+impl Perturbed for Pbr {
+    fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        let mut normalv = normalv;
+        if let Some(ref normal_map) = self.normal_map {
+            normalv = normal_map.perturb_normal(body, position, normalv);
+        }
+        if let Some(bump) = self.bump {
+            normalv = bump.perturb_normal(body, position, normalv);
+        }
+        normalv
+    }
+}
+
+impl FuzzyEq<Pbr> for Pbr {
+    fn fuzzy_eq(&self, other: Pbr) -> bool {
+        self.color.fuzzy_eq(other.color)
+            && self.color_pattern.fuzzy_eq(other.color_pattern)
+            && self.metallic.fuzzy_eq(other.metallic)
+            && self.roughness.fuzzy_eq(other.roughness)
+            && self.roughness_pattern.fuzzy_eq(other.roughness_pattern)
+            && self.ambient.fuzzy_eq(other.ambient)
+            && self.reflective.fuzzy_eq(other.reflective)
+            && self.emission.fuzzy_eq(other.emission)
+            && self.fresnel == other.fresnel
+            && self.bump.fuzzy_eq(other.bump)
+            && self.normal_map.fuzzy_eq(other.normal_map)
     }
 }
 
@@ -180,9 +1073,11 @@ impl FuzzyEq<Material> for Material {
     fn fuzzy_eq(&self, other: Material) -> bool {
         match (self, other) {
             (Material::Phong(ref m), Material::Phong(other)) => m.fuzzy_eq(other),
-            // Add default case (different types) to return false, once more than one
-            // Material exists
-            // _ => false,
+            (Material::Toon(ref m), Material::Toon(other)) => m.fuzzy_eq(other),
+            (Material::Lambert(ref m), Material::Lambert(other)) => m.fuzzy_eq(other),
+            (Material::BlinnPhong(ref m), Material::BlinnPhong(other)) => m.fuzzy_eq(other),
+            (Material::Pbr(ref m), Material::Pbr(other)) => m.fuzzy_eq(other),
+            _ => false,
         }
     }
 }
@@ -192,6 +1087,7 @@ mod tests {
     // use crate::fuzzy_eq::FuzzyEq;
     use super::*;
     use crate::sphere::Sphere;
+    use crate::{assert_fuzzy_eq, assert_fuzzy_ne};
     #[test]
     fn default_material() {
         let m = Phong::default();
@@ -311,4 +1207,172 @@ mod tests {
 
 		assert_eq!(m.reflective, 0.5);
 	}
+    #[test]
+    fn phong_material_without_bump_keeps_the_normal() {
+        let m = Material::default();
+        let body = Body::from(Sphere::default());
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+
+        assert_eq!(m.perturb_normal(&body, Tuple::point(0.0, 0.0, -1.0), normalv), normalv);
+    }
+
+    #[test]
+    fn toon_diffuse_is_cut_into_bands() {
+        let m = Toon::default().with_specular(0.0);
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let eyev = Tuple::vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let lit_at = |angle: F| {
+            let light = PointLight::new(
+                Tuple::point(0.0, 10.0 * angle.sin(), -10.0 * angle.cos()),
+                Color::new(1.0, 1.0, 1.0),
+            );
+            m.lighting(&body, light, position, eyev, normalv, false)
+        };
+
+        // cos = 0.98, 0.9 and 0.7 are all in the last band
+        assert_eq!(lit_at(0.2), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(lit_at(0.45), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(lit_at(0.8), Color::new(1.0, 1.0, 1.0));
+        // cos = 0.5 is in the middle one, cos = 0.2 in the first one
+        assert_eq!(lit_at(1.05), Color::new(0.7, 0.7, 0.7));
+        assert_eq!(lit_at(1.37), Color::new(0.4, 0.4, 0.4));
+        // unlit side only gets ambient
+        assert_eq!(lit_at(2.0), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn toon_specular_highlight_is_hard() {
+        let m = Toon::default().with_bands(1).with_shininess(10.0);
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let eye_at = |angle: F| Tuple::vector(0.0, angle.sin(), -angle.cos());
+
+        // 0.99^10 = 0.90 is in the highlight, 0.9^10 = 0.35 is not
+        assert_eq!(m.lighting(&body, light, position, eye_at(0.14), normalv, false), Color::new(1.9, 1.9, 1.9));
+        assert_eq!(m.lighting(&body, light, position, eye_at(0.45), normalv, false), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(m.lighting(&body, light, position, eye_at(0.0), normalv, true), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lambert_ignores_where_the_eye_is() {
+        let m = Lambert::default();
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let sqrt2_over_2 = (2.0 as F).sqrt() / 2.0;
+
+        let facing = m.lighting(&body, light, position, Tuple::vector(0.0, 0.0, -1.0), normalv, false);
+        let mirrored = m.lighting(&body, light, position, Tuple::vector(0.0, -sqrt2_over_2, -sqrt2_over_2), normalv, false);
+
+        assert_eq!(facing, Color::new(0.7364, 0.7364, 0.7364));
+        assert_eq!(mirrored, facing);
+        assert_eq!(m.lighting(&body, light, position, Tuple::vector(0.0, 0.0, -1.0), normalv, true), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn blinn_phong_highlight_follows_the_half_vector() {
+        let m = BlinnPhong::default().with_shininess(10.0);
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let sqrt2_over_2 = (2.0 as F).sqrt() / 2.0;
+
+        let facing = m.lighting(&body, light, position, Tuple::vector(0.0, 0.0, -1.0), normalv, false);
+        // the half vector is 22.5 degrees off the normal, 0.92388^10 = 0.45306
+        let offset = m.lighting(&body, light, position, Tuple::vector(0.0, sqrt2_over_2, -sqrt2_over_2), normalv, false);
+
+        assert_eq!(facing, Color::new(1.9, 1.9, 1.9));
+        assert_eq!(offset, Color::new(1.40775, 1.40775, 1.40775));
+    }
+
+    #[test]
+    fn pbr_rough_dielectric_facing_the_light() {
+        let m = Pbr::default().with_roughness(1.0);
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let eyev = Tuple::vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+        // 0.1 ambient, 0.96 diffuse and D = 1 / PI, G = 1, F = 0.04 specular
+        assert_eq!(m.lighting(&body, light, position, eyev, normalv, false), Color::new(1.07, 1.07, 1.07));
+        assert_eq!(m.lighting(&body, light, position, eyev, normalv, true), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn pbr_metals_tint_their_highlights_and_have_no_diffuse() {
+        let m = Pbr::default()
+            .with_color(Color::new(1.0, 0.5, 0.0))
+            .with_metallic(1.0)
+            .with_ambient(0.0);
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let sqrt2_over_2 = (2.0 as F).sqrt() / 2.0;
+
+        let highlight = m.lighting(&body, light, position, Tuple::vector(0.0, -sqrt2_over_2, -sqrt2_over_2), normalv, false);
+        let off_highlight = m.lighting(&body, light, position, Tuple::vector(0.0, sqrt2_over_2, -sqrt2_over_2), normalv, false);
+
+        // Fresnel only brings a little white back at 45 degrees
+        assert!(highlight.red > highlight.green && highlight.green > highlight.blue);
+        assert!(highlight.blue < highlight.red * 0.05);
+        assert!(off_highlight.red < highlight.red * 0.1);
+    }
+
+    #[test]
+    fn pbr_smooth_surfaces_have_sharper_highlights() {
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.0, 0.0);
+        let normalv = Tuple::vector(0.0, 0.0, -1.0);
+        let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let specular = |roughness: F, eyev: Tuple| {
+            let m = Pbr::default().with_roughness(roughness).with_metallic(1.0).with_ambient(0.0);
+            m.lighting(&body, light, position, eyev, normalv, false).red
+        };
+        let head_on = Tuple::vector(0.0, 0.0, -1.0);
+        let grazing = Tuple::vector(0.0, 0.5, -(0.75 as F).sqrt());
+
+        assert!(specular(0.1, head_on) > specular(0.8, head_on));
+        assert!(specular(0.1, grazing) < specular(0.8, grazing));
+    }
+
+    #[test]
+    fn pbr_roughness_can_come_from_a_pattern() {
+        let body = Body::from(Sphere::default());
+        let pattern = Pattern::from(
+            crate::pattern::Striped::default().with_colors(Color::new(0.3, 0.3, 0.3), Color::new(0.0, 0.03, 0.0)),
+        );
+        let m = Pbr::default().with_roughness_pattern(pattern);
+
+        assert_eq!(m.roughness_at(Tuple::point(0.5, 0.0, 0.0), &body), 0.3);
+        // too smooth to be rendered, see MIN_ROUGHNESS
+        assert_eq!(m.roughness_at(Tuple::point(1.5, 0.0, 0.0), &body), 0.02);
+        assert_eq!(Pbr::default().roughness_at(Tuple::point(0.0, 0.0, 0.0), &body), 0.5);
+    }
+
+    #[test]
+    fn fresnel_reflectance_grows_at_grazing_angles() {
+        let constant = Material::from(Phong::default().with_reflective(0.1));
+        let fresnel = Material::from(Lambert::default().with_reflective(0.1).with_fresnel(true));
+
+        assert_eq!(constant.reflectance(0.5), 0.1);
+        assert_eq!(fresnel.reflectance(1.0), 0.1);
+        assert!(fresnel.reflectance(0.5).fuzzy_eq(0.128125));
+        assert_eq!(fresnel.reflectance(0.0), 1.0);
+    }
+
+    #[test]
+    fn materials_of_different_kinds_are_not_equal() {
+        assert_fuzzy_ne!(Material::from(Phong::default()), Material::from(Toon::default()));
+        assert_fuzzy_ne!(Material::from(Phong::default()), Material::from(BlinnPhong::default()));
+        assert_fuzzy_ne!(Material::from(Lambert::default()), Material::from(Toon::default()));
+        assert_fuzzy_eq!(Material::from(Toon::default()), Material::from(Toon::default()));
+    }
 }
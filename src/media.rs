@@ -0,0 +1,121 @@
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::F;
+
+// Share of the light going through a homogeneous medium of the given density
+// over a distance (Beer-Lambert law)
+pub fn transmittance(density: F, distance: F) -> F {
+    (-density * distance).exp()
+}
+
+// Fog filling the whole world. Whatever is seen through it fades towards its
+// color with the distance, rays reaching nothing see the fog color only. It
+// is a depth cue: the light reaching the bodies is not dimmed by it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub color: Color,
+    // extinction per unit of distance, 0 being clear air
+    pub density: F,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            color: Color::new(0.5, 0.5, 0.5),
+            density: 0.1,
+        }
+    }
+}
+
+impl Fog {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_density(mut self, density: F) -> Self {
+        self.density = density;
+        self
+    }
+
+    // share of what stands at the given distance still seen through the fog,
+    // nothing for rays going to infinity unless there is no fog at all
+    pub fn transmittance(&self, distance: F) -> F {
+        if self.density == 0.0 {
+            1.0
+        } else if distance.is_infinite() {
+            0.0
+        } else {
+            transmittance(self.density, distance)
+        }
+    }
+}
+
+impl FuzzyEq<Fog> for Fog {
+    fn fuzzy_eq(&self, other: Fog) -> bool {
+        self.color.fuzzy_eq(other.color) && self.density.fuzzy_eq(other.density)
+    }
+}
+
+// Smoke filling a body, which is then only seen through the light it
+// scatters. Light reaching points along the rays from the lights is scattered
+// once towards the eye, see World::media_along, and the light going through
+// it to the surfaces behind is dimmed the same way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Medium {
+    // extinction per unit of distance in world space
+    pub density: F,
+    // share of the light scattered rather than absorbed, per channel
+    pub color: Color,
+    // points along each ray where the light is gathered
+    pub steps: usize,
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Medium {
+            density: 1.0,
+            color: Color::white(),
+            steps: 16,
+        }
+    }
+}
+
+impl Medium {
+    pub fn with_density(mut self, density: F) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+}
+
+impl FuzzyEq<Medium> for Medium {
+    fn fuzzy_eq(&self, other: Medium) -> bool {
+        self.density.fuzzy_eq(other.density) && self.color.fuzzy_eq(other.color) && self.steps == other.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fog_hides_more_with_the_distance() {
+        let fog = Fog::default().with_density(0.5);
+
+        assert_eq!(fog.transmittance(0.0), 1.0);
+        assert!(fog.transmittance(1.0) > fog.transmittance(4.0));
+        assert!(fog.transmittance(4.0) > 0.0);
+        assert_eq!(fog.transmittance(F::INFINITY), 0.0);
+        assert_eq!(Fog::default().with_density(0.0).transmittance(F::INFINITY), 1.0);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::fuzzy_eq::FuzzyEq;
+use crate::F;
+
+// Averages several rays per pixel, cast at times spread over the interval the
+// shutter stays open. Time 0 puts moving bodies at their transform and time 1
+// at their end transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionBlur {
+    pub samples: usize,
+    pub open: F,
+    pub close: F,
+}
+
+impl Default for MotionBlur {
+    fn default() -> Self {
+        MotionBlur {
+            samples: 8,
+            open: 0.0,
+            close: 1.0,
+        }
+    }
+}
+
+impl MotionBlur {
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_shutter(mut self, open: F, close: F) -> Self {
+        self.open = open;
+        self.close = close;
+        self
+    }
+
+    // One time in each of the equal slices of the shutter interval. The place
+    // within the slices changes from pixel to pixel so that few samples give
+    // grain instead of sharp ghost copies of the moving bodies.
+    pub fn times(&self, x: usize, y: usize) -> impl Iterator<Item = F> + '_ {
+        // R2 low discrepancy sequence, well spread over neighbouring pixels
+        let jitter = (0.5 + x as F * 0.754_877_666_2 + y as F * 0.569_840_290_9).fract();
+        let slice = (self.close - self.open) / self.samples as F;
+        (0..self.samples).map(move |sample| self.open + (sample as F + jitter) * slice)
+    }
+}
+
+impl FuzzyEq<MotionBlur> for MotionBlur {
+    fn fuzzy_eq(&self, other: MotionBlur) -> bool {
+        self.samples == other.samples
+            && self.open.fuzzy_eq(other.open)
+            && self.close.fuzzy_eq(other.close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_are_spread_over_the_shutter_interval() {
+        let motion_blur = MotionBlur::default().with_samples(4).with_shutter(0.2, 0.6);
+
+        for (x, y) in [(0, 0), (3, 7), (120, 45)] {
+            let times: Vec<F> = motion_blur.times(x, y).collect();
+            assert_eq!(times.len(), 4);
+            for (sample, time) in times.iter().enumerate() {
+                let slice_start = 0.2 + sample as F * 0.1;
+                assert!(*time >= slice_start && *time < slice_start + 0.1);
+            }
+        }
+    }
+
+    #[test]
+    fn neighbouring_pixels_sample_different_times() {
+        let motion_blur = MotionBlur::default().with_samples(1);
+
+        let first: Vec<F> = motion_blur.times(0, 0).collect();
+        let second: Vec<F> = motion_blur.times(1, 0).collect();
+
+        assert!(!first[0].fuzzy_eq(second[0]));
+    }
+}
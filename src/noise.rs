@@ -0,0 +1,145 @@
+use crate::tuple::Tuple;
+use crate::F;
+
+// Ken Perlin's improved noise (https://mrl.cs.nyu.edu/~perlin/noise/)
+// Deterministic on purpose: the same scene always renders the same image.
+const PERMUTATION: [usize; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn permutation(index: usize) -> usize {
+    PERMUTATION[index & 255]
+}
+
+fn fade(t: F) -> F {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: F, a: F, b: F) -> F {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: F, y: F, z: F) -> F {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+// smooth noise in roughly [-1, 1], zero on every integer lattice point
+pub fn perlin(point: Tuple) -> F {
+    let floor_x = point.x.floor();
+    let floor_y = point.y.floor();
+    let floor_z = point.z.floor();
+    let xi = (floor_x as i64 & 255) as usize;
+    let yi = (floor_y as i64 & 255) as usize;
+    let zi = (floor_z as i64 & 255) as usize;
+    let x = point.x - floor_x;
+    let y = point.y - floor_y;
+    let z = point.z - floor_z;
+    let u = fade(x);
+    let v = fade(y);
+    let w = fade(z);
+
+    let a = permutation(xi) + yi;
+    let aa = permutation(a) + zi;
+    let ab = permutation(a + 1) + zi;
+    let b = permutation(xi + 1) + yi;
+    let ba = permutation(b) + zi;
+    let bb = permutation(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(permutation(aa), x, y, z), grad(permutation(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(permutation(ab), x, y - 1.0, z),
+                grad(permutation(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(permutation(aa + 1), x, y, z - 1.0),
+                grad(permutation(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(permutation(ab + 1), x, y - 1.0, z - 1.0),
+                grad(permutation(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+// sum of noise octaves, each twice the frequency and half the amplitude of the last
+pub fn turbulence(point: Tuple, octaves: usize) -> F {
+    let mut sum = 0.0;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        sum += perlin(point * frequency).abs() / frequency;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_eq::FuzzyEq;
+
+    #[test]
+    fn noise_is_zero_on_lattice_points() {
+        assert!(perlin(Tuple::point(0.0, 0.0, 0.0)).fuzzy_eq(0.0));
+        assert!(perlin(Tuple::point(1.0, 2.0, 3.0)).fuzzy_eq(0.0));
+        assert!(perlin(Tuple::point(-4.0, 7.0, -1.0)).fuzzy_eq(0.0));
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_bounded() {
+        for i in 0..200 {
+            let point = Tuple::point(i as F * 0.37, i as F * -0.11, i as F * 0.73);
+            let value = perlin(point);
+            assert_eq!(value, perlin(point));
+            assert!((-1.0..=1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn noise_varies_between_lattice_points() {
+        let values: Vec<F> = (0..10)
+            .map(|i| perlin(Tuple::point(0.5 + i as F * 0.1, 0.25, 0.75)))
+            .collect();
+        assert!(values.iter().any(|value| value.abs() > 0.01));
+    }
+
+    #[test]
+    fn turbulence_is_positive_and_grows_with_octaves() {
+        let point = Tuple::point(0.3, 1.7, -2.2);
+
+        assert_eq!(turbulence(point, 0), 0.0);
+        assert_eq!(turbulence(point, 1), perlin(point).abs());
+        assert!(turbulence(point, 4) >= turbulence(point, 1));
+    }
+}
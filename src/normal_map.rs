@@ -0,0 +1,107 @@
+use crate::body::{Body, Intersectable};
+use crate::fuzzy_eq::FuzzyEq;
+use crate::texture::Texture;
+use crate::tuple::Tuple;
+use crate::F;
+
+// Tangent space normal map: red and green tilt the normal along u and v,
+// blue points away from the surface, as exported by Blender.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NormalMap {
+    pub texture: Texture,
+    pub strength: F,
+}
+
+impl NormalMap {
+    pub fn new(texture: Texture) -> Self {
+        NormalMap {
+            texture,
+            strength: 1.0,
+        }
+    }
+
+    pub fn with_strength(mut self, strength: F) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn perturb_normal(&self, body: &Body, position: Tuple, normalv: Tuple) -> Tuple {
+        let object_point = body.transform().inverse() * position;
+        let (u, v) = body.uv_at_in_object_space(object_point);
+        let texel = self.texture.color_at_uv(u, v);
+        let tangent_space_normal = Tuple::vector(
+            (texel.red * 2.0 - 1.0) * self.strength,
+            (texel.green * 2.0 - 1.0) * self.strength,
+            texel.blue * 2.0 - 1.0,
+        );
+
+        let tangent = body.tangent_at(position, normalv);
+        let bitangent = tangent.cross(normalv);
+        (tangent * tangent_space_normal.x
+            + bitangent * tangent_space_normal.y
+            + normalv * tangent_space_normal.z)
+            .normalize()
+    }
+}
+
+impl FuzzyEq<NormalMap> for NormalMap {
+    fn fuzzy_eq(&self, other: NormalMap) -> bool {
+        self.texture == other.texture && self.strength.fuzzy_eq(other.strength)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+
+    fn single_color_texture(color: Color) -> Texture {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, color);
+        Texture::new(canvas)
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_the_normal() {
+        let normal_map = NormalMap::new(single_color_texture(Color::new(0.5, 0.5, 1.0)));
+        let body = Body::from(Sphere::default());
+        let position = Tuple::point(0.0, 0.6, -0.8);
+        let normalv = body.normal_at(position);
+
+        assert_eq!(normal_map.perturb_normal(&body, position, normalv), normalv);
+    }
+
+    #[test]
+    fn red_and_green_tilt_along_u_and_v() {
+        let body = Body::from(Plane::default());
+        let position = Tuple::point(0.2, 0.0, 0.3);
+        let normalv = Tuple::vector(0.0, 1.0, 0.0);
+        let sqrt2_over_2 = (2.0 as F).sqrt() / 2.0;
+
+        let along_u = NormalMap::new(single_color_texture(Color::new(1.0, 0.5, 1.0)));
+        assert_eq!(
+            along_u.perturb_normal(&body, position, normalv),
+            Tuple::vector(sqrt2_over_2, sqrt2_over_2, 0.0)
+        );
+
+        let along_v = NormalMap::new(single_color_texture(Color::new(0.5, 1.0, 1.0)));
+        assert_eq!(
+            along_v.perturb_normal(&body, position, normalv),
+            Tuple::vector(0.0, sqrt2_over_2, sqrt2_over_2)
+        );
+    }
+
+    #[test]
+    fn strength_scales_the_tilt() {
+        let body = Body::from(Plane::default());
+        let position = Tuple::point(0.2, 0.0, 0.3);
+        let normalv = Tuple::vector(0.0, 1.0, 0.0);
+        let normal_map =
+            NormalMap::new(single_color_texture(Color::new(1.0, 0.5, 1.0))).with_strength(0.0);
+
+        assert_eq!(normal_map.perturb_normal(&body, position, normalv), normalv);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::body::Intersectable;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+use crate::world::World;
+use crate::F;
+
+// Ink lines drawn where neighbouring pixels see different surfaces: around
+// silhouettes (big jump in depth) and along creases (big change of normal).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outline {
+    pub color: Color,
+    // how far behind the tangent plane of a pixel its neighbour may be, relative
+    // to the depth of the pixel
+    pub depth_threshold: F,
+    // 1 - cos of the angle between neighbouring normals, 0.3 is about 45 degrees
+    pub normal_threshold: F,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Outline {
+            color: Color::black(),
+            depth_threshold: 0.1,
+            normal_threshold: 0.3,
+        }
+    }
+}
+
+// What a camera ray sees first, without shading it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surface {
+    pub depth: F,
+    pub point: Tuple,
+    // facing the eye
+    pub normalv: Tuple,
+}
+
+pub fn surface_at(world: &World, ray: Ray) -> Option<Surface> {
+    world.intersect(ray).hit().map(|hit| {
+        let point = ray.position(hit.t);
+        let normalv = hit.body.normal_at(point);
+        Surface {
+            depth: hit.t,
+            point,
+            normalv: if normalv.dot(ray.direction) > 0.0 { -normalv } else { normalv },
+        }
+    })
+}
+
+impl Outline {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_depth_threshold(mut self, depth_threshold: F) -> Self {
+        self.depth_threshold = depth_threshold;
+        self
+    }
+
+    pub fn with_normal_threshold(mut self, normal_threshold: F) -> Self {
+        self.normal_threshold = normal_threshold;
+        self
+    }
+
+    // only the pixel in front gets inked, so silhouettes stay one pixel wide
+    // a surface seen at a grazing angle is not an edge, however fast its depth changes
+    fn is_edge(&self, surface: Option<Surface>, neighbour: Option<Surface>) -> bool {
+        match (surface, neighbour) {
+            (Some(_), None) => true,
+            (Some(surface), Some(neighbour)) => {
+                let off_plane = (neighbour.point - surface.point).dot(surface.normalv).abs();
+                (neighbour.depth > surface.depth && off_plane > self.depth_threshold * surface.depth)
+                    || 1.0 - surface.normalv.dot(neighbour.normalv) > self.normal_threshold
+            }
+            _ => false,
+        }
+    }
+
+    // surfaces holds one entry per pixel of the canvas, row by row
+    pub fn draw(&self, canvas: &mut Canvas, surfaces: &[Option<Surface>]) {
+        let width = canvas.width;
+        let height = canvas.height;
+        let surface = |x: usize, y: usize| surfaces[y * width + x];
+        for y in 0..height {
+            for x in 0..width {
+                let neighbours = [
+                    (x > 0).then(|| surface(x - 1, y)),
+                    (x + 1 < width).then(|| surface(x + 1, y)),
+                    (y > 0).then(|| surface(x, y - 1)),
+                    (y + 1 < height).then(|| surface(x, y + 1)),
+                ];
+                if neighbours
+                    .into_iter()
+                    .flatten()
+                    .any(|neighbour| self.is_edge(surface(x, y), neighbour))
+                {
+                    canvas.write_pixel(x, y, self.color);
+                }
+            }
+        }
+    }
+}
+
+impl FuzzyEq<Outline> for Outline {
+    fn fuzzy_eq(&self, other: Outline) -> bool {
+        self.color.fuzzy_eq(other.color)
+            && self.depth_threshold.fuzzy_eq(other.depth_threshold)
+            && self.normal_threshold.fuzzy_eq(other.normal_threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn surface_seen_by_a_ray() {
+        let world = World::new(vec![Body::from(Sphere::default())], vec![]);
+        let ray = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert_eq!(
+            surface_at(&world, ray),
+            Some(Surface {
+                depth: 4.0,
+                point: Tuple::point(0.0, 0.0, -1.0),
+                normalv: Tuple::vector(0.0, 0.0, -1.0)
+            })
+        );
+        let inside = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(surface_at(&world, inside).unwrap().normalv, Tuple::vector(0.0, 0.0, -1.0));
+        let miss = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        assert_eq!(surface_at(&world, miss), None);
+    }
+
+    #[test]
+    fn silhouettes_are_drawn_on_the_closest_surface() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let mut canvas = Canvas::new(4, 1);
+        for x in 0..4 {
+            canvas.write_pixel(x, 0, white);
+        }
+        let at = |x: F, depth: F| {
+            Some(Surface {
+                depth,
+                point: Tuple::point(x, 0.0, depth),
+                normalv: Tuple::vector(0.0, 0.0, -1.0),
+            })
+        };
+        // background | far wall | near object | near object
+        let surfaces = [None, at(1.0, 10.0), at(2.0, 5.0), at(3.0, 5.1)];
+        let outline = Outline::default().with_color(Color::new(1.0, 0.0, 0.0));
+
+        outline.draw(&mut canvas, &surfaces);
+
+        assert_eq!(canvas.color_at(0, 0), white);
+        assert_eq!(canvas.color_at(1, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(canvas.color_at(2, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(canvas.color_at(3, 0), white);
+    }
+
+    #[test]
+    fn grazing_planes_are_not_outlined() {
+        let mut canvas = Canvas::new(3, 1);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let on_floor = |depth: F| {
+            Some(Surface {
+                depth,
+                point: Tuple::point(0.0, 0.0, depth),
+                normalv: up,
+            })
+        };
+        let surfaces = [on_floor(10.0), on_floor(20.0), on_floor(40.0)];
+
+        Outline::default().with_color(Color::new(1.0, 0.0, 0.0)).draw(&mut canvas, &surfaces);
+
+        assert_eq!(canvas, Canvas::new(3, 1));
+    }
+
+    #[test]
+    fn creases_are_drawn_on_both_sides() {
+        let mut canvas = Canvas::new(3, 1);
+        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let front = Tuple::vector(0.0, 0.0, -1.0);
+        let tilted = Tuple::vector(0.0, 0.2, -1.0).normalize();
+        let at = |x: F, normalv: Tuple| {
+            Some(Surface {
+                depth: 5.0,
+                point: Tuple::point(x, 0.0, 5.0),
+                normalv,
+            })
+        };
+        let surfaces = [at(0.0, up), at(1.0, front), at(2.0, tilted)];
+        let white = Color::new(1.0, 1.0, 1.0);
+        for x in 0..3 {
+            canvas.write_pixel(x, 0, white);
+        }
+
+        Outline::default().draw(&mut canvas, &surfaces);
+
+        assert_eq!(canvas.color_at(0, 0), Color::black());
+        assert_eq!(canvas.color_at(1, 0), Color::black());
+        assert_eq!(canvas.color_at(2, 0), white);
+    }
+}
@@ -2,7 +2,10 @@ use crate::body::{Body, Intersectable};
 use crate::color::Color;
 use crate::fuzzy_eq::FuzzyEq;
 use crate::matrix::Matrix;
+use crate::noise::turbulence;
+use crate::texture::Texture;
 use crate::tuple::Tuple;
+use crate::F;
 
 
 pub trait Stencil {
@@ -15,12 +18,16 @@ pub trait Stencil {
       self.color_at_in_pattern_space(pattern_position)
     }
 }
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Pattern {
     Striped(Striped),
     Gradient(Gradient),
     Ring(Ring),
     Checker(Checker),
+    UvChecker(UvChecker),
+    Marble(Marble),
+    Wood(Wood),
+    Image(ImageTexture),
 }
 
 impl FuzzyEq<Pattern> for Pattern {
@@ -30,6 +37,10 @@ impl FuzzyEq<Pattern> for Pattern {
         (Pattern::Gradient(ref gradient), Pattern::Gradient(other)) => gradient.fuzzy_eq(other),
         (Pattern::Ring(ref ring), Pattern::Ring(other)) => ring.fuzzy_eq(other),
         (Pattern::Checker(ref checker), Pattern::Checker(other)) => checker.fuzzy_eq(other),
+        (Pattern::UvChecker(ref checker), Pattern::UvChecker(other)) => checker.fuzzy_eq(other),
+        (Pattern::Marble(ref marble), Pattern::Marble(other)) => marble.fuzzy_eq(other),
+        (Pattern::Wood(ref wood), Pattern::Wood(other)) => wood.fuzzy_eq(other),
+        (Pattern::Image(ref image), Pattern::Image(other)) => image.fuzzy_eq(other),
         _ => false,
       }
     }
@@ -42,6 +53,10 @@ impl Stencil for Pattern {
             Pattern::Gradient(ref gradient) => gradient.color_at_in_pattern_space(position),
             Pattern::Ring(ref ring) => ring.color_at_in_pattern_space(position),
             Pattern::Checker(ref checker) => checker.color_at_in_pattern_space(position),
+            Pattern::UvChecker(ref checker) => checker.color_at_in_pattern_space(position),
+            Pattern::Marble(ref marble) => marble.color_at_in_pattern_space(position),
+            Pattern::Wood(ref wood) => wood.color_at_in_pattern_space(position),
+            Pattern::Image(ref image) => image.color_at_in_pattern_space(position),
         }
     }
     fn transform(&self) -> Matrix<4> {
@@ -50,6 +65,22 @@ impl Stencil for Pattern {
         Pattern::Gradient(ref gradient) => gradient.transform(),
         Pattern::Ring(ref ring) => ring.transform(),
         Pattern::Checker(ref checker) => checker.transform(),
+        Pattern::UvChecker(ref checker) => checker.transform(),
+        Pattern::Marble(ref marble) => marble.transform(),
+        Pattern::Wood(ref wood) => wood.transform(),
+        Pattern::Image(ref image) => image.transform(),
+      }
+    }
+    fn color_at(&self, position: Tuple, body: &Body) -> Color {
+      match *self {
+        Pattern::Striped(ref striped) => striped.color_at(position, body),
+        Pattern::Gradient(ref gradient) => gradient.color_at(position, body),
+        Pattern::Ring(ref ring) => ring.color_at(position, body),
+        Pattern::Checker(ref checker) => checker.color_at(position, body),
+        Pattern::UvChecker(ref checker) => checker.color_at(position, body),
+        Pattern::Marble(ref marble) => marble.color_at(position, body),
+        Pattern::Wood(ref wood) => wood.color_at(position, body),
+        Pattern::Image(ref image) => image.color_at(position, body),
       }
     }
 }
@@ -77,6 +108,30 @@ impl From<Checker> for Pattern {
   }
 }
 
+impl From<UvChecker> for Pattern {
+  fn from(checker: UvChecker) -> Self {
+      Pattern::UvChecker(checker)
+  }
+}
+
+impl From<Marble> for Pattern {
+  fn from(marble: Marble) -> Self {
+      Pattern::Marble(marble)
+  }
+}
+
+impl From<Wood> for Pattern {
+  fn from(wood: Wood) -> Self {
+      Pattern::Wood(wood)
+  }
+}
+
+impl From<ImageTexture> for Pattern {
+  fn from(image: ImageTexture) -> Self {
+      Pattern::Image(image)
+  }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Striped {
     color_a: Color,
@@ -277,9 +332,282 @@ impl FuzzyEq<Checker> for Checker {
   }
 }
 
+// Checker laid out in texture space instead of object space, so the squares
+// follow the uv mapping of the body (no more lopsided checkers on spheres).
+// Its pattern space is the (u, v) plane, with u along x and v along y.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvChecker {
+  width: F,
+  height: F,
+  color_a: Color,
+  color_b: Color,
+  transform: Matrix<4>,
+}
+
+impl UvChecker {
+  pub fn with_colors(mut self, color_a: Color, color_b: Color) -> Self {
+    self.color_a = color_a;
+    self.color_b = color_b;
+    self
+  }
+
+  pub fn with_size(mut self, width: F, height: F) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+    self.transform = transform;
+    self
+  }
+}
+
+impl  Default for UvChecker {
+  fn default() -> Self {
+    Self {
+      width: 2.0,
+      height: 2.0,
+      color_a: Color::white(),
+      color_b: Color::black(),
+      transform: Matrix::identity(),
+    }
+  }
+}
+
+impl Stencil for UvChecker {
+  fn color_at_in_pattern_space(&self, position: Tuple) -> Color {
+    let u = (position.x * self.width).floor();
+    let v = (position.y * self.height).floor();
+    if (u + v) as isize % 2 == 0 {
+      self.color_a
+    } else {
+      self.color_b
+    }
+  }
+
+  fn transform(&self) -> Matrix<4> {
+    self.transform
+  }
+
+  fn color_at(&self, position: Tuple, body: &Body) -> Color {
+    let object_position = body.transform().inverse() * position;
+    let (u, v) = body.uv_at_in_object_space(object_position);
+    let pattern_position = self.transform().inverse() * Tuple::point(u, v, 0.0);
+    self.color_at_in_pattern_space(pattern_position)
+  }
+}
+
+impl FuzzyEq<UvChecker> for UvChecker {
+  fn fuzzy_eq(&self, other: UvChecker) -> bool {
+    self.width.fuzzy_eq(other.width)
+      && self.height.fuzzy_eq(other.height)
+      && self.color_a.fuzzy_eq(other.color_a)
+      && self.color_b.fuzzy_eq(other.color_b)
+  }
+}
+
+// Image wrapped around the body following its uv mapping, like UvChecker.
+// The image repeats outside of [0, 1), so scaling the pattern tiles it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTexture {
+  texture: Texture,
+  transform: Matrix<4>,
+}
+
+impl ImageTexture {
+  pub fn new(texture: Texture) -> Self {
+    ImageTexture {
+      texture,
+      transform: Matrix::identity(),
+    }
+  }
+
+  pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+    self.transform = transform;
+    self
+  }
+}
+
+impl Stencil for ImageTexture {
+  fn color_at_in_pattern_space(&self, position: Tuple) -> Color {
+    self.texture.color_at_uv(position.x.rem_euclid(1.0), position.y.rem_euclid(1.0))
+  }
+
+  fn transform(&self) -> Matrix<4> {
+    self.transform
+  }
+
+  fn color_at(&self, position: Tuple, body: &Body) -> Color {
+    let object_position = body.transform().inverse() * position;
+    let (u, v) = body.uv_at_in_object_space(object_position);
+    let pattern_position = self.transform().inverse() * Tuple::point(u, v, 0.0);
+    self.color_at_in_pattern_space(pattern_position)
+  }
+}
+
+impl FuzzyEq<ImageTexture> for ImageTexture {
+  fn fuzzy_eq(&self, other: ImageTexture) -> bool {
+    self.texture == other.texture && self.transform.fuzzy_eq(other.transform)
+  }
+}
+
+// Marble veins: a sine wave along x, bent by turbulence.
+// The wave is mapped on a ramp from color_a (troughs) to color_b (veins).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Marble {
+  color_a: Color,
+  color_b: Color,
+  frequency: F,
+  turbulence: F,
+  octaves: usize,
+  transform: Matrix<4>,
+}
+
+impl Marble {
+  pub fn with_colors(mut self, color_a: Color, color_b: Color) -> Self {
+    self.color_a = color_a;
+    self.color_b = color_b;
+    self
+  }
+
+  pub fn with_frequency(mut self, frequency: F) -> Self {
+    self.frequency = frequency;
+    self
+  }
+
+  pub fn with_turbulence(mut self, turbulence: F, octaves: usize) -> Self {
+    self.turbulence = turbulence;
+    self.octaves = octaves;
+    self
+  }
+
+  pub fn with_octaves(mut self, octaves: usize) -> Self {
+    self.octaves = octaves;
+    self
+  }
+
+  pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+    self.transform = transform;
+    self
+  }
+}
+
+impl  Default for Marble {
+  fn default() -> Self {
+    Self {
+      color_a: Color::white(),
+      color_b: Color::new(0.3, 0.3, 0.35),
+      frequency: 4.0,
+      turbulence: 5.0,
+      octaves: 6,
+      transform: Matrix::identity(),
+    }
+  }
+}
+
+impl Stencil for Marble {
+  fn color_at_in_pattern_space(&self, position: Tuple) -> Color {
+    let wave = (position.x * self.frequency + self.turbulence * turbulence(position, self.octaves)).sin();
+    let fraction = (wave + 1.0) / 2.0;
+    self.color_a + (self.color_b - self.color_a) * fraction
+  }
+
+  fn transform(&self) -> Matrix<4> {
+    self.transform
+  }
+}
+
+impl FuzzyEq<Marble> for Marble {
+  fn fuzzy_eq(&self, other: Marble) -> bool {
+    self.color_a.fuzzy_eq(other.color_a)
+      && self.color_b.fuzzy_eq(other.color_b)
+      && self.frequency.fuzzy_eq(other.frequency)
+      && self.turbulence.fuzzy_eq(other.turbulence)
+      && self.octaves == other.octaves
+  }
+}
+
+// Wood grain: concentric rings around the y axis, wobbled by turbulence.
+// Each ring ramps from color_a (early wood) to color_b (late wood).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wood {
+  color_a: Color,
+  color_b: Color,
+  rings: F,
+  turbulence: F,
+  octaves: usize,
+  transform: Matrix<4>,
+}
+
+impl Wood {
+  pub fn with_colors(mut self, color_a: Color, color_b: Color) -> Self {
+    self.color_a = color_a;
+    self.color_b = color_b;
+    self
+  }
+
+  pub fn with_rings(mut self, rings: F) -> Self {
+    self.rings = rings;
+    self
+  }
+
+  pub fn with_turbulence(mut self, turbulence: F, octaves: usize) -> Self {
+    self.turbulence = turbulence;
+    self.octaves = octaves;
+    self
+  }
+
+  pub fn with_octaves(mut self, octaves: usize) -> Self {
+    self.octaves = octaves;
+    self
+  }
+
+  pub fn with_transform(mut self, transform: Matrix<4>) -> Self {
+    self.transform = transform;
+    self
+  }
+}
+
+impl  Default for Wood {
+  fn default() -> Self {
+    Self {
+      color_a: Color::new(0.85, 0.6, 0.35),
+      color_b: Color::new(0.45, 0.25, 0.1),
+      rings: 8.0,
+      turbulence: 0.1,
+      octaves: 3,
+      transform: Matrix::identity(),
+    }
+  }
+}
+
+impl Stencil for Wood {
+  fn color_at_in_pattern_space(&self, position: Tuple) -> Color {
+    let distance = (position.x.powi(2) + position.z.powi(2)).sqrt();
+    let rings = (distance + self.turbulence * turbulence(position, self.octaves)) * self.rings;
+    let fraction = rings - rings.floor();
+    self.color_a + (self.color_b - self.color_a) * fraction
+  }
+
+  fn transform(&self) -> Matrix<4> {
+    self.transform
+  }
+}
+
+impl FuzzyEq<Wood> for Wood {
+  fn fuzzy_eq(&self, other: Wood) -> bool {
+    self.color_a.fuzzy_eq(other.color_a)
+      && self.color_b.fuzzy_eq(other.color_b)
+      && self.rings.fuzzy_eq(other.rings)
+      && self.turbulence.fuzzy_eq(other.turbulence)
+      && self.octaves == other.octaves
+  }
+}
+
   #[cfg(test)]
 mod tests {
-  use crate::{assert_fuzzy_eq, sphere::Sphere};
+  use crate::{assert_fuzzy_eq, assert_fuzzy_ne, plane::Plane, sphere::Sphere};
 
 use super::*;
 
@@ -510,6 +838,134 @@ use super::*;
       pattern.color_at(Tuple::point( 0.0, 0.0, 1.01), &body)
     );
   }
+  #[test]
+  fn uv_checker_pattern_in_texture_space() {
+    let pattern = UvChecker::default()
+      .with_size(2.0, 2.0)
+      .with_colors(Color::black(), Color::white());
+    let expectations = [
+      (0.0, 0.0, Color::black()),
+      (0.5, 0.0, Color::white()),
+      (0.0, 0.5, Color::white()),
+      (0.5, 0.5, Color::black()),
+      (1.0, 1.0, Color::black()),
+    ];
+    for (u, v, expected) in expectations {
+      assert_fuzzy_eq!(expected, pattern.color_at_in_pattern_space(Tuple::point(u, v, 0.0)));
+    }
+  }
+  #[test]
+  fn uv_checker_follows_spherical_mapping() {
+    let pattern = Pattern::from(
+      UvChecker::default()
+        .with_size(16.0, 8.0)
+        .with_colors(Color::black(), Color::white()),
+    );
+    let body = Body::from(Sphere::default());
+    let expectations = [
+      (Tuple::point(0.4315, 0.4670, 0.7719), Color::white()),
+      (Tuple::point(-0.9654, 0.2552, -0.0534), Color::black()),
+      (Tuple::point(0.1039, 0.7090, 0.6975), Color::white()),
+      (Tuple::point(-0.4986, -0.7856, -0.3663), Color::black()),
+      (Tuple::point(-0.0317, -0.9395, 0.3411), Color::black()),
+      (Tuple::point(0.4809, -0.7721, 0.4154), Color::black()),
+      (Tuple::point(0.0285, -0.9612, -0.2745), Color::black()),
+      (Tuple::point(-0.5734, -0.2162, -0.7903), Color::white()),
+      (Tuple::point(0.7688, -0.1470, 0.6223), Color::black()),
+      (Tuple::point(-0.7652, 0.2175, 0.6060), Color::black()),
+    ];
+    for (point, expected) in expectations {
+      assert_fuzzy_eq!(expected, pattern.color_at(point, &body));
+    }
+  }
+  #[test]
+  fn uv_checker_follows_planar_mapping_and_body_transform() {
+    let pattern = Pattern::from(
+      UvChecker::default()
+        .with_size(2.0, 2.0)
+        .with_colors(Color::black(), Color::white()),
+    );
+    let body = Body::from(Plane::default().with_transform(Matrix::scaling(2.0, 2.0, 2.0)));
+    assert_fuzzy_eq!(
+      Color::black(),
+      pattern.color_at(Tuple::point(0.5, 0.0, 0.5), &body)
+    );
+    assert_fuzzy_eq!(
+      Color::white(),
+      pattern.color_at(Tuple::point(1.5, 0.0, 0.5), &body)
+    );
+    assert_fuzzy_eq!(
+      Color::black(),
+      pattern.color_at(Tuple::point(2.5, 0.0, 2.5), &body)
+    );
+  }
+  #[test]
+  fn image_texture_follows_planar_mapping_and_tiles() {
+    let mut canvas = crate::canvas::Canvas::new(2, 2);
+    canvas.write_pixel(0, 1, Color::red());
+    canvas.write_pixel(1, 0, Color::blue());
+    let pattern = Pattern::from(
+      ImageTexture::new(Texture::new(canvas)).with_transform(Matrix::scaling(0.5, 0.5, 1.0)),
+    );
+    let body = Body::from(Plane::default());
+
+    // the bottom left texel maps to small u and v
+    assert_fuzzy_eq!(Color::red(), pattern.color_at(Tuple::point(0.1, 0.0, 0.1), &body));
+    assert_fuzzy_eq!(Color::blue(), pattern.color_at(Tuple::point(1.9, 0.0, 1.9), &body));
+    // the pattern repeats every two units
+    assert_fuzzy_eq!(Color::red(), pattern.color_at(Tuple::point(2.1, 0.0, -1.9), &body));
+  }
+  #[test]
+  fn marble_without_turbulence_is_a_sine_ramp_along_x() {
+    let pattern = Marble::default()
+      .with_colors(Color::black(), Color::white())
+      .with_frequency(1.0)
+      .with_turbulence(0.0, 0);
+    assert_fuzzy_eq!(
+      Color::new(0.5, 0.5, 0.5),
+      pattern.color_at_in_pattern_space(Tuple::point(0.0, 3.0, 2.0))
+    );
+    assert_fuzzy_eq!(
+      Color::white(),
+      pattern.color_at_in_pattern_space(Tuple::point(std::f64::consts::FRAC_PI_2, 0.0, 0.0))
+    );
+    assert_fuzzy_eq!(
+      Color::black(),
+      pattern.color_at_in_pattern_space(Tuple::point(-std::f64::consts::FRAC_PI_2, 1.0, 0.0))
+    );
+  }
+  #[test]
+  fn marble_turbulence_bends_the_veins() {
+    let straight = Marble::default().with_turbulence(0.0, 0);
+    let turbulent = Marble::default();
+    let point = Tuple::point(0.31, 0.47, 0.83);
+    assert_fuzzy_ne!(
+      straight.color_at_in_pattern_space(point),
+      turbulent.color_at_in_pattern_space(point)
+    );
+  }
+  #[test]
+  fn wood_without_turbulence_has_concentric_rings() {
+    let pattern = Pattern::from(
+      Wood::default()
+        .with_colors(Color::black(), Color::white())
+        .with_rings(1.0)
+        .with_turbulence(0.0, 0),
+    );
+    let body = Body::from(Sphere::default());
+    assert_fuzzy_eq!(
+      Color::new(0.25, 0.25, 0.25),
+      pattern.color_at(Tuple::point(0.25, 0.0, 0.0), &body)
+    );
+    assert_fuzzy_eq!(
+      Color::new(0.25, 0.25, 0.25),
+      pattern.color_at(Tuple::point(0.0, 5.0, -1.25), &body)
+    );
+    assert_fuzzy_eq!(
+      Color::new(0.5, 0.5, 0.5),
+      pattern.color_at(Tuple::point(0.3, 0.0, 0.4), &body)
+    );
+  }
 //   Scenario: Checkers should repeat in x
 // Given pattern ← checkers_pattern(white, black)
 // Then pattern_at(pattern, point(0, 0, 0)) = white
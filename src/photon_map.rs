@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use crate::body::Intersectable;
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::material::Reflective;
+use crate::ray::Ray;
+use crate::sampling::{glossy_direction, in_cone, Rng};
+use crate::tuple::Tuple;
+use crate::world::World;
+use crate::F;
+
+// Light carried from a light to a matte surface through one or more mirrors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Photon {
+    pub position: Tuple,
+    // where the photon was going when it landed
+    pub direction: Tuple,
+    pub power: Color,
+}
+
+// Photons stored as a balanced kd-tree laid out in a single vector: the median
+// of each slice along its splitting axis sits in its middle, the photons
+// before and after it being its two subtrees. Axes take turns with the depth.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+}
+
+fn coordinate(position: Tuple, axis: usize) -> F {
+    match axis {
+        0 => position.x,
+        1 => position.y,
+        _ => position.z,
+    }
+}
+
+impl PhotonMap {
+    pub fn new(photons: Vec<Photon>) -> Self {
+        let mut photons = photons;
+        PhotonMap::build(&mut photons, 0);
+        PhotonMap { photons }
+    }
+
+    fn build(photons: &mut [Photon], depth: usize) {
+        if photons.len() <= 1 {
+            return;
+        }
+        let axis = depth % 3;
+        let median = photons.len() / 2;
+        photons.select_nth_unstable_by(median, |a, b| {
+            coordinate(a.position, axis)
+                .partial_cmp(&coordinate(b.position, axis))
+                .unwrap_or(Ordering::Equal)
+        });
+        let (before, after) = photons.split_at_mut(median);
+        PhotonMap::build(before, depth + 1);
+        PhotonMap::build(&mut after[1..], depth + 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    // photons closer to position than radius, in no particular order
+    pub fn within(&self, position: Tuple, radius: F) -> Vec<&Photon> {
+        let mut found = Vec::new();
+        PhotonMap::search(&self.photons, 0, position, radius, &mut found);
+        found
+    }
+
+    fn search<'a>(photons: &'a [Photon], depth: usize, position: Tuple, radius: F, found: &mut Vec<&'a Photon>) {
+        if photons.is_empty() {
+            return;
+        }
+        let median = photons.len() / 2;
+        let photon = &photons[median];
+        if (photon.position - position).magnitude() <= radius {
+            found.push(photon);
+        }
+        let axis = depth % 3;
+        let offset = coordinate(position, axis) - coordinate(photon.position, axis);
+        // the subtree on the other side of the splitting plane only matters
+        // when the plane cuts the search sphere
+        if offset <= radius {
+            PhotonMap::search(&photons[..median], depth + 1, position, radius, found);
+        }
+        if offset >= -radius {
+            PhotonMap::search(&photons[median + 1..], depth + 1, position, radius, found);
+        }
+    }
+}
+
+// Caustics from a photon mapping pre-pass. Photons shot from the lights are
+// followed through reflective bodies and stored where they land on a matte
+// surface after at least one reflection; shading then adds the light they
+// bring around each hit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Caustics {
+    // photons shot from all the lights together
+    pub photons: usize,
+    // distance around a hit within which photons are gathered, larger is
+    // smoother but blurrier
+    pub radius: F,
+    pub map: PhotonMap,
+}
+
+impl Default for Caustics {
+    fn default() -> Self {
+        Caustics {
+            photons: 100_000,
+            radius: 0.1,
+            map: PhotonMap::default(),
+        }
+    }
+}
+
+impl Caustics {
+    pub fn with_photons(mut self, photons: usize) -> Self {
+        self.photons = photons;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: F) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    // Shoots the photons, uniformly around each light and shared between
+    // the lights by intensity. Point lights do not fade with distance here,
+    // so the power of a photon grows with the square of the path it travelled:
+    // light reflected by a flat mirror then matches what the mirror shows.
+    pub fn trace(mut self, world: &World) -> Self {
+        let total_intensity: F = world.lights.iter().map(|light| intensity(light.intensity)).sum();
+        let mut photons = Vec::new();
+        if total_intensity > 0.0 {
+            for (index, light) in world.lights.iter().enumerate() {
+                let shot = (self.photons as F * intensity(light.intensity) / total_intensity).round() as usize;
+                let mut rng = Rng::new(index as u64);
+                for _ in 0..shot {
+                    let direction = in_cone(Tuple::vector(0.0, 1.0, 0.0), std::f64::consts::PI, rng.next_f(), rng.next_f());
+                    let ray = Ray::new(light.position, direction);
+                    let power = light.intensity * (4.0 * std::f64::consts::PI / shot as F);
+                    if let Some(photon) = Caustics::follow(world, ray, power, &mut rng) {
+                        photons.push(photon);
+                    }
+                }
+            }
+        }
+        self.map = PhotonMap::new(photons);
+        self
+    }
+
+    // The photon where the ray lands after one or more reflections, if it
+    // does. At each hit the photon is reflected with the reflectance of the
+    // material as probability, otherwise it stays there.
+    fn follow(world: &World, ray: Ray, power: Color, rng: &mut Rng) -> Option<Photon> {
+        let mut ray = ray;
+        let mut distance = 0.0;
+        for reflections in 0..=world.reflection_limit {
+            let xs = world.intersect(ray);
+            let hit = xs.hit()?;
+            let c = hit.get_computed();
+            let material = hit.body.material();
+            distance += hit.t;
+            if rng.next_f() >= material.reflectance(c.eyev.dot(c.normalv)) {
+                if reflections == 0 {
+                    // direct light, already in the shading
+                    return None;
+                }
+                return Some(Photon {
+                    position: c.point,
+                    direction: ray.direction,
+                    power: power * (distance * distance),
+                });
+            }
+            let direction = glossy_direction(material.roughness(), c.reflectv, c.normalv, rng);
+            ray = Ray::new(c.over_point, direction);
+        }
+        None
+    }
+
+    // Light brought by the photons landing around position on the side of
+    // normalv, as seen on a matte surface of the given albedo
+    pub fn color_at(&self, albedo: Color, position: Tuple, normalv: Tuple) -> Color {
+        let power = self
+            .map
+            .within(position, self.radius)
+            .iter()
+            .filter(|photon| photon.direction.dot(normalv) < 0.0)
+            .fold(Color::black(), |power, photon| power + photon.power);
+        albedo * power * (1.0 / (std::f64::consts::PI * self.radius * self.radius))
+    }
+}
+
+// the settings only, the map is traced from them
+impl FuzzyEq<Caustics> for Caustics {
+    fn fuzzy_eq(&self, other: Caustics) -> bool {
+        self.photons == other.photons && self.radius.fuzzy_eq(other.radius)
+    }
+}
+
+fn intensity(color: Color) -> F {
+    color.red + color.green + color.blue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+    use crate::lights::PointLight;
+    use crate::material::{Material, Phong};
+    use crate::matrix::Matrix;
+    use crate::plane::Plane;
+
+    #[test]
+    fn the_kd_tree_finds_the_photons_within_the_radius() {
+        let mut rng = Rng::new(3);
+        let photons: Vec<Photon> = (0..500)
+            .map(|_| Photon {
+                position: Tuple::point(rng.next_f(), rng.next_f(), rng.next_f()),
+                direction: Tuple::vector(0.0, -1.0, 0.0),
+                power: Color::white(),
+            })
+            .collect();
+        let center = Tuple::point(0.4, 0.5, 0.6);
+        let mut expected: Vec<Photon> = photons
+            .iter()
+            .filter(|photon| (photon.position - center).magnitude() <= 0.2)
+            .copied()
+            .collect();
+        let map = PhotonMap::new(photons);
+
+        let mut found: Vec<Photon> = map.within(center, 0.2).into_iter().copied().collect();
+
+        let by_x = |a: &Photon, b: &Photon| a.position.x.partial_cmp(&b.position.x).unwrap();
+        expected.sort_by(by_x);
+        found.sort_by(by_x);
+        assert_eq!(map.len(), 500);
+        assert!(!found.is_empty());
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn a_flat_mirror_casts_the_light_it_reflects() {
+        // a mirror ceiling above a matte floor, the light in between
+        let ceiling = Plane::default()
+            .with_transform(Matrix::translation(0.0, 4.0, 0.0))
+            .with_material(Material::from(Phong::default().with_reflective(1.0)));
+        let floor = Plane::default();
+        let light = PointLight::new(Tuple::point(0.0, 2.0, 0.0), Color::white());
+        let w = World::new(vec![Body::from(ceiling), Body::from(floor)], vec![light]);
+
+        let caustics = Caustics::default().with_photons(50_000).with_radius(1.0).trace(&w);
+
+        // only the photons that went up came down on the floor
+        assert!(caustics.map.len() > 22_500 && caustics.map.len() < 27_500);
+        assert!(caustics.map.within(Tuple::point(0.0, 0.0, 0.0), 1.0).iter().all(|photon| photon.position.y.abs() < 1e-6));
+        // like the mirror image of the light, straight above the floor
+        let color = caustics.color_at(Color::white(), Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+        assert!((color.red - 1.0).abs() < 0.15);
+        let seen_from_below = caustics.color_at(Color::white(), Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        assert_eq!(seen_from_below, Color::black());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::animation::Interpolate;
 use crate::body::*;
 use crate::fuzzy_eq::FuzzyEq;
 use crate::material::*;
@@ -6,10 +7,12 @@ use crate::ray::*;
 use crate::EPSILON;
 use crate::tuple::*;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Plane {
     pub transform: Matrix<4>,
     pub material: Material,
+    // where the body ends up when the shutter closes, None when it stands still
+    pub end_transform: Option<Matrix<4>>,
 }
 
 impl Default for Plane {
@@ -17,6 +20,7 @@ impl Default for Plane {
         Self {
             transform: Matrix::identity(),
             material: Default::default(),
+            end_transform: None,
         }
     }
 }
@@ -26,6 +30,7 @@ impl Plane {
         Plane {
             material,
             transform,
+            end_transform: None,
         }
     }
 
@@ -37,6 +42,10 @@ impl Plane {
         self.transform = transform;
         self
     }
+    pub fn with_end_transform(mut self, end_transform: Matrix<4>) -> Self {
+        self.end_transform = Some(end_transform);
+        self
+    }
 }
 
 impl Intersectable for Plane {
@@ -46,24 +55,42 @@ impl Intersectable for Plane {
         else {
             let t = -object_space_ray.origin.y / object_space_ray.direction.y;
             vec![
-                (t, Body::from(*self))
+                (t, Body::from(self.clone()))
             ]
         }
     }
     fn normal_at_in_object_space(&self, _object_space_point: Tuple) -> Tuple {
         Tuple::vector(0.0, 1.0, 0.0)
     }
+    fn uv_at_in_object_space(&self, object_space_point: Tuple) -> (crate::F, crate::F) {
+        // planar mapping: the texture repeats every unit along x and z
+        (object_space_point.x.rem_euclid(1.0), object_space_point.z.rem_euclid(1.0))
+    }
+    fn tangent_at_in_object_space(&self, _object_space_point: Tuple) -> Tuple {
+        Tuple::vector(1.0, 0.0, 0.0)
+    }
     fn material(&self) -> Material {
-        self.material
+        self.material.clone()
     }
     fn transform(&self) -> Matrix<4> {
         self.transform
     }
+    fn at_time(&self, time: crate::F) -> Body {
+        match self.end_transform {
+            Some(end_transform) => Body::from(Plane::new(
+                self.material.clone(),
+                self.transform.interpolate(&end_transform, time),
+            )),
+            None => Body::from(self.clone()),
+        }
+    }
 }
 
 impl FuzzyEq<&Plane> for Plane {
     fn fuzzy_eq(&self, other: &Plane) -> bool {
-      self.transform.fuzzy_eq(other.transform) && self.material.fuzzy_eq(other.material)
+      self.transform.fuzzy_eq(other.transform)
+        && self.material.fuzzy_eq(other.material.clone())
+        && self.end_transform.fuzzy_eq(other.end_transform)
     }
   }
 
@@ -123,4 +150,23 @@ use crate::ray::Ray;
     assert_eq!(ts[0].0, 1.0); // t
     assert_eq!(ts[0].1, Body::from(p)) // body
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn planar_mapping_on_a_3d_point() {
+    let p = Plane::default();
+    let points = [
+      (Tuple::point(0.25, 0.0, 0.5), (0.25, 0.5)),
+      (Tuple::point(0.25, 0.0, -0.25), (0.25, 0.75)),
+      (Tuple::point(0.25, 0.5, -0.25), (0.25, 0.75)),
+      (Tuple::point(1.25, 0.0, 0.5), (0.25, 0.5)),
+      (Tuple::point(0.25, 0.0, -1.75), (0.25, 0.25)),
+      (Tuple::point(1.0, 0.0, -1.0), (0.0, 0.0)),
+      (Tuple::point(0.0, 0.0, 0.0), (0.0, 0.0)),
+    ];
+    for (point, (u, v)) in points {
+      let (actual_u, actual_v) = p.uv_at_in_object_space(point);
+      assert!(actual_u.fuzzy_eq(u));
+      assert!(actual_v.fuzzy_eq(v));
+    }
+  }
+}
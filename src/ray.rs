@@ -6,11 +6,18 @@ use crate::F;
 pub struct Ray {
 	pub origin: Tuple,
 	pub direction: Tuple,
+	// when the ray is cast during the shutter interval, moving bodies are
+	// frozen at this time, see Intersectable::at_time
+	pub time: F,
 }
 
 impl Ray{
 	pub fn new(origin: Tuple, direction: Tuple) -> Self {
-		Ray {origin, direction}
+		Ray {origin, direction, time: 0.0}
+	}
+	pub fn with_time(mut self, time: F) -> Self {
+		self.time = time;
+		self
 	}
 	pub fn	position(&self, t: F) -> Tuple {
 		self.origin + self.direction * t
@@ -19,6 +26,7 @@ impl Ray{
 		Ray {
 			origin: m * self.origin,
 			direction: m * self.direction,
+			time: self.time,
 		}
 	}
 }
@@ -66,4 +74,12 @@ mod tests{
 		assert_eq!(r2.origin, Tuple::point(2.0, 6.0, 12.0));
 		assert_eq!(r2.direction, Tuple::vector(0.0, 3.0, 0.0));
 	}
-}
\ No newline at end of file
+	#[test]
+	fn	transforming_a_ray_keeps_its_time()
+	{
+		let r = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0)).with_time(0.25);
+		let r2 = r.transform(Matrix::translation(3.0, 4.0, 5.0));
+		assert_eq!(r.time, 0.25);
+		assert_eq!(r2.time, 0.25);
+	}
+}
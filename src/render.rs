@@ -0,0 +1,386 @@
+use std::borrow::Cow;
+use std::time::Instant;
+
+use rayon::prelude::*;
+
+use crate::aov::Aov;
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::outline::surface_at;
+use crate::stats::RenderStats;
+use crate::world::World;
+use crate::F;
+
+// Traces every pixel of the camera in parallel, returning linear colors.
+pub fn render(world: &World, camera: &Camera) -> Canvas {
+    render_with_progress(world, camera, || {})
+}
+
+// Same as render, calling progress once per traced pixel, see Camera::pixel_count.
+pub fn render_with_progress<P>(world: &World, camera: &Camera, progress: P) -> Canvas
+where
+    P: Fn() + Sync,
+{
+    render_with_stats(world, camera, progress).0
+}
+
+// Same as render_with_progress, also returning what it took to render.
+pub fn render_with_stats<P>(world: &World, camera: &Camera, progress: P) -> (Canvas, RenderStats)
+where
+    P: Fn() + Sync,
+{
+    let mut stats = RenderStats::default();
+    let world = traced(world, &mut stats);
+    let canvas = render_camera(&world, camera, &progress, &mut stats);
+    (canvas, stats)
+}
+
+// Same as render_with_stats, also returning the auxiliary outputs of the
+// camera, which line up with the render.
+pub fn render_with_aovs<P>(world: &World, camera: &Camera, progress: P) -> (Canvas, Vec<(Aov, Canvas)>, RenderStats)
+where
+    P: Fn() + Sync,
+{
+    let mut stats = RenderStats::default();
+    let world = traced(world, &mut stats);
+    let canvas = render_camera(&world, camera, &progress, &mut stats);
+    let start = Instant::now();
+    let aovs = aovs_of_camera(&world, camera);
+    if !aovs.is_empty() {
+        stats.add_phase("auxiliary outputs", start.elapsed());
+    }
+    (canvas, aovs, stats)
+}
+
+// The auxiliary outputs of the camera, one image each in the same order. One
+// ray is cast through the center of each pixel, stereo eyes are laid out as
+// in the render.
+pub fn render_aovs(world: &World, camera: &Camera) -> Vec<(Aov, Canvas)> {
+    let world = traced(world, &mut RenderStats::default());
+    aovs_of_camera(&world, camera)
+}
+
+// the world with its caustics photons traced, when it has some
+fn traced<'a>(world: &'a World, stats: &mut RenderStats) -> Cow<'a, World> {
+    if world.caustics.is_some() {
+        let start = Instant::now();
+        let traced = world.clone().with_traced_photons();
+        stats.add_phase("photons", start.elapsed());
+        Cow::Owned(traced)
+    } else {
+        Cow::Borrowed(world)
+    }
+}
+
+fn render_camera<P>(world: &World, camera: &Camera, progress: &P, stats: &mut RenderStats) -> Canvas
+where
+    P: Fn() + Sync,
+{
+    match camera.stereo {
+        Some(stereo) => {
+            let (left, right) = camera.stereo_pair(stereo.interocular, stereo.convergence);
+            stereo.combine(
+                &render_view(world, &left, progress, stats),
+                &render_view(world, &right, progress, stats),
+            )
+        }
+        None => render_view(world, camera, progress, stats),
+    }
+}
+
+fn aovs_of_camera(world: &World, camera: &Camera) -> Vec<(Aov, Canvas)> {
+    match camera.stereo {
+        Some(stereo) => {
+            let (left, right) = camera.stereo_pair(stereo.interocular, stereo.convergence);
+            camera
+                .aovs
+                .iter()
+                .map(|aov| (*aov, stereo.combine(&aov_of_view(world, &left, *aov), &aov_of_view(world, &right, *aov))))
+                .collect()
+        }
+        None => camera.aovs.iter().map(|aov| (*aov, aov_of_view(world, camera, *aov))).collect(),
+    }
+}
+
+fn aov_of_view(world: &World, camera: &Camera, aov: Aov) -> Canvas {
+    let colors: Vec<_> = (0..camera.vsize)
+        .into_par_iter()
+        .flat_map_iter(|y| (0..camera.hsize).map(move |x| aov.color_at(world, camera.ray_for_pixel(x, y))))
+        .collect();
+    let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+    for (index, color) in colors.into_iter().enumerate() {
+        canvas.write_pixel(index % camera.hsize, index / camera.hsize, color);
+    }
+    canvas
+}
+
+fn render_view<P>(world: &World, camera: &Camera, progress: &P, stats: &mut RenderStats) -> Canvas
+where
+    P: Fn() + Sync,
+{
+    let start = Instant::now();
+    let rows: Vec<_> = (0..camera.vsize)
+        .into_par_iter()
+        .map(|y| {
+            // counted per row so the workers never wait on each other
+            let mut row_stats = RenderStats::default();
+            let row = (0..camera.hsize)
+                .map(|x| {
+                    let rays = camera.rays_for_pixel(x, y);
+                    let sum = rays.iter().fold(Color::black(), |sum, ray| {
+                        let mut ray_stats = RenderStats::default();
+                        let color = camera.integrator.color_at(world, *ray, &mut ray_stats);
+                        row_stats.add_camera_ray(&ray_stats);
+                        sum + color
+                    });
+                    progress();
+                    sum * (1.0 / rays.len() as F)
+                })
+                .collect::<Vec<_>>();
+            (row, row_stats)
+        })
+        .collect();
+
+    let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+    for (y, (row, row_stats)) in rows.into_iter().enumerate() {
+        for (x, color) in row.into_iter().enumerate() {
+            canvas.write_pixel(x, y, color);
+        }
+        stats.merge(&row_stats);
+    }
+    stats.add_phase("trace", start.elapsed());
+    if let Some(outline) = camera.outline {
+        let start = Instant::now();
+        // a second, cheap pass: one ray per pixel without any shading
+        let surfaces: Vec<_> = (0..camera.vsize)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                (0..camera.hsize).map(move |x| surface_at(world, camera.ray_for_pixel(x, y)))
+            })
+            .collect();
+        outline.draw(&mut canvas, &surfaces);
+        stats.add_phase("outline", start.elapsed());
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_fuzzy_eq;
+    use crate::body::Body;
+    use crate::fuzzy_eq::FuzzyEq;
+    use crate::lights::PointLight;
+    use crate::material::{Material, Phong};
+    use crate::matrix::Matrix;
+    use crate::motion_blur::MotionBlur;
+    use crate::outline::Outline;
+    use crate::photon_map::Caustics;
+    use crate::plane::Plane;
+    use crate::sphere::Sphere;
+    use crate::stereo::Stereo;
+    use crate::tuple::Tuple;
+    use std::f64::consts::PI;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn rendering_a_world_with_a_camera() {
+        let light = PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let material = Phong {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Phong::default()
+        };
+        let s1 = Body::from(Sphere::default().with_material(Material::from(material)));
+        let s2 = Body::from(Sphere::default().with_transform(Matrix::scaling(0.5, 0.5, 0.5)));
+        let world = World::new(vec![s1, s2], vec![light]);
+        let camera = Camera::new(11, 11, PI / 2.0).view_transform(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let pixels = AtomicUsize::new(0);
+
+        let image = render_with_progress(&world, &camera, || {
+            pixels.fetch_add(1, Ordering::Relaxed);
+        });
+
+        assert_fuzzy_eq!(image.color_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(pixels.load(Ordering::Relaxed), 121);
+        assert_eq!(render(&world, &camera), image);
+    }
+
+    #[test]
+    fn rendering_auxiliary_outputs() {
+        let world = World::new(vec![Body::from(Sphere::default())], vec![]);
+        let camera = Camera::new(11, 11, PI / 2.0)
+            .view_transform(Tuple::point(0.0, 0.0, -5.0), Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0))
+            .with_aov(Aov::Depth)
+            .with_aov(Aov::ObjectId)
+            .with_aov(Aov::Normal)
+            .with_aov(Aov::Depth);
+
+        let aovs = render_aovs(&world, &camera);
+
+        assert_eq!(aovs.len(), 3);
+        let (aov, depth) = &aovs[0];
+        assert_eq!(*aov, Aov::Depth);
+        assert_eq!(depth.color_at(5, 5), Color::new(4.0, 4.0, 4.0));
+        assert_eq!(depth.color_at(0, 0), Color::black());
+        let (aov, ids) = &aovs[1];
+        assert_eq!(*aov, Aov::ObjectId);
+        assert_eq!(ids.color_at(5, 5), Color::white());
+        let (aov, normals) = &aovs[2];
+        assert_eq!(*aov, Aov::Normal);
+        let normals = aov.displayable(normals);
+        assert_eq!(normals.color_at(5, 5), Color::new(0.5, 0.5, 0.0));
+        assert_eq!(normals.color_at(0, 0), Color::black());
+    }
+
+    #[test]
+    fn auxiliary_outputs_line_up_with_the_render() {
+        // stereo eyes side by side
+        let world = World::new(vec![Body::from(Sphere::default())], vec![]);
+        let camera = Camera::new(11, 11, PI / 2.0)
+            .view_transform(Tuple::point(0.0, 0.0, -5.0), Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0))
+            .with_stereo(Stereo::new(0.2, 5.0))
+            .with_aov(Aov::ObjectId);
+
+        let (image, aovs, _) = render_with_aovs(&world, &camera, || {});
+
+        let (_, ids) = &aovs[0];
+        assert_eq!((ids.width, ids.height), (image.width, image.height));
+        assert_eq!(ids.color_at(5, 5), Color::white());
+        assert_eq!(ids.color_at(16, 5), Color::white());
+
+        // reflections of a floor lit by a mirror ceiling show its caustics
+        let ceiling = Plane::default()
+            .with_transform(Matrix::translation(0.0, 4.0, 0.0))
+            .with_material(Material::from(Phong::default().with_reflective(1.0)));
+        let light = PointLight::new(Tuple::point(0.0, 2.0, 0.0), Color::white());
+        let world = World::new(vec![Body::from(ceiling), Body::from(Plane::default())], vec![light]);
+        let camera = Camera::new(1, 1, PI / 2.0)
+            .view_transform(Tuple::point(0.0, 1.0, 0.0), Tuple::point(0.0, 4.0, 0.0), Tuple::vector(0.0, 0.0, 1.0))
+            .with_aov(Aov::Reflection);
+        let caustics = Caustics::default().with_photons(20_000).with_radius(1.0);
+
+        let without = render_aovs(&world, &camera);
+        let with = render_aovs(&world.clone().with_caustics(caustics), &camera);
+
+        assert!(with[0].1.color_at(0, 0).red > without[0].1.color_at(0, 0).red + 0.5);
+    }
+
+    #[test]
+    fn rendering_with_an_outline() {
+        let world = World::new(
+            vec![Body::from(Sphere::default())],
+            vec![PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))],
+        );
+        let red = Color::new(1.0, 0.0, 0.0);
+        let camera = Camera::new(21, 21, PI / 3.0)
+            .view_transform(
+                Tuple::point(0.0, 0.0, -5.0),
+                Tuple::point(0.0, 0.0, 0.0),
+                Tuple::vector(0.0, 1.0, 0.0),
+            )
+            .with_outline(Outline::default().with_color(red));
+
+        let image = render(&world, &camera);
+
+        // the middle row crosses the silhouette once on each side
+        let inked: Vec<usize> = (0..21).filter(|x| image.color_at(*x, 10) == red).collect();
+        assert_eq!(inked.len(), 2);
+        assert_eq!(image.color_at(inked[0] - 1, 10), Color::black());
+        assert_ne!(image.color_at(inked[0] + 1, 10), red);
+    }
+
+    #[test]
+    fn rendering_a_stereo_pair() {
+        let world = World::new(
+            vec![Body::from(Sphere::default())],
+            vec![PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))],
+        );
+        let camera = Camera::new(11, 11, PI / 3.0).view_transform(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let stereo = Stereo::new(0.5, 4.0);
+        let (left, right) = camera.stereo_pair(0.5, 4.0);
+        let pixels = AtomicUsize::new(0);
+
+        let image = render_with_progress(&world, &camera.clone().with_stereo(stereo), || {
+            pixels.fetch_add(1, Ordering::Relaxed);
+        });
+
+        assert_eq!(image, stereo.combine(&render(&world, &left), &render(&world, &right)));
+        assert_eq!(pixels.load(Ordering::Relaxed), camera.with_stereo(stereo).pixel_count());
+        assert_eq!(pixels.load(Ordering::Relaxed), 242);
+    }
+
+    #[test]
+    fn rendering_with_motion_blur() {
+        let moving = Sphere::default()
+            .with_transform(Matrix::translation(-3.0, 0.0, 0.0))
+            .with_end_transform(Matrix::translation(3.0, 0.0, 0.0));
+        let world = World::new(
+            vec![Body::from(moving)],
+            vec![PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))],
+        );
+        let camera = Camera::new(11, 11, PI / 3.0).view_transform(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(0.0, 1.0, 0.0),
+        );
+        let blurred_camera = camera.clone().with_motion_blur(MotionBlur::default().with_samples(6));
+
+        let still = render(&world, &camera);
+        let blurred = render(&world, &blurred_camera);
+
+        // the sphere only crosses the middle of the image while the shutter is open
+        assert_eq!(still.color_at(5, 5), Color::black());
+        assert_ne!(blurred.color_at(5, 5), Color::black());
+        let rays = blurred_camera.rays_for_pixel(5, 5);
+        let hits = rays.iter().filter(|ray| world.intersect(**ray).hit().is_some()).count();
+        assert_eq!(hits, 2);
+        let average = rays
+            .iter()
+            .fold(Color::black(), |sum, ray| sum + world.color_at(*ray))
+            * (1.0 / 6.0);
+        assert_fuzzy_eq!(blurred.color_at(5, 5), average);
+    }
+
+    #[test]
+    fn rendering_with_stats() {
+        let mirror = Phong::default().with_reflective(0.5);
+        let world = World::new(
+            vec![
+                Body::from(Sphere::default().with_material(Material::from(mirror))),
+                Body::from(Plane::default().with_transform(Matrix::translation(0.0, -1.0, 0.0))),
+            ],
+            vec![PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))],
+        );
+        let camera = Camera::new(11, 11, PI / 3.0).view_transform(
+            Tuple::point(0.0, 0.0, -5.0),
+            Tuple::point(0.0, 0.0, 0.0),
+            Tuple::vector(0.0, 1.0, 0.0),
+        );
+
+        let (image, stats) = render_with_stats(&world, &camera, || {});
+
+        assert_eq!(image, render(&world, &camera));
+        assert_eq!(stats.primary_rays, 121);
+        assert!(stats.reflection_rays > 0);
+        assert!(stats.shadow_rays > 0);
+        // every ray is tested against both bodies
+        assert_eq!(stats.sphere_tests, stats.rays());
+        assert_eq!(stats.plane_tests, stats.rays());
+        assert!(stats.max_depth >= 1);
+        // only the rays hitting the mirror go deeper than the first hit
+        assert!(stats.average_depth() > 0.0 && stats.average_depth() < 1.0);
+        assert_eq!(stats.phases.len(), 1);
+        assert_eq!(stats.phases[0].0, "trace");
+    }
+}
@@ -0,0 +1,143 @@
+use std::f64::consts::PI;
+
+use crate::ray::Ray;
+use crate::tuple::Tuple;
+use crate::F;
+
+// Small deterministic random number generator (SplitMix64). Renders are
+// reproducible: the same seed always gives the same sequence, whatever the
+// thread tracing the pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    // seeded from where a ray starts and where it goes, so that secondary rays
+    // get their own sequence without any state being passed around
+    pub fn from_tuples(a: Tuple, b: Tuple) -> Self {
+        Rng::from_values(&[a.x, a.y, a.z, b.x, b.y, b.z])
+    }
+
+    // camera rays of the same pixel only differ by their time with motion blur
+    pub fn from_ray(ray: Ray) -> Self {
+        let (o, d) = (ray.origin, ray.direction);
+        Rng::from_values(&[o.x, o.y, o.z, d.x, d.y, d.z, ray.time])
+    }
+
+    fn from_values(values: &[F]) -> Self {
+        let seed = values.iter().fold(0x9e37_79b9_7f4a_7c15_u64, |seed, value| {
+            (seed ^ value.to_bits()).wrapping_mul(0x0100_0000_01b3).rotate_left(29)
+        });
+        Rng::new(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f(&mut self) -> F {
+        (self.next_u64() >> 11) as F / (1u64 << 53) as F
+    }
+}
+
+// Two unit vectors perpendicular to axis and to each other
+pub fn orthonormal_basis(axis: Tuple) -> (Tuple, Tuple) {
+    let helper = if axis.x.abs() > 0.9 {
+        Tuple::vector(0.0, 1.0, 0.0)
+    } else {
+        Tuple::vector(1.0, 0.0, 0.0)
+    };
+    let tangent = axis.cross(helper).normalize();
+    let bitangent = axis.cross(tangent);
+    (tangent, bitangent)
+}
+
+// A direction picked uniformly within the cone of the given half angle around
+// axis, u and v being uniform in [0, 1)
+pub fn in_cone(axis: Tuple, half_angle: F, u: F, v: F) -> Tuple {
+    let cos_theta = 1.0 - u * (1.0 - half_angle.cos());
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    let (tangent, bitangent) = orthonormal_basis(axis);
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta)
+        .normalize()
+}
+
+// A direction above the surface of the given normal, more likely close to
+// it following the cosine of their angle, like the light a matte surface sends
+// back. u and v are uniform in [0, 1).
+pub fn cosine_weighted(normal: Tuple, u: F, v: F) -> Tuple {
+    let radius = u.sqrt();
+    let phi = 2.0 * PI * v;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (radius * phi.cos()) + bitangent * (radius * phi.sin()) + normal * (1.0 - u).sqrt())
+        .normalize()
+}
+
+// The mirror direction reflectv, perturbed within a cone wider as the
+// roughness grows up to the whole hemisphere. Directions going through the
+// surface are mirrored back above it.
+pub fn glossy_direction(roughness: F, reflectv: Tuple, normalv: Tuple, rng: &mut Rng) -> Tuple {
+    if roughness <= 0.0 {
+        return reflectv;
+    }
+    let half_angle = roughness.min(1.0).powi(2) * PI / 2.0;
+    let direction = in_cone(reflectv, half_angle, rng.next_f(), rng.next_f());
+    let below = direction.dot(normalv);
+    if below < 0.0 { direction - normalv * (2.0 * below) } else { direction }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_eq::FuzzyEq;
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let numbers: Vec<F> = (0..100).map(|_| a.next_f()).collect();
+
+        assert!(numbers.iter().all(|n| *n >= 0.0 && *n < 1.0));
+        assert!(numbers.iter().all(|n| *n == b.next_f()));
+        let mean = numbers.iter().sum::<F>() / 100.0;
+        assert!((mean - 0.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn directions_stay_within_the_cone() {
+        let axis = Tuple::vector(1.0, 1.0, 0.0).normalize();
+        let mut rng = Rng::from_tuples(Tuple::point(1.0, 2.0, 3.0), axis);
+
+        for _ in 0..100 {
+            let direction = in_cone(axis, 0.3, rng.next_f(), rng.next_f());
+            assert!(direction.magnitude().fuzzy_eq(1.0));
+            assert!(direction.dot(axis) >= 0.3_f64.cos() - 1e-9);
+        }
+        assert_eq!(in_cone(axis, 0.3, 0.0, 0.7), axis);
+    }
+
+    #[test]
+    fn cosine_weighted_directions_favour_the_normal() {
+        let normal = Tuple::vector(0.0, 0.0, -1.0);
+        let mut rng = Rng::new(7);
+        let cosines: Vec<F> = (0..1000)
+            .map(|_| cosine_weighted(normal, rng.next_f(), rng.next_f()).dot(normal))
+            .collect();
+
+        assert!(cosines.iter().all(|cos| *cos >= 0.0));
+        // the mean cosine is 2/3 against 1/2 for uniform directions
+        let mean = cosines.iter().sum::<F>() / 1000.0;
+        assert!((mean - 2.0 / 3.0).abs() < 0.03);
+    }
+}
@@ -1,14 +1,22 @@
+use crate::animation::Interpolate;
 use crate::body::*;
 use crate::fuzzy_eq::FuzzyEq;
 use crate::material::*;
 use crate::matrix::*;
+use crate::media::Medium;
 use crate::ray::*;
 use crate::tuple::*;
+use crate::EPSILON;
+use std::f64::consts::PI;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Sphere {
     pub transform: Matrix<4>,
     pub material: Material,
+    // where the body ends up when the shutter closes, None when it stands still
+    pub end_transform: Option<Matrix<4>>,
+    // fills the sphere with smoke, its surface is then invisible
+    pub medium: Option<Medium>,
 }
 
 impl Default for Sphere {
@@ -16,6 +24,8 @@ impl Default for Sphere {
         Self {
             transform: Matrix::identity(),
             material: Default::default(),
+            end_transform: None,
+            medium: None,
         }
     }
 }
@@ -25,6 +35,8 @@ impl Sphere {
         Sphere {
             material,
             transform,
+            end_transform: None,
+            medium: None,
         }
     }
 
@@ -36,14 +48,35 @@ impl Sphere {
         self.transform = transform;
         self
     }
+    pub fn with_end_transform(mut self, end_transform: Matrix<4>) -> Self {
+        self.end_transform = Some(end_transform);
+        self
+    }
+    pub fn with_medium(mut self, medium: Medium) -> Self {
+        self.medium = Some(medium);
+        self
+    }
 }
 
 impl FuzzyEq<&Sphere> for Sphere {
 	fn fuzzy_eq(&self, other: &Sphere) -> bool {
-	  self.transform.fuzzy_eq(other.transform) && self.material.fuzzy_eq(other.material)
+	  self.transform.fuzzy_eq(other.transform)
+        && self.material.fuzzy_eq(other.material.clone())
+        && self.end_transform.fuzzy_eq(other.end_transform)
+        && self.medium.fuzzy_eq(other.medium)
 	}
   }
 
+// spherical mapping of a direction: u goes around the y axis, v from the south to the north pole
+pub fn spherical_map(direction: Tuple) -> (crate::F, crate::F) {
+    let theta = direction.x.atan2(direction.z);
+    let phi = (direction.y / direction.magnitude()).acos();
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
 impl Intersectable for Sphere {
     fn intersect_in_object_space(&self, object_space_ray: Ray) -> Vec<(crate::F, Body)> {
         // let object_space_ray = ray.transform(self.transform.inverse());
@@ -60,20 +93,42 @@ impl Intersectable for Sphere {
             let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
             let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
             vec![
-                (t1, Body::from(*self)),
-                (t2, Body::from(*self)),
+                (t1, Body::from(self.clone())),
+                (t2, Body::from(self.clone())),
             ]
         }
     }
     fn normal_at_in_object_space(&self, object_space_point: Tuple) -> Tuple {
         (object_space_point - Tuple::new(0.0, 0.0, 0.0, 1.0)).normalize()
     }
+    fn uv_at_in_object_space(&self, object_space_point: Tuple) -> (crate::F, crate::F) {
+        spherical_map(object_space_point - Tuple::point(0.0, 0.0, 0.0))
+    }
+    fn tangent_at_in_object_space(&self, object_space_point: Tuple) -> Tuple {
+        let tangent = Tuple::vector(-object_space_point.z, 0.0, object_space_point.x);
+        if tangent.magnitude() < EPSILON {
+            // u is undefined on the poles
+            Tuple::vector(1.0, 0.0, 0.0)
+        } else {
+            tangent.normalize()
+        }
+    }
     fn material(&self) -> Material {
-        self.material
+        self.material.clone()
     }
     fn transform(&self) -> Matrix<4> {
         self.transform
     }
+    fn at_time(&self, time: crate::F) -> Body {
+        match self.end_transform {
+            Some(end_transform) => Body::from(Sphere {
+                transform: self.transform.interpolate(&end_transform, time),
+                end_transform: None,
+                ..self.clone()
+            }),
+            None => Body::from(self.clone()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -82,7 +137,6 @@ mod tests {
     use crate::color::*;
     use crate::fuzzy_eq::FuzzyEq;
     use crate::F;
-    use std::f64::consts::PI;
     // use crate::{tuple::Tuple, matrix::Matrix};
     #[test]
     fn ray_intersect_sphere_at_two_point() {
@@ -258,8 +312,63 @@ mod tests {
         .with_specular(0.95)
         .with_shininess(400.0));
 
-        let s = Sphere::default().with_material(m);
+        let s = Sphere::default().with_material(m.clone());
 
         assert_eq!(s.material, m);
     }
+
+    #[test]
+    fn spherical_mapping_on_a_3d_point() {
+        let s = Sphere::default();
+        let sqrt2_over_2 = (2.0 as F).sqrt() / 2.0;
+        let points = [
+            (Tuple::point(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Tuple::point(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Tuple::point(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Tuple::point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Tuple::point(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Tuple::point(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (Tuple::point(sqrt2_over_2, sqrt2_over_2, 0.0), (0.25, 0.75)),
+        ];
+        for (point, (u, v)) in points {
+            let (actual_u, actual_v) = s.uv_at_in_object_space(point);
+            assert!(actual_u.fuzzy_eq(u));
+            assert!(actual_v.fuzzy_eq(v));
+        }
+    }
+
+    #[test]
+    fn tangent_follows_increasing_u() {
+        let s = Sphere::default();
+        let points = [
+            Tuple::point(0.0, 0.0, -1.0),
+            Tuple::point(1.0, 0.0, 0.0),
+            Tuple::point(0.5, 0.5, (0.5 as F).sqrt()),
+        ];
+        for point in points {
+            let tangent = s.tangent_at_in_object_space(point);
+            let (u, _) = s.uv_at_in_object_space(point);
+            let (next_u, _) = s.uv_at_in_object_space(point + tangent * 0.001);
+            assert!(next_u > u);
+            assert!(tangent.dot(s.normal_at_in_object_space(point)).fuzzy_eq(0.0));
+        }
+    }
+
+    #[test]
+    fn tangent_on_the_poles_is_still_defined() {
+        let s = Sphere::default();
+
+        assert_eq!(s.tangent_at_in_object_space(Tuple::point(0.0, 1.0, 0.0)), Tuple::vector(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn tangent_of_transformed_sphere_is_perpendicular_to_the_normal() {
+        let s = Sphere::default().with_transform(Matrix::scaling(1.0, 0.5, 2.0) * Matrix::rotation_z(PI / 5.0));
+        let point = s.transform * Tuple::point(0.6, 0.0, -0.8);
+        let normal = s.normal_at(point);
+        let tangent = s.tangent_at(point, normal);
+
+        assert!(tangent.dot(normal).fuzzy_eq(0.0));
+        assert!(tangent.magnitude().fuzzy_eq(1.0));
+    }
 }
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::F;
+
+// Counters gathered while rendering, to tell where the time goes. Each worker
+// fills its own copy, they are merged once the image is done.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    // rays cast from the camera, one per pixel and shutter sample
+    pub primary_rays: u64,
+    pub shadow_rays: u64,
+    pub reflection_rays: u64,
+    // cast by the path tracer to carry on its paths
+    pub bounce_rays: u64,
+    // cast to estimate ambient occlusion
+    pub occlusion_rays: u64,
+    // ray against body tests, by body type
+    pub sphere_tests: u64,
+    pub plane_tests: u64,
+    // deepest reflection or bounce reached by any camera ray
+    pub max_depth: usize,
+    // deepest level reached by each camera ray, added up
+    pub total_depth: u64,
+    // wall-clock time of each phase, in the order they first ran
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+impl RenderStats {
+    pub fn rays(&self) -> u64 {
+        self.primary_rays + self.shadow_rays + self.reflection_rays + self.bounce_rays + self.occlusion_rays
+    }
+
+    pub fn intersection_tests(&self) -> u64 {
+        self.sphere_tests + self.plane_tests
+    }
+
+    // deepest level reached per camera ray
+    pub fn average_depth(&self) -> F {
+        if self.primary_rays == 0 {
+            0.0
+        } else {
+            self.total_depth as F / self.primary_rays as F
+        }
+    }
+
+    // the integrators leave the camera ray itself to the caller, its stats
+    // are gathered apart to know how deep it went
+    pub fn add_camera_ray(&mut self, ray_stats: &RenderStats) {
+        self.primary_rays += 1;
+        self.total_depth += ray_stats.max_depth as u64;
+        self.merge(ray_stats);
+    }
+
+    // time spent in phases sharing the same name adds up
+    pub fn add_phase(&mut self, name: &'static str, duration: Duration) {
+        match self.phases.iter_mut().find(|(phase, _)| *phase == name) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((name, duration)),
+        }
+    }
+
+    pub fn merge(&mut self, other: &RenderStats) {
+        self.primary_rays += other.primary_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.reflection_rays += other.reflection_rays;
+        self.bounce_rays += other.bounce_rays;
+        self.occlusion_rays += other.occlusion_rays;
+        self.sphere_tests += other.sphere_tests;
+        self.plane_tests += other.plane_tests;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.total_depth += other.total_depth;
+        for (name, duration) in other.phases.iter() {
+            self.add_phase(name, *duration);
+        }
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Rays: {}", self.rays())?;
+        writeln!(f, "  primary: {}", self.primary_rays)?;
+        writeln!(f, "  shadow: {}", self.shadow_rays)?;
+        writeln!(f, "  reflection: {}", self.reflection_rays)?;
+        writeln!(f, "  bounce: {}", self.bounce_rays)?;
+        writeln!(f, "  occlusion: {}", self.occlusion_rays)?;
+        writeln!(f, "Intersection tests: {}", self.intersection_tests())?;
+        writeln!(f, "  sphere: {}", self.sphere_tests)?;
+        writeln!(f, "  plane: {}", self.plane_tests)?;
+        writeln!(
+            f,
+            "Depth per camera ray: {:.3} on average, {} at most",
+            self.average_depth(),
+            self.max_depth
+        )?;
+        write!(f, "Phases:")?;
+        for (name, duration) in self.phases.iter() {
+            write!(f, "\n  {}: {:.3}s", name, duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_adds_up_counters_and_phases() {
+        let mut stats = RenderStats {
+            primary_rays: 4,
+            shadow_rays: 3,
+            sphere_tests: 8,
+            max_depth: 1,
+            total_depth: 1,
+            ..RenderStats::default()
+        };
+        stats.add_phase("trace", Duration::from_millis(10));
+        let mut other = RenderStats {
+            primary_rays: 4,
+            reflection_rays: 2,
+            bounce_rays: 1,
+            occlusion_rays: 3,
+            plane_tests: 5,
+            max_depth: 2,
+            total_depth: 3,
+            ..RenderStats::default()
+        };
+        other.add_phase("trace", Duration::from_millis(5));
+        other.add_phase("outline", Duration::from_millis(1));
+
+        stats.merge(&other);
+
+        assert_eq!(stats.rays(), 17);
+        assert_eq!(stats.intersection_tests(), 13);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.average_depth(), 0.5);
+        assert_eq!(
+            stats.phases,
+            vec![("trace", Duration::from_millis(15)), ("outline", Duration::from_millis(1))]
+        );
+    }
+
+    #[test]
+    fn camera_rays_add_the_depth_they_reached() {
+        let mut stats = RenderStats::default();
+        let deep = RenderStats {
+            reflection_rays: 3,
+            max_depth: 3,
+            ..RenderStats::default()
+        };
+
+        stats.add_camera_ray(&RenderStats::default());
+        stats.add_camera_ray(&deep);
+
+        assert_eq!(stats.primary_rays, 2);
+        assert_eq!(stats.rays(), 5);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.average_depth(), 1.5);
+    }
+}
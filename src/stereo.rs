@@ -0,0 +1,124 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::F;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StereoLayout {
+    // left eye on the left half, for parallel viewing and most VR players
+    SideBySide,
+    // left eye on the top half
+    TopBottom,
+    // red channel from the left eye, green and blue from the right one
+    Anaglyph,
+}
+
+// Renders the scene twice, from two eyes on the horizontal axis of the camera,
+// both converging at the same distance in front of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stereo {
+    pub interocular: F,
+    pub convergence: F,
+    pub layout: StereoLayout,
+}
+
+impl Stereo {
+    pub fn new(interocular: F, convergence: F) -> Self {
+        Stereo {
+            interocular,
+            convergence,
+            layout: StereoLayout::SideBySide,
+        }
+    }
+
+    pub fn with_layout(mut self, layout: StereoLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    // size of the combined image for eyes of the given size
+    pub fn size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self.layout {
+            StereoLayout::SideBySide => (width * 2, height),
+            StereoLayout::TopBottom => (width, height * 2),
+            StereoLayout::Anaglyph => (width, height),
+        }
+    }
+
+    pub fn combine(&self, left: &Canvas, right: &Canvas) -> Canvas {
+        let (width, height) = self.size(left.width, left.height);
+        let mut combined = Canvas::new(width, height);
+        for y in 0..left.height {
+            for x in 0..left.width {
+                let left_color = left.color_at(x, y);
+                let right_color = right.color_at(x, y);
+                match self.layout {
+                    StereoLayout::SideBySide => {
+                        combined.write_pixel(x, y, left_color);
+                        combined.write_pixel(x + left.width, y, right_color);
+                    }
+                    StereoLayout::TopBottom => {
+                        combined.write_pixel(x, y, left_color);
+                        combined.write_pixel(x, y + left.height, right_color);
+                    }
+                    StereoLayout::Anaglyph => {
+                        let color = Color::new(left_color.red, right_color.green, right_color.blue);
+                        combined.write_pixel(x, y, color);
+                    }
+                }
+            }
+        }
+        combined
+    }
+}
+
+impl FuzzyEq<Stereo> for Stereo {
+    fn fuzzy_eq(&self, other: Stereo) -> bool {
+        self.interocular.fuzzy_eq(other.interocular)
+            && self.convergence.fuzzy_eq(other.convergence)
+            && self.layout == other.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eyes() -> (Canvas, Canvas) {
+        let mut left = Canvas::new(2, 1);
+        let mut right = Canvas::new(2, 1);
+        left.write_pixel(0, 0, Color::new(1.0, 0.5, 0.25));
+        right.write_pixel(1, 0, Color::new(0.75, 0.5, 1.0));
+        (left, right)
+    }
+
+    #[test]
+    fn side_by_side_and_top_bottom_layouts() {
+        let (left, right) = eyes();
+
+        let side_by_side = Stereo::new(0.1, 5.0).combine(&left, &right);
+        assert_eq!((side_by_side.width, side_by_side.height), (4, 1));
+        assert_eq!(side_by_side.color_at(0, 0), Color::new(1.0, 0.5, 0.25));
+        assert_eq!(side_by_side.color_at(3, 0), Color::new(0.75, 0.5, 1.0));
+
+        let top_bottom = Stereo::new(0.1, 5.0)
+            .with_layout(StereoLayout::TopBottom)
+            .combine(&left, &right);
+        assert_eq!((top_bottom.width, top_bottom.height), (2, 2));
+        assert_eq!(top_bottom.color_at(0, 0), Color::new(1.0, 0.5, 0.25));
+        assert_eq!(top_bottom.color_at(1, 1), Color::new(0.75, 0.5, 1.0));
+    }
+
+    #[test]
+    fn anaglyph_takes_red_from_the_left_eye() {
+        let (left, right) = eyes();
+
+        let anaglyph = Stereo::new(0.1, 5.0)
+            .with_layout(StereoLayout::Anaglyph)
+            .combine(&left, &right);
+
+        assert_eq!((anaglyph.width, anaglyph.height), (2, 1));
+        assert_eq!(anaglyph.color_at(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(anaglyph.color_at(1, 0), Color::new(0.0, 0.5, 1.0));
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::tone_mapping::srgb_decode;
+use crate::F;
+
+// Image data shared by every material or background that uses it, cloning a
+// texture does not copy the image.
+#[derive(Clone)]
+pub struct Texture {
+    canvas: Arc<Canvas>,
+}
+
+impl Texture {
+    pub fn new(canvas: Canvas) -> Self {
+        Texture {
+            canvas: Arc::new(canvas),
+        }
+    }
+
+    // for color images, which hold sRGB encoded values rather than radiance
+    pub fn from_srgb(canvas: Canvas) -> Self {
+        let mut linear = Canvas::new(canvas.width, canvas.height);
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                let color = canvas.color_at(x, y);
+                let decoded = Color::new(srgb_decode(color.red), srgb_decode(color.green), srgb_decode(color.blue));
+                linear.write_pixel(x, y, decoded);
+            }
+        }
+        Texture::new(linear)
+    }
+
+    pub fn width(&self) -> usize {
+        self.canvas.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.canvas.height
+    }
+
+    // nearest texel lookup, (0, 0) is the bottom left corner of the image
+    pub fn color_at_uv(&self, u: F, v: F) -> Color {
+        let u = u.clamp(0.0, 1.0);
+        let v = 1.0 - v.clamp(0.0, 1.0);
+        let x = (u * (self.width() - 1) as F).round() as usize;
+        let y = (v * (self.height() - 1) as F).round() as usize;
+        self.canvas.color_at(x, y)
+    }
+}
+
+impl PartialEq for Texture {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.canvas, &other.canvas)
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Texture({}x{})", self.width(), self.height())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_texture() -> Texture {
+        let mut canvas = Canvas::new(10, 10);
+        for x in 0..10 {
+            for y in 0..10 {
+                canvas.write_pixel(x, y, Color::new(x as F / 9.0, y as F / 9.0, 0.0));
+            }
+        }
+        Texture::new(canvas)
+    }
+
+    #[test]
+    fn texture_lookup_flips_v() {
+        let texture = create_test_texture();
+
+        assert_eq!(texture.color_at_uv(0.0, 0.0), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(texture.color_at_uv(1.0, 1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.color_at_uv(0.4, 0.6), Color::new(4.0 / 9.0, 4.0 / 9.0, 0.0));
+    }
+
+    #[test]
+    fn texture_lookup_clamps_out_of_range_uv() {
+        let texture = create_test_texture();
+
+        assert_eq!(texture.color_at_uv(-0.5, 1.5), texture.color_at_uv(0.0, 1.0));
+    }
+
+    #[test]
+    fn textures_compare_by_identity() {
+        let texture = create_test_texture();
+        let copy = texture.clone();
+
+        assert_eq!(texture, copy);
+        assert_ne!(texture, create_test_texture());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::fuzzy_eq::FuzzyEq;
+use crate::F;
+
+// white point of the filmic curve, linear values above it map to pure white
+const FILMIC_WHITE: F = 11.2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMapping {
+    // keeps the historic behaviour, everything above 1 blows out
+    Clamp,
+    // x / (1 + x), compresses highlights but flattens the image a bit
+    Reinhard,
+    // John Hable's Uncharted 2 curve, with a toe and a soft shoulder
+    Filmic,
+    // Krzysztof Narkowicz's fit of the ACES reference rendering transform
+    Aces,
+}
+
+// Turns linear radiance into display values before they are quantized:
+// exposure (in stops), then the tone curve, then the sRGB transfer function
+// expected by image viewers, which can be turned off to keep linear values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMap {
+    pub operator: ToneMapping,
+    pub exposure: F,
+    pub srgb: bool,
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        ToneMap {
+            operator: ToneMapping::Clamp,
+            exposure: 0.0,
+            srgb: true,
+        }
+    }
+}
+
+fn filmic_curve(x: F) -> F {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+fn srgb_encode(x: F) -> F {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// inverse of the sRGB transfer function, for images stored as display values
+pub fn srgb_decode(x: F) -> F {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl ToneMap {
+    pub fn with_operator(mut self, operator: ToneMapping) -> Self {
+        self.operator = operator;
+        self
+    }
+
+    pub fn with_exposure(mut self, exposure: F) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    fn map_channel(&self, value: F) -> F {
+        let exposed = value.max(0.0) * (2.0 as F).powf(self.exposure);
+        let mapped = match self.operator {
+            ToneMapping::Clamp => exposed,
+            ToneMapping::Reinhard => exposed / (1.0 + exposed),
+            ToneMapping::Filmic => filmic_curve(exposed) / filmic_curve(FILMIC_WHITE),
+            ToneMapping::Aces => {
+                (exposed * (2.51 * exposed + 0.03)) / (exposed * (2.43 * exposed + 0.59) + 0.14)
+            }
+        }
+        .clamp(0.0, 1.0);
+        if self.srgb {
+            srgb_encode(mapped)
+        } else {
+            mapped
+        }
+    }
+
+    pub fn map(&self, color: Color) -> Color {
+        Color::new(
+            self.map_channel(color.red),
+            self.map_channel(color.green),
+            self.map_channel(color.blue),
+        )
+    }
+
+    pub fn apply(&self, canvas: &Canvas) -> Canvas {
+        let mut mapped = Canvas::new(canvas.width, canvas.height);
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                mapped.write_pixel(x, y, self.map(canvas.color_at(x, y)));
+            }
+        }
+        mapped
+    }
+}
+
+impl FuzzyEq<ToneMap> for ToneMap {
+    fn fuzzy_eq(&self, other: ToneMap) -> bool {
+        self.operator == other.operator
+            && self.exposure.fuzzy_eq(other.exposure)
+            && self.srgb == other.srgb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_fuzzy_eq;
+
+    #[test]
+    fn default_tone_map_clamps_and_encodes_srgb() {
+        let tone_map = ToneMap::default();
+
+        assert_fuzzy_eq!(
+            tone_map.map(Color::new(1.5, 0.214041, -0.5)),
+            Color::new(1.0, 0.5, 0.0)
+        );
+        assert_fuzzy_eq!(
+            tone_map.with_srgb(false).map(Color::new(1.5, 0.5, -0.5)),
+            Color::new(1.0, 0.5, 0.0)
+        );
+    }
+
+    #[test]
+    fn exposure_is_expressed_in_stops() {
+        let tone_map = ToneMap::default().with_srgb(false).with_exposure(1.0);
+        assert_fuzzy_eq!(tone_map.map(Color::new(0.25, 0.1, 0.0)), Color::new(0.5, 0.2, 0.0));
+
+        let tone_map = ToneMap::default().with_srgb(false).with_exposure(-2.0);
+        assert_fuzzy_eq!(tone_map.map(Color::new(2.0, 1.0, 0.0)), Color::new(0.5, 0.25, 0.0));
+    }
+
+    #[test]
+    fn reinhard_compresses_highlights() {
+        let tone_map = ToneMap::default().with_srgb(false).with_operator(ToneMapping::Reinhard);
+
+        assert_fuzzy_eq!(tone_map.map(Color::new(1.0, 3.0, 0.0)), Color::new(0.5, 0.75, 0.0));
+    }
+
+    #[test]
+    fn filmic_and_aces_stay_monotonic_and_in_range() {
+        for operator in [ToneMapping::Filmic, ToneMapping::Aces] {
+            let tone_map = ToneMap::default().with_srgb(false).with_operator(operator);
+            let mut previous = tone_map.map_channel(0.0);
+            assert!(previous.abs() < 0.01);
+            for step in 1..200 {
+                let value = tone_map.map_channel(step as F * 0.1);
+                assert!(value >= previous);
+                assert!(value <= 1.0);
+                previous = value;
+            }
+            assert!(previous > 0.95);
+        }
+    }
+
+    #[test]
+    fn srgb_encoding_brightens_midtones() {
+        let tone_map = ToneMap::default().with_srgb(true);
+
+        assert_fuzzy_eq!(tone_map.map_channel(0.0), 0.0);
+        assert_fuzzy_eq!(tone_map.map_channel(0.001), 0.01292);
+        assert!((tone_map.map_channel(0.2140) - 0.5).abs() < 0.001);
+        assert_fuzzy_eq!(tone_map.map_channel(1.0), 1.0);
+        for value in [0.0, 0.001, 0.2140, 0.5, 1.0] {
+            assert_fuzzy_eq!(srgb_decode(tone_map.map_channel(value)), value);
+        }
+    }
+
+    #[test]
+    fn applying_a_tone_map_to_a_canvas() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::new(3.0, 1.0, 0.0));
+        let tone_map = ToneMap::default().with_srgb(false).with_operator(ToneMapping::Reinhard);
+
+        let mapped = tone_map.apply(&canvas);
+
+        assert_fuzzy_eq!(mapped.color_at(0, 0), Color::black());
+        assert_fuzzy_eq!(mapped.color_at(1, 0), Color::new(0.75, 0.5, 0.0));
+    }
+}
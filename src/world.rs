@@ -1,32 +1,95 @@
-
+use crate::ambient_occlusion::AmbientOcclusion;
+use crate::background::Background;
 use crate::body::*;
 use crate::color::Color;
 use crate::computed_intersection::ComputedIntersection;
 use crate::fuzzy_eq::FuzzyEq;
+use crate::integrator::PathTracer;
 use crate::intersections::*;
 use crate::lights::PointLight;
 use crate::material::*;
+use crate::media::{transmittance, Fog, Medium};
+use crate::photon_map::Caustics;
 use crate::ray::*;
+use crate::sampling::{cosine_weighted, glossy_direction, Rng};
+use crate::stats::RenderStats;
 use crate::tuple::Tuple;
+use crate::F;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct World {
     pub bodies: Vec<Body>,
     pub lights: Vec<PointLight>,
     pub reflection_limit: usize,
+    pub background: Background,
+    // point lights spread over each emissive sphere to light the other
+    // bodies, 0 leaves emissive bodies glowing without lighting anything
+    pub emitter_samples: usize,
+    // rays averaged for the blurred reflections of rough materials, deeper
+    // reflections follow a single one to keep the cost in check
+    pub reflection_samples: usize,
+    // photon map of the light reflected onto matte surfaces, see
+    // World::with_traced_photons
+    pub caustics: Option<Caustics>,
+    pub fog: Option<Fog>,
+    // scales the ambient term by how open the surroundings of each hit are
+    pub ambient_occlusion: Option<AmbientOcclusion>,
 }
 
 impl World {
     pub fn new(bodies: Vec<Body>, lights: Vec<PointLight>) -> Self {
         //FIXME make reflection_limit configurable
         //FIXME change world parsing to update
-        World { bodies, lights , reflection_limit: 5}
+        World { bodies, lights , reflection_limit: 5, background: Background::default(), emitter_samples: 0, reflection_samples: 8, caustics: None, fog: None, ambient_occlusion: None}
+    }
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+    pub fn with_emitter_samples(mut self, emitter_samples: usize) -> Self {
+        self.emitter_samples = emitter_samples;
+        self
+    }
+    pub fn with_reflection_samples(mut self, reflection_samples: usize) -> Self {
+        self.reflection_samples = reflection_samples.max(1);
+        self
+    }
+    pub fn with_caustics(mut self, caustics: Caustics) -> Self {
+        self.caustics = Some(caustics);
+        self
+    }
+    pub fn with_fog(mut self, fog: Fog) -> Self {
+        self.fog = Some(fog);
+        self
+    }
+    pub fn with_ambient_occlusion(mut self, ambient_occlusion: AmbientOcclusion) -> Self {
+        self.ambient_occlusion = Some(ambient_occlusion);
+        self
+    }
+    // the photons depend on where the bodies and lights are, they are traced
+    // again before each render
+    pub fn with_traced_photons(mut self) -> Self {
+        if let Some(caustics) = self.caustics.take() {
+            self.caustics = Some(caustics.trace(&self));
+        }
+        self
     }
     pub fn intersect(&self, ray: Ray) -> Intersections {
+        self.intersect_with_stats(ray, &mut RenderStats::default())
+    }
+
+    // bodies filled with a medium have no surface and are never hit, see
+    // World::media_along
+    pub fn intersect_with_stats(&self, ray: Ray, stats: &mut RenderStats) -> Intersections {
+        let solids = || self.bodies.iter().filter(|body| body.medium().is_none());
+        for body in solids() {
+            match body {
+                Body::Sphere(_) => stats.sphere_tests += 1,
+                Body::Plane(_) => stats.plane_tests += 1,
+            }
+        }
         // need more infos about iter, flat_map and collect
-        let xs = self
-            .bodies
-            .iter()
+        let xs = solids()
             .flat_map(|body| body.intersect(ray))
             .collect();
         Intersections::new(xs)
@@ -46,50 +109,345 @@ impl World {
         // } else {
         //     Color::black()
         // }
-        self.color_at_with_reflection_limit(ray, self.reflection_limit)
+        self.color_at_with_stats(ray, &mut RenderStats::default())
+    }
+
+    // same as color_at, counting the work done for the camera ray in stats
+    pub fn color_at_with_stats(&self, ray: Ray, stats: &mut RenderStats) -> Color {
+        self.color_at_with_reflection_limit(ray, self.reflection_limit, stats)
     }
 
-    fn color_at_with_reflection_limit(&self, ray: Ray, remaining_reflections: usize) -> Color
+    fn color_at_with_reflection_limit(&self, ray: Ray, remaining_reflections: usize, stats: &mut RenderStats) -> Color
     {
-        let xs = self.intersect(ray);
+        stats.max_depth = stats.max_depth.max(self.reflection_limit - remaining_reflections);
+        let xs = self.intersect_with_stats(ray, stats);
 
         let hit = xs.hit();
-        if let Some(hit) = hit {
+        let (color, distance) = if let Some(hit) = hit {
             let c = hit.get_computed();
             let material = hit.body.material();
-            let is_in_shadow = self.is_shadowed(c.over_point);
-            let surface_color = material.lighting(&hit.body, self.lights[0], c.over_point, c.eyev, c.normalv, is_in_shadow);
-            let reflected_color = self.reflect_color_at(&material, &c, remaining_reflections);
-            surface_color + reflected_color
+            let surface_color = self.direct_light_at(&hit.body, &material, &c, ray.time, stats)
+                + self.caustics_at(&hit.body, &material, &c);
+            let reflected_color = self.reflect_color_with_stats(&material, &c, remaining_reflections, stats);
+            (material.emission() + surface_color + reflected_color, hit.t)
         } else {
-            Color::black()
+            (self.background.color_at(ray.direction), F::INFINITY)
+        };
+        let (transmitted, scattered) = self.media_along(ray, distance, stats);
+        color * transmitted + scattered
+    }
+
+    // What the media and the fog let through of the light coming from
+    // distance along the ray, infinite when it reaches nothing, and the light
+    // they send along the ray on the way. The media come first, as if the fog
+    // were behind them.
+    fn media_along(&self, ray: Ray, distance: F, stats: &mut RenderStats) -> (Color, Color) {
+        let mut transmitted = Color::white();
+        let mut scattered = Color::black();
+        let mut segments = Vec::new();
+        for body in self.bodies.iter() {
+            if let Some(medium) = body.medium() {
+                stats.sphere_tests += 1;
+                let ts: Vec<F> = body.intersect(ray).into_iter().map(|i| i.t).collect();
+                if ts.len() == 2 {
+                    let (enter, leave) = (ts[0].min(ts[1]).max(0.0), ts[0].max(ts[1]).min(distance));
+                    if enter < leave {
+                        segments.push((enter, leave, medium));
+                    }
+                }
+            }
+        }
+        segments.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        for (enter, leave, medium) in segments {
+            let (medium_transmitted, medium_scattered) = self.scatter_through(ray, enter, leave, &medium, stats);
+            scattered = scattered + transmitted * medium_scattered;
+            transmitted = transmitted * medium_transmitted;
+        }
+        if let Some(fog) = self.fog {
+            let fog_transmittance = fog.transmittance(distance * ray.direction.magnitude());
+            scattered = scattered + transmitted * fog.color * (1.0 - fog_transmittance);
+            transmitted = transmitted * fog_transmittance;
+        }
+        (transmitted, scattered)
+    }
+
+    // Single scattering along the ray between enter and leave inside a
+    // medium: at each step the light reaching the point from the lights is
+    // partly sent towards the ray origin, dimmed by the medium on both ways.
+    fn scatter_through(&self, ray: Ray, enter: F, leave: F, medium: &Medium, stats: &mut RenderStats) -> (Color, Color) {
+        let step = (leave - enter) / medium.steps as F;
+        let step_transmittance = transmittance(medium.density, step * ray.direction.magnitude());
+        let mut transmitted = 1.0;
+        let mut scattered = Color::black();
+        for i in 0..medium.steps {
+            let point = ray.position(enter + (i as F + 0.5) * step);
+            let light = self.light_in_medium(point, ray.time, stats);
+            scattered = scattered + medium.color * light * (transmitted * (1.0 - step_transmittance));
+            transmitted *= step_transmittance;
         }
+        (Color::new(transmitted, transmitted, transmitted), scattered)
+    }
+
+    // light of the lights reaching a point inside a medium, dimmed by that
+    // medium and the ones in between
+    fn light_in_medium(&self, point: Tuple, time: F, stats: &mut RenderStats) -> Color {
+        self.lights.iter().fold(Color::black(), |light_sum, light| {
+            light_sum + light.intensity * self.light_reaching(point, light.position, None, time, stats)
+        })
+    }
+
+    // Share of the light going from the light to position: none when a body
+    // other than the ignored one stands in between, otherwise what the media
+    // crossed let through. The fog does not dim the lights.
+    fn light_reaching(&self, position: Tuple, light_position: Tuple, ignored: Option<&Body>, time: F, stats: &mut RenderStats) -> F {
+        if self.is_shadowed_from(position, light_position, ignored, time, stats) {
+            return 0.0;
+        }
+        let to_light = light_position - position;
+        let distance = to_light.magnitude();
+        let ray = Ray::new(position, to_light.normalize()).with_time(time);
+        let mut reaching = 1.0;
+        for body in self.bodies.iter() {
+            if let Some(medium) = body.medium() {
+                stats.sphere_tests += 1;
+                let ts: Vec<F> = body.intersect(ray).into_iter().map(|i| i.t).collect();
+                if ts.len() == 2 {
+                    let (enter, leave) = (ts[0].min(ts[1]).max(0.0), ts[0].max(ts[1]).min(distance));
+                    if enter < leave {
+                        reaching *= transmittance(medium.density, leave - enter);
+                    }
+                }
+            }
+        }
+        reaching
     }
 
     pub fn reflect_color_at(&self, material: &Material, computed_intersection: &ComputedIntersection, remaining_reflections: usize) -> Color {
-        if material.reflectiveness() == 0.0 || remaining_reflections == 0
+        self.reflect_color_with_stats(material, computed_intersection, remaining_reflections, &mut RenderStats::default())
+    }
+
+    fn reflect_color_with_stats(&self, material: &Material, computed_intersection: &ComputedIntersection, remaining_reflections: usize, stats: &mut RenderStats) -> Color {
+        let reflectance = material.reflectance(computed_intersection.eyev.dot(computed_intersection.normalv));
+        if reflectance == 0.0 || remaining_reflections == 0
         {
            return Color::black()
         }
-        let reflected_ray = Ray::new(computed_intersection.over_point, computed_intersection.reflectv);
-        let reflected_color = self.color_at_with_reflection_limit(reflected_ray, remaining_reflections - 1);
-        reflected_color * material.reflectiveness()
+        let directions = self.reflection_directions(material.roughness(), computed_intersection, remaining_reflections);
+        let samples = directions.len();
+        let reflected_color = directions.into_iter().fold(Color::black(), |color, direction| {
+            let reflected_ray = Ray::new(computed_intersection.over_point, direction)
+                .with_time(computed_intersection.intersection.ray.time);
+            stats.reflection_rays += 1;
+            color + self.color_at_with_reflection_limit(reflected_ray, remaining_reflections - 1, stats)
+        });
+        reflected_color * (reflectance / samples as F)
     }
 
-    fn is_shadowed(&self, position: Tuple) -> bool {
-        let v = self.lights[0].position - position;
-        let distance = v.magnitude();
-        let direction = v.normalize();
-        let r = Ray::new(position, direction);
+    // The mirror direction, or for rough materials directions spread in a
+    // cone around it, see glossy_direction
+    fn reflection_directions(&self, roughness: F, c: &ComputedIntersection, remaining_reflections: usize) -> Vec<Tuple> {
+        if roughness <= 0.0 {
+            return vec![c.reflectv];
+        }
+        let samples = if remaining_reflections == self.reflection_limit { self.reflection_samples.max(1) } else { 1 };
+        let mut rng = Rng::from_tuples(c.over_point, c.reflectv);
+        (0..samples).map(|_| glossy_direction(roughness, c.reflectv, c.normalv, &mut rng)).collect()
+    }
 
-        let xs = self.intersect(r);
-        if let Some(hit) = xs.hit() {
-            if hit.t < distance {
-                return true;
+    // One path of the path tracer. At each hit the light coming straight from
+    // the lights is gathered, then the path goes on in a single direction:
+    // mirrored with the reflectance of the material as probability, otherwise
+    // spread around the normal and tinted by the albedo. The diffuse bounce
+    // makes up for the paths sent to the mirror, so that on average the
+    // diffuse light is whole and the reflection weighs the reflectance, as
+    // with World::color_at. Russian roulette ends the paths whose
+    // contribution gets small, without biasing the average.
+    pub fn path_trace_with_stats(&self, ray: Ray, path_tracer: &PathTracer, rng: &mut Rng, stats: &mut RenderStats) -> Color {
+        let mut ray = ray;
+        let mut color = Color::black();
+        let mut throughput = Color::white();
+        // emitters sampled as lights were already seen from the last diffuse
+        // bounce, hitting them again would count their light twice
+        let mut count_emission = true;
+        for bounce in 0..path_tracer.max_bounces {
+            stats.max_depth = stats.max_depth.max(bounce);
+            let xs = self.intersect_with_stats(ray, stats);
+            let (transmitted, scattered) = self.media_along(ray, xs.hit().map_or(F::INFINITY, |hit| hit.t), stats);
+            color = color + throughput * scattered;
+            throughput = throughput * transmitted;
+            let hit = match xs.hit() {
+                Some(hit) => hit,
+                None => {
+                    color = color + throughput * self.background.color_at(ray.direction);
+                    break;
+                }
+            };
+            let c = hit.get_computed();
+            let material = hit.body.material();
+            if count_emission || !self.samples_as_light(&hit.body) {
+                color = color + throughput * material.emission();
             }
+            // point lights cannot be hit, the light they send through
+            // mirrors only comes from the photons
+            color = color
+                + throughput * (self.direct_light_only(&hit.body, &material, &c, ray.time, stats) + self.caustics_at(&hit.body, &material, &c));
+
+            if bounce + 1 >= path_tracer.min_bounces {
+                let survival = throughput.red.max(throughput.green).max(throughput.blue).clamp(0.05, 1.0);
+                if rng.next_f() >= survival {
+                    break;
+                }
+                throughput = throughput * (1.0 / survival);
+            }
+            let reflectance = material.reflectance(c.eyev.dot(c.normalv));
+            // perfect mirrors have no diffuse bounce to weigh
+            let direction = if reflectance >= 1.0 || rng.next_f() < reflectance {
+                count_emission = true;
+                glossy_direction(material.roughness(), c.reflectv, c.normalv, rng)
+            } else {
+                count_emission = false;
+                throughput = throughput * material.albedo(&hit.body, c.over_point) * (1.0 / (1.0 - reflectance));
+                cosine_weighted(c.normalv, rng.next_f(), rng.next_f())
+            };
+            stats.bounce_rays += 1;
+            ray = Ray::new(c.over_point, direction).with_time(ray.time);
         }
-        false
+        color
+    }
+
+    fn caustics_at(&self, body: &Body, material: &Material, c: &ComputedIntersection) -> Color {
+        match self.caustics {
+            Some(ref caustics) => caustics.color_at(material.albedo(body, c.over_point), c.point, c.normalv),
+            None => Color::black(),
+        }
+    }
+
+    // light reaching the hit straight from every point light and emissive
+    // body, without the ambient term standing in for the indirect light
+    fn direct_light_only(&self, body: &Body, material: &Material, c: &ComputedIntersection, time: F, stats: &mut RenderStats) -> Color {
+        let emitters = self.emitter_lights(body, c.over_point, time);
+        let lights = self
+            .lights
+            .iter()
+            .map(|light| (None, *light))
+            .chain(emitters.iter().map(|(emitter, light)| (Some(emitter), *light)));
+        let mut color = Color::black();
+        for (ignored, light) in lights {
+            let reaching = self.light_reaching(c.over_point, light.position, ignored, time, stats);
+            if reaching > 0.0 {
+                let lit = material.lighting(body, light, c.over_point, c.eyev, c.normalv, false);
+                let ambient = material.lighting(body, light, c.over_point, c.eyev, c.normalv, true);
+                color = color + (lit - ambient) * reaching;
+            }
+        }
+        color
+    }
+
+    // light reaching the hit from the point light and from emissive bodies
+    fn direct_light_at(&self, body: &Body, material: &Material, c: &ComputedIntersection, time: F, stats: &mut RenderStats) -> Color {
+        let mut color = Color::black();
+        if let Some(light) = self.lights.first() {
+            let reaching = self.light_reaching(c.over_point, light.position, None, time, stats);
+            color = material.lighting(body, *light, c.over_point, c.eyev, c.normalv, reaching == 0.0);
+            if reaching > 0.0 && reaching < 1.0 {
+                let ambient = material.lighting(body, *light, c.over_point, c.eyev, c.normalv, true);
+                color = ambient + (color - ambient) * reaching;
+            }
+            if let Some(ref ambient_occlusion) = self.ambient_occlusion {
+                let ambient = material.lighting(body, *light, c.over_point, c.eyev, c.normalv, true);
+                let openness = self.openness_at(c.over_point, c.normalv, ambient_occlusion, time, stats);
+                color = color - ambient * (1.0 - openness);
+            }
+        }
+        for (emitter, light) in self.emitter_lights(body, c.over_point, time) {
+            let reaching = self.light_reaching(c.over_point, light.position, Some(&emitter), time, stats);
+            if reaching > 0.0 {
+                // each lighting call has its own ambient term, only the
+                // first light gets to add it
+                let lit = material.lighting(body, light, c.over_point, c.eyev, c.normalv, false);
+                let ambient = material.lighting(body, light, c.over_point, c.eyev, c.normalv, true);
+                color = color + (lit - ambient) * reaching;
+            }
+        }
+        color
+    }
+
+    // Share of the rays cast around the normal that reach nothing within the
+    // radius, 1 for a hit with nothing around and 0 at the bottom of a hole
+    pub fn openness_at(&self, position: Tuple, normalv: Tuple, ambient_occlusion: &AmbientOcclusion, time: F, stats: &mut RenderStats) -> F {
+        let mut rng = Rng::from_tuples(position, normalv);
+        let open = (0..ambient_occlusion.samples)
+            .filter(|_| {
+                stats.occlusion_rays += 1;
+                let direction = cosine_weighted(normalv, rng.next_f(), rng.next_f());
+                let ray = Ray::new(position, direction).with_time(time);
+                !self
+                    .intersect_with_stats(ray, stats)
+                    .into_iter()
+                    .any(|hit| hit.t > 0.0 && hit.t < ambient_occlusion.radius)
+            })
+            .count();
+        open as F / ambient_occlusion.samples as F
+    }
+
+    // Point lights spread evenly over the emissive spheres other than body,
+    // sharing their emission. Only the samples on the side facing the position
+    // are kept, so a sphere hidden by nothing gives off its whole emission.
+    fn emitter_lights(&self, body: &Body, position: Tuple, time: F) -> Vec<(Body, PointLight)> {
+        let mut lights = Vec::new();
+        if self.emitter_samples == 0 {
+            return lights;
+        }
+        for emitter in self.bodies.iter().map(|emitter| emitter.at_time(time)) {
+            if emitter == *body || !self.samples_as_light(&emitter) {
+                continue;
+            }
+            let emission = emitter.material().emission();
+            let samples: Vec<Tuple> = fibonacci_sphere(self.emitter_samples)
+                .map(|point| emitter.transform() * point)
+                .filter(|point| (position - *point).dot(emitter.normal_at(*point)) > 0.0)
+                .collect();
+            let intensity = emission * (1.0 / samples.len() as F);
+            for point in samples {
+                lights.push((emitter.clone(), PointLight::new(point, intensity)));
+            }
+        }
+        lights
+    }
+
+    fn samples_as_light(&self, body: &Body) -> bool {
+        // planes are endless, they cannot be sampled
+        self.emitter_samples > 0 && body.material().emission() != Color::black() && !matches!(body, Body::Plane(_))
     }
+
+    #[cfg(test)]
+    fn is_shadowed(&self, position: Tuple, time: F, stats: &mut RenderStats) -> bool {
+        self.is_shadowed_from(position, self.lights[0].position, None, time, stats)
+    }
+
+    // whether anything but the ignored body stands between position and the light
+    fn is_shadowed_from(&self, position: Tuple, light_position: Tuple, ignored: Option<&Body>, time: F, stats: &mut RenderStats) -> bool {
+        stats.shadow_rays += 1;
+        let v = light_position - position;
+        let distance = v.magnitude();
+        let direction = v.normalize();
+        let r = Ray::new(position, direction).with_time(time);
+
+        let xs = self.intersect_with_stats(r, stats);
+        xs.into_iter()
+            .any(|hit| hit.t > 0.0 && hit.t < distance && Some(&hit.body) != ignored)
+    }
+}
+
+// n points evenly spread over the unit sphere
+fn fibonacci_sphere(n: usize) -> impl Iterator<Item = Tuple> {
+    let golden_angle = std::f64::consts::PI * (3.0 - (5.0 as F).sqrt());
+    (0..n).map(move |i| {
+        let y = 1.0 - 2.0 * (i as F + 0.5) / n as F;
+        let radius = (1.0 - y * y).sqrt();
+        let phi = i as F * golden_angle;
+        Tuple::point(radius * phi.cos(), y, radius * phi.sin())
+    })
 }
 
 impl Default for World {
@@ -98,13 +456,26 @@ impl Default for World {
             bodies: vec![],
             lights: vec![],
             reflection_limit : 5,
+            background: Background::default(),
+            emitter_samples: 0,
+            reflection_samples: 8,
+            caustics: None,
+            fog: None,
+            ambient_occlusion: None,
         }
     }
 }
 
 impl FuzzyEq<World> for World {
 	fn fuzzy_eq(&self, other: World) -> bool {
-	  self.bodies.fuzzy_eq(other.bodies) && self.lights.fuzzy_eq(other.lights)
+	  self.bodies.fuzzy_eq(other.bodies)
+        && self.lights.fuzzy_eq(other.lights)
+        && self.background.fuzzy_eq(other.background)
+        && self.emitter_samples == other.emitter_samples
+        && self.reflection_samples == other.reflection_samples
+        && self.caustics.fuzzy_eq(other.caustics)
+        && self.fog.fuzzy_eq(other.fog)
+        && self.ambient_occlusion.fuzzy_eq(other.ambient_occlusion)
 	}
   }
 
@@ -112,9 +483,12 @@ impl FuzzyEq<World> for World {
 mod tests {
     use crate::color::*;
 
+    use crate::plane::Plane;
     use crate::sphere::Sphere;
 
     use super::*;
+    use crate::{assert_fuzzy_eq, assert_fuzzy_ne};
+    use crate::EPSILON;
     use crate::matrix::*;
     use crate::tuple::*;
     #[test]
@@ -161,6 +535,18 @@ mod tests {
         assert!(world.lights.contains(&light));
     }
 
+    #[test]
+    fn worlds_differing_in_their_settings_differ() {
+        let world = create_default_world();
+
+        assert_fuzzy_eq!(world.clone(), create_default_world());
+        assert_fuzzy_ne!(world.clone().with_emitter_samples(16), create_default_world());
+        assert_fuzzy_ne!(world.clone().with_reflection_samples(16), create_default_world());
+        assert_fuzzy_ne!(world.clone().with_caustics(Caustics::default()), create_default_world());
+        assert_fuzzy_ne!(world.clone().with_fog(Fog::default()), create_default_world());
+        assert_fuzzy_ne!(world.clone().with_ambient_occlusion(AmbientOcclusion::default()), create_default_world());
+    }
+
     #[test]
     fn intersect_a_world_with_a_ray() {
         let w = create_default_world();
@@ -183,6 +569,42 @@ mod tests {
         assert_eq!(c, Color::black());
     }
 
+    #[test]
+    fn the_color_when_a_ray_misses_is_the_background() {
+        let background = Background::Gradient {
+            bottom: Color::white(),
+            top: Color::new(0.2, 0.4, 0.8),
+        };
+        let w = create_default_world().with_background(background);
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+        let c = w.color_at(r);
+
+        assert_eq!(c, Color::new(0.2, 0.4, 0.8));
+    }
+
+    #[test]
+    fn reflective_surfaces_reflect_the_background() {
+        let mirror = Material::from(
+            Phong::default()
+                .with_color(Color::black())
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_reflective(1.0),
+        );
+        let floor = Body::from(crate::plane::Plane::default().with_material(mirror));
+        let light = PointLight::new(Tuple::point(0.0, 10.0, 0.0), Color::white());
+        let w = World::new(vec![floor], vec![light])
+            .with_background(Background::Solid(Color::new(0.1, 0.2, 0.3)));
+        let sqrt2_over_2 = (2.0 as crate::F).sqrt() / 2.0;
+        let r = Ray::new(
+            Tuple::point(0.0, 1.0, -1.0),
+            Tuple::vector(0.0, -sqrt2_over_2, sqrt2_over_2),
+        );
+
+        assert_eq!(w.color_at(r), Color::new(0.1, 0.2, 0.3));
+    }
+
     #[test]
     fn the_color_when_a_ray_hits() {
         let w = create_default_world();
@@ -196,7 +618,7 @@ mod tests {
     fn there_is_no_shadow_when_nothing_is_colinear_with_point_and_light() {
         let w = create_default_world();
         let p = Tuple::point(0.0, 10.0, 0.0);
-        let is_in_shadow = w.is_shadowed(p);
+        let is_in_shadow = w.is_shadowed(p, 0.0, &mut RenderStats::default());
 
         assert_eq!(is_in_shadow, false);
     }
@@ -205,7 +627,7 @@ mod tests {
     fn there_is_shadow_when_an_object_is_between_the_point_and_the_light() {
         let w = create_default_world();
         let p = Tuple::point(10.0, -10.0, 10.0);
-        let is_in_shadow = w.is_shadowed(p);
+        let is_in_shadow = w.is_shadowed(p, 0.0, &mut RenderStats::default());
 
         assert_eq!(is_in_shadow, true);
     }
@@ -214,7 +636,7 @@ mod tests {
     fn there_is_no_shadow_when_an_object_is_behind_the_light() {
         let w = create_default_world();
         let p = Tuple::point(-20.0, 20.0, -20.0);
-        let is_in_shadow = w.is_shadowed(p);
+        let is_in_shadow = w.is_shadowed(p, 0.0, &mut RenderStats::default());
 
         assert_eq!(is_in_shadow, false);
     }
@@ -223,11 +645,178 @@ mod tests {
     fn there_is_no_shadow_when_an_object_is_behind_the_point() {
         let w = create_default_world();
         let p = Tuple::point(-2.0, 2.0, -2.0);
-        let is_in_shadow = w.is_shadowed(p);
+        let is_in_shadow = w.is_shadowed(p, 0.0, &mut RenderStats::default());
 
         assert_eq!(is_in_shadow, false);
     }
 
+    #[test]
+    fn shadows_follow_moving_bodies() {
+        let blocker = Sphere::default()
+            .with_transform(Matrix::translation(0.0, 5.0, 0.0))
+            .with_end_transform(Matrix::translation(10.0, 5.0, 0.0));
+        let light = PointLight::new(Tuple::point(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let w = World::new(vec![Body::from(blocker)], vec![light]);
+        let p = Tuple::point(0.0, 0.0, 0.0);
+
+        assert!(w.is_shadowed(p, 0.0, &mut RenderStats::default()));
+        assert!(!w.is_shadowed(p, 1.0, &mut RenderStats::default()));
+    }
+
+    #[test]
+    fn fresnel_floors_reflect_more_at_grazing_angles() {
+        let water = Plane::default().with_material(Material::from(Phong::default().with_reflective(0.02).with_fresnel(true)));
+        let sky = Background::Solid(Color::white());
+        let w = World::new(vec![Body::from(water)], vec![]).with_background(sky);
+        let reflection_at = |direction: Tuple| {
+            let ray = Ray::new(Tuple::point(0.0, 1.0, 0.0), direction.normalize());
+            let hit = w.intersect(ray).hit().unwrap();
+            w.reflect_color_at(&hit.body.material(), &hit.get_computed(), 1)
+        };
+
+        assert_eq!(reflection_at(Tuple::vector(0.0, -1.0, 0.0)), Color::new(0.02, 0.02, 0.02));
+        assert!(reflection_at(Tuple::vector(0.0, -1.0, 20.0)).red > 0.5);
+    }
+
+    #[test]
+    fn rough_floors_blur_reflections() {
+        let lamp = Sphere::default()
+            .with_transform(Matrix::translation(0.0, 3.0, 0.0))
+            .with_material(Material::from(Phong::default().with_emission(Color::white())));
+        let floor = |roughness: F| {
+            Plane::default().with_material(Material::from(Phong::default().with_reflective(1.0).with_roughness(roughness)))
+        };
+        let reflection_at = |roughness: F, x: F| {
+            let w = World::new(vec![Body::from(lamp.clone()), Body::from(floor(roughness))], vec![])
+                .with_reflection_samples(64);
+            let ray = Ray::new(Tuple::point(x, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+            let hit = w.intersect(ray).hit().unwrap();
+            w.reflect_color_at(&hit.body.material(), &hit.get_computed(), w.reflection_limit)
+        };
+
+        assert_eq!(reflection_at(0.0, 0.0), Color::white());
+        assert_eq!(reflection_at(0.0, 2.0), Color::black());
+        // part of the cone misses the lamp straight above, and part of it
+        // reaches the lamp beside the mirror direction
+        let below = reflection_at(0.6, 0.0);
+        let beside = reflection_at(0.6, 2.0);
+        assert!(below.red > 0.1 && below.red < 0.9);
+        assert!(beside.red > 0.0 && beside.red < below.red);
+        // the same rays are cast each time
+        assert_eq!(reflection_at(0.6, 2.0), beside);
+    }
+
+    #[test]
+    fn fog_fades_bodies_into_its_color() {
+        let fog = Fog::default().with_color(Color::new(0.5, 0.5, 0.5)).with_density(0.2);
+        let glowing = Phong::default().with_emission(Color::white()).with_ambient(0.0);
+        let sphere = Sphere::default().with_material(Material::from(glowing));
+        let w = World::new(vec![Body::from(sphere)], vec![]).with_fog(fog);
+
+        let towards = w.color_at(Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0)));
+        let away = w.color_at(Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, -1.0)));
+
+        // 4 units of fog in front of the sphere
+        let seen = (-0.8 as F).exp();
+        assert_eq!(towards, Color::white() * seen + Color::new(0.5, 0.5, 0.5) * (1.0 - seen));
+        assert_eq!(away, Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn smoke_scatters_the_light_reaching_it() {
+        let smoke = |medium: Medium| Body::from(Sphere::default().with_medium(medium));
+        let light = PointLight::new(Tuple::point(0.0, 10.0, 0.0), Color::white());
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        let thin = World::new(vec![smoke(Medium::default().with_density(0.2))], vec![light]).color_at(r);
+        let thick = World::new(vec![smoke(Medium::default().with_density(0.5))], vec![light]).color_at(r);
+        let blue = World::new(vec![smoke(Medium::default().with_color(Color::new(0.0, 0.0, 1.0)))], vec![light]).color_at(r);
+        let blocker = Body::from(Sphere::default().with_transform(Matrix::translation(0.0, 5.0, 0.0) * Matrix::scaling(3.0, 1.0, 3.0)));
+        let in_shadow = World::new(vec![smoke(Medium::default()), blocker], vec![light]).color_at(r);
+
+        assert!(thin.red > 0.0 && thin.red < thick.red && thick.red < 1.0);
+        assert!(blue.red == 0.0 && blue.blue > 0.0);
+        assert_eq!(in_shadow, Color::black());
+    }
+
+    #[test]
+    fn bodies_show_through_smoke() {
+        let smoke = Sphere::default().with_medium(Medium::default().with_density(0.5));
+        let glowing = Phong::default().with_emission(Color::white()).with_ambient(0.0);
+        let wall = Plane::default()
+            .with_transform(Matrix::translation(0.0, 0.0, 5.0) * Matrix::rotation_x(std::f64::consts::PI / 2.0))
+            .with_material(Material::from(glowing));
+        let w = World::new(vec![Body::from(smoke), Body::from(wall)], vec![]);
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        // no light to scatter, the smoke only dims the wall over its diameter
+        assert_eq!(w.color_at(r), Color::white() * (-1.0 as F).exp());
+        assert_eq!(w.intersect(r).len(), 1);
+    }
+
+    #[test]
+    fn smoke_dims_the_light_it_stands_in_front_of() {
+        let smoke = Sphere::default()
+            .with_transform(Matrix::translation(0.0, 5.0, 0.0))
+            .with_medium(Medium::default().with_density(0.5));
+        let light = PointLight::new(Tuple::point(0.0, 10.0, 0.0), Color::white());
+        let clear = World::new(vec![Body::from(Plane::default())], vec![light]);
+        let smoky = World::new(vec![Body::from(Plane::default()), Body::from(smoke)], vec![light]);
+        let r = Ray::new(Tuple::point(0.0, 1.0, -1.0), Tuple::vector(0.0, -1.0, 1.0).normalize());
+
+        // the light crosses the whole diameter of the smoke, the ambient term
+        // is left alone
+        let ambient = Color::new(0.1, 0.1, 0.1);
+        assert_eq!(smoky.color_at(r), ambient + (clear.color_at(r) - ambient) * (-1.0 as F).exp());
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_the_ambient_term_near_bodies() {
+        let ball = Sphere::default().with_transform(Matrix::translation(0.0, 1.0, 0.0));
+        // a light below the floor leaves only the ambient term
+        let light = PointLight::new(Tuple::point(0.0, -10.0, 0.0), Color::white());
+        let w = World::new(vec![Body::from(ball), Body::from(Plane::default())], vec![light]);
+        let down_at = |x: F| Ray::new(Tuple::point(x, 0.5, -2.0), Tuple::vector(0.0, -0.5, 2.0).normalize());
+
+        assert_eq!(w.color_at(down_at(0.0)), w.color_at(down_at(10.0)));
+
+        let w = w.with_ambient_occlusion(AmbientOcclusion::default().with_samples(64).with_radius(2.0));
+        let next_to_the_ball = w.color_at(down_at(0.0));
+        let in_the_open = w.color_at(down_at(10.0));
+        assert_eq!(in_the_open, Color::new(0.1, 0.1, 0.1));
+        assert!(next_to_the_ball.red < 0.08 && next_to_the_ball.red > 0.0);
+    }
+
+    #[test]
+    fn emission_shows_without_any_light() {
+        let glowing = Phong::default().with_emission(Color::new(0.5, 0.2, 0.0));
+        let w = World::new(vec![Body::from(Sphere::default().with_material(Material::from(glowing)))], vec![]);
+        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.color_at(r), Color::new(0.5, 0.2, 0.0));
+    }
+
+    #[test]
+    fn emissive_bodies_light_the_others_when_sampled() {
+        let lamp = Sphere::default()
+            .with_transform(Matrix::translation(0.0, 3.0, 0.0) * Matrix::scaling(0.5, 0.5, 0.5))
+            .with_material(Material::from(Lambert::default().with_emission(Color::new(1.0, 1.0, 1.0))));
+        let floor = Plane::default().with_material(Material::from(Lambert::default().with_ambient(0.0)));
+        let blocker = Sphere::default().with_transform(Matrix::translation(3.0, 1.5, 0.0) * Matrix::scaling(0.5, 0.5, 0.5));
+        let w = World::new(vec![Body::from(lamp), Body::from(floor), Body::from(blocker)], vec![]);
+        let below_the_lamp = Ray::new(Tuple::point(0.0, 1.0, -1.0), Tuple::vector(0.0, -1.0, 1.0).normalize());
+        let behind_the_blocker = Ray::new(Tuple::point(6.0, 1.0, -1.0), Tuple::vector(0.0, -1.0, 1.0).normalize());
+
+        assert_eq!(w.color_at(below_the_lamp), Color::black());
+
+        let w = w.with_emitter_samples(32);
+        let lit = w.color_at(below_the_lamp);
+        let shadowed = w.color_at(behind_the_blocker);
+        // straight below a fully visible lamp, like a point light of the same intensity
+        assert!(lit.red > 0.85 && lit.red <= 0.9 + EPSILON);
+        assert!(shadowed.red < 0.1);
+    }
+
     //   #[test]
     //   fn the_color_when_a_ray_hits_something_in_shadow() {
     //     // let material = Material::default();
@@ -1,11 +1,15 @@
 use std::collections::HashMap;
 
+use crate::animation::Animation;
 use crate::camera::Camera;
 use crate::world::World;
 
 pub type LoaderResult = anyhow::Result<(World, HashMap<String, Camera>)>;
+// the world and cameras are the scene as it is without any keyframe
+pub type AnimationLoaderResult = anyhow::Result<(World, HashMap<String, Camera>, Animation)>;
 pub trait WorldLoader {
   fn load_world<T: AsRef<str>>(&self, source: T) -> LoaderResult;
+  fn load_animation<T: AsRef<str>>(&self, source: T) -> AnimationLoaderResult;
 }
 
 pub mod yaml;
\ No newline at end of file
@@ -1,20 +1,38 @@
 use std::collections::HashMap;
 use std::f64::consts::PI;
 
-use super::{LoaderResult, WorldLoader};
+use super::{AnimationLoaderResult, LoaderResult, WorldLoader};
 use anyhow::*;
 use itertools::Itertools;
 use yaml_rust::{yaml, YamlLoader};
 
+use crate::animation::{Animation, CameraView, Easing, Interpolate, Keyframe, TransformStep, Track, compose};
+use crate::ambient_occlusion::AmbientOcclusion;
+use crate::aov::Aov;
+use crate::background::{Background, CubeMap};
 use crate::body::Body;
+use crate::bump::{Bump, BumpKind};
 use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::canvas::from_png::FromPNG;
+use crate::canvas::from_ppm::FromPPM;
 use crate::color::Color;
+use crate::filter::Filter;
 use crate::lights::PointLight;
-use crate::material::{Material, Phong};
+use crate::material::{BlinnPhong, Lambert, Material, Pbr, Phong, Toon};
 use crate::matrix::Matrix;
-use crate::pattern::{Pattern, Striped, Gradient, Ring, Checker};
+use crate::integrator::{Integrator, PathTracer};
+use crate::media::{Fog, Medium};
+use crate::motion_blur::MotionBlur;
+use crate::photon_map::Caustics;
+use crate::normal_map::NormalMap;
+use crate::outline::Outline;
+use crate::pattern::{Pattern, Striped, Gradient, Ring, Checker, UvChecker, Marble, Wood, ImageTexture};
 use crate::plane::Plane;
 use crate::sphere::Sphere;
+use crate::stereo::{Stereo, StereoLayout};
+use crate::texture::Texture;
+use crate::tone_mapping::{ToneMap, ToneMapping};
 use crate::tuple::Tuple;
 use crate::world::World;
 use crate::F;
@@ -58,6 +76,59 @@ macro_rules! key {
 
 type ParserResult<T = ()> = anyhow::Result<T>;
 
+// Material fields shared by several material types, so that each one is
+// parsed in a single place. The setters are the ones of the materials.
+trait MaterialFields: Sized {
+    fn with_color(self, color: Color) -> Self;
+    fn with_ambient(self, ambient: F) -> Self;
+    fn with_reflective(self, reflective: F) -> Self;
+    fn with_fresnel(self, fresnel: bool) -> Self;
+    fn with_roughness(self, roughness: F) -> Self;
+    fn with_emission(self, emission: Color) -> Self;
+}
+
+trait DiffuseFields: Sized {
+    fn with_pattern(self, pattern: Pattern) -> Self;
+    fn with_diffuse(self, diffuse: F) -> Self;
+}
+
+trait SpecularFields: Sized {
+    fn with_specular(self, specular: F) -> Self;
+    fn with_shininess(self, shininess: F) -> Self;
+}
+
+trait PerturbedFields: Sized {
+    fn with_bump(self, bump: Bump) -> Self;
+    fn with_normal_map(self, normal_map: NormalMap) -> Self;
+}
+
+macro_rules! material_fields {
+    ($trait:ident for [$($material:ident),+] $setters:tt) => {
+        $(material_fields!(@impl $trait, $material, $setters);)+
+    };
+    (@impl $trait:ident, $material:ident, { $($setter:ident($value:ty)),+ }) => {
+        impl $trait for $material {
+            $(
+                fn $setter(self, value: $value) -> Self {
+                    $material::$setter(self, value)
+                }
+            )+
+        }
+    };
+}
+
+material_fields!(MaterialFields for [Phong, Toon, Lambert, BlinnPhong, Pbr] {
+    with_color(Color),
+    with_ambient(F),
+    with_reflective(F),
+    with_fresnel(bool),
+    with_roughness(F),
+    with_emission(Color)
+});
+material_fields!(DiffuseFields for [Phong, Toon, Lambert, BlinnPhong] { with_pattern(Pattern), with_diffuse(F) });
+material_fields!(SpecularFields for [Phong, Toon, BlinnPhong] { with_specular(F), with_shininess(F) });
+material_fields!(PerturbedFields for [Phong, Lambert, BlinnPhong, Pbr] { with_bump(Bump), with_normal_map(NormalMap) });
+
 #[derive(Default)]
 pub struct YamlParser<'a> {
     data: &'a str,
@@ -65,6 +136,15 @@ pub struct YamlParser<'a> {
     lights: Vec<PointLight>,
     bodies: Vec<Body>,
     cameras: HashMap<String, Camera>,
+    background: Background,
+    emitter_samples: usize,
+    reflection_samples: usize,
+    caustics: Option<Caustics>,
+    fog: Option<Fog>,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    animation: Animation,
+    // images already decoded, by file name and whether they hold colors
+    textures: HashMap<(String, bool), Texture>,
 }
 impl<'a> YamlParser<'a> {
     pub fn new(data: &'a str) -> Self {
@@ -74,6 +154,14 @@ impl<'a> YamlParser<'a> {
             lights: Vec::new(),
             bodies: Vec::new(),
             cameras: HashMap::new(),
+            background: Background::default(),
+            emitter_samples: 0,
+            reflection_samples: 8,
+            caustics: None,
+            fog: None,
+            ambient_occlusion: None,
+            animation: Animation::default(),
+            textures: HashMap::new(),
         }
     }
 
@@ -102,7 +190,7 @@ impl<'a> YamlParser<'a> {
         array: &'b yaml::Array,
         index: usize,
     ) -> ParserResult<&'b yaml::Yaml> {
-        if index > array.len() {
+        if index >= array.len() {
             Err(anyhow!(
         "Tried to get value with index {} from array at {}: Index not found (Array length = {}).",
         index,
@@ -163,6 +251,27 @@ impl<'a> YamlParser<'a> {
         result
     }
 
+    #[inline(always)]
+    fn value_to_bool(&self, yaml: &yaml::Yaml) -> ParserResult<bool> {
+        match yaml {
+            yaml::Yaml::Boolean(content) => Ok(*content),
+            _ => Err(anyhow!(
+                "Expected boolean value at {}, but found {:?}",
+                self.path.to_string(),
+                yaml
+            )),
+        }
+    }
+
+    #[inline(always)]
+    fn hash_value_to_bool(&mut self, hash: &yaml::Hash, key: impl AsRef<str>) -> ParserResult<bool> {
+        self.path.push(Segment::Key(key.as_ref().into()));
+        let value = self.get_value_from_hash(hash, key)?;
+        let result = self.value_to_bool(value);
+        self.path.pop();
+        result
+    }
+
     #[inline(always)]
     fn value_to_float(&self, yaml: &yaml::Yaml) -> ParserResult<F> {
         match yaml {
@@ -240,7 +349,20 @@ impl<'a> YamlParser<'a> {
         let cameras_clone = self.cameras.clone();
         let bodies_clone = self.bodies.clone();
         let lights_clone = self.lights.clone();
-        Ok((World::new(bodies_clone, lights_clone), cameras_clone))
+        let mut world = World::new(bodies_clone, lights_clone)
+            .with_background(self.background.clone())
+            .with_emitter_samples(self.emitter_samples)
+            .with_reflection_samples(self.reflection_samples);
+        if let Some(ref caustics) = self.caustics {
+            world = world.with_caustics(caustics.clone());
+        }
+        if let Some(fog) = self.fog {
+            world = world.with_fog(fog);
+        }
+        if let Some(ambient_occlusion) = self.ambient_occlusion {
+            world = world.with_ambient_occlusion(ambient_occlusion);
+        }
+        Ok((world, cameras_clone))
     }
 
     fn visit_document(&mut self, document: &yaml_rust::Yaml) -> ParserResult {
@@ -276,6 +398,16 @@ impl<'a> YamlParser<'a> {
             let (name, camera) = self.visit_camera(camera_value)?;
             self.path.pop();
             self.cameras.insert(name, camera);
+        } else if item_hash.contains_key(key!("world")) {
+            let world_value = self.get_value_from_hash(item_hash, "world")?;
+            self.path.push(Segment::Key("world".into()));
+            self.visit_world(world_value)?;
+            self.path.pop();
+        } else if item_hash.contains_key(key!("animation")) {
+            let animation_value = self.get_value_from_hash(item_hash, "animation")?;
+            self.path.push(Segment::Key("animation".into()));
+            self.visit_animation(animation_value)?;
+            self.path.pop();
         } else {
             return Err(anyhow!(format!(
                 "Unknown item type found at {}",
@@ -285,6 +417,210 @@ impl<'a> YamlParser<'a> {
         Ok(())
     }
 
+    fn visit_animation(&mut self, animation: &yaml::Yaml) -> ParserResult {
+        let animation_hash = self.value_to_hash(animation)?;
+        let frames_value = self.get_value_from_hash(animation_hash, "frames")?;
+        self.path.push(Segment::Key("frames".into()));
+        let frames_array = self.value_to_array(frames_value)?;
+        let first_value = self.get_index_from_array(frames_array, 0)?;
+        let first = self.value_to_int(first_value)?;
+        let last_value = self.get_index_from_array(frames_array, 1)?;
+        let last = self.value_to_int(last_value)?;
+        if first < 0 || last < first {
+            return Err(anyhow!(
+                "Expected a frame range [first, last] at {}, but found [{}, {}]",
+                self.path.to_string(),
+                first,
+                last
+            ));
+        }
+        self.path.pop();
+        self.animation.frames = Some(first as usize..=last as usize);
+        Ok(())
+    }
+
+    // every keyframe is a hash with a frame, an optional easing and the values
+    // read by visit_value
+    fn visit_keyframes<T: Interpolate>(
+        &mut self,
+        hash: &yaml::Hash,
+        mut visit_value: impl FnMut(&mut Self, &yaml::Hash) -> ParserResult<T>,
+    ) -> ParserResult<Track<T>> {
+        let keyframes_value = self.get_value_from_hash(hash, "keyframes")?;
+        self.path.push(Segment::Key("keyframes".into()));
+        let keyframes_array = self.value_to_array(keyframes_value)?;
+        let mut keyframes = Vec::new();
+        for (index, keyframe) in keyframes_array.iter().enumerate() {
+            self.path.push(Segment::Index(index));
+            let keyframe_hash = self.value_to_hash(keyframe)?;
+            let frame = self.hash_value_to_int(keyframe_hash, "frame")?;
+            if frame < 0 {
+                return Err(anyhow!(
+                    "Keyframe frame must not be negative, got {} at {}",
+                    frame,
+                    self.path.to_string()
+                ));
+            }
+            let value = visit_value(self, keyframe_hash)?;
+            let mut keyframe = Keyframe::new(frame as usize, value);
+            if keyframe_hash.contains_key(key!("easing")) {
+                let easing = self.hash_value_to_string(keyframe_hash, "easing")?;
+                let easing = match easing.as_ref() {
+                    "linear" => Easing::Linear,
+                    "smooth" => Easing::Smooth,
+                    _ => {
+                        return Err(anyhow!(
+                            "Unknown easing '{}' found at {}",
+                            easing.as_ref(),
+                            self.path.to_string()
+                        ))
+                    }
+                };
+                keyframe = keyframe.with_easing(easing);
+            }
+            keyframes.push(keyframe);
+            self.path.pop();
+        }
+        let track = Track::new(keyframes)
+            .map_err(|error| anyhow!("{} at {}", error, self.path.to_string()));
+        self.path.pop();
+        track
+    }
+
+    fn visit_world(&mut self, world: &yaml::Yaml) -> ParserResult {
+        let world_hash = self.value_to_hash(world)?;
+
+        if world_hash.contains_key(key!("emitter_samples")) {
+            let emitter_samples = self.hash_value_to_int(world_hash, "emitter_samples")?;
+            if emitter_samples < 0 {
+                return Err(anyhow!(
+                    "Emitter samples must not be negative, got {} at {}",
+                    emitter_samples,
+                    self.path.to_string()
+                ));
+            }
+            self.emitter_samples = emitter_samples as usize;
+        }
+        if world_hash.contains_key(key!("reflection_samples")) {
+            let reflection_samples = self.hash_value_to_int(world_hash, "reflection_samples")?;
+            if reflection_samples < 1 {
+                return Err(anyhow!(
+                    "Glossy reflections need at least one sample, got {} at {}",
+                    reflection_samples,
+                    self.path.to_string()
+                ));
+            }
+            self.reflection_samples = reflection_samples as usize;
+        }
+        if world_hash.contains_key(key!("caustics")) {
+            let caustics_value = self.get_value_from_hash(world_hash, "caustics")?;
+            self.path.push(Segment::Key("caustics".into()));
+            self.caustics = Some(self.visit_caustics(caustics_value)?);
+            self.path.pop();
+        }
+        if world_hash.contains_key(key!("fog")) {
+            let fog_value = self.get_value_from_hash(world_hash, "fog")?;
+            self.path.push(Segment::Key("fog".into()));
+            self.fog = Some(self.visit_fog(fog_value)?);
+            self.path.pop();
+        }
+        if world_hash.contains_key(key!("ambient_occlusion")) {
+            let ambient_occlusion_value = self.get_value_from_hash(world_hash, "ambient_occlusion")?;
+            self.path.push(Segment::Key("ambient_occlusion".into()));
+            self.ambient_occlusion = Some(self.visit_ambient_occlusion(ambient_occlusion_value)?);
+            self.path.pop();
+        }
+
+        if world_hash.contains_key(key!("background")) {
+            let background_value = self.get_value_from_hash(world_hash, "background")?;
+            self.path.push(Segment::Key("background".into()));
+            self.background = self.visit_background(background_value)?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    fn visit_background(&mut self, background: &yaml::Yaml) -> ParserResult<Background> {
+        let background_hash = self.value_to_hash(background)?;
+        let background_type = self.hash_value_to_string(background_hash, "type")?;
+
+        match background_type.as_ref() {
+            "solid" => {
+                let color_value = self.get_value_from_hash(background_hash, "color")?;
+                self.path.push(Segment::Key("color".into()));
+                let color = self.visit_color(color_value)?;
+                self.path.pop();
+                Ok(Background::Solid(color))
+            }
+            "gradient" => {
+                let bottom_value = self.get_value_from_hash(background_hash, "bottom")?;
+                self.path.push(Segment::Key("bottom".into()));
+                let bottom = self.visit_color(bottom_value)?;
+                self.path.pop();
+                let top_value = self.get_value_from_hash(background_hash, "top")?;
+                self.path.push(Segment::Key("top".into()));
+                let top = self.visit_color(top_value)?;
+                self.path.pop();
+                Ok(Background::Gradient { bottom, top })
+            }
+            "equirectangular" => {
+                let texture = self.visit_texture(background_hash, "file", true)?;
+                Ok(Background::Equirectangular(texture))
+            }
+            "cube_map" => {
+                let left = self.visit_texture(background_hash, "left", true)?;
+                let front = self.visit_texture(background_hash, "front", true)?;
+                let right = self.visit_texture(background_hash, "right", true)?;
+                let back = self.visit_texture(background_hash, "back", true)?;
+                let up = self.visit_texture(background_hash, "up", true)?;
+                let down = self.visit_texture(background_hash, "down", true)?;
+                Ok(Background::CubeMap(CubeMap::new(left, front, right, back, up, down)))
+            }
+            _ => Err(anyhow!(
+                "Unknown background type '{}' found at {}",
+                background_type.as_ref(),
+                self.path.to_string()
+            )),
+        }
+    }
+
+    // Image files are looked up relative to the working directory, each one
+    // is read once and shared by everything using it. Colors are stored sRGB
+    // encoded and turned back into linear values, other data such as normal
+    // maps is used as is.
+    fn visit_texture(&mut self, hash: &yaml::Hash, key: impl AsRef<str>, colors: bool) -> ParserResult<Texture> {
+        let file_name = self.hash_value_to_string(hash, key.as_ref())?.as_ref().to_string();
+        if let Some(texture) = self.textures.get(&(file_name.clone(), colors)) {
+            return Ok(texture.clone());
+        }
+        self.path.push(Segment::Key(key.as_ref().into()));
+        let result = std::fs::read(&file_name)
+            .map_err(|error| anyhow!(
+                "Could not read image file '{}' at {}: {}",
+                file_name,
+                self.path.to_string(),
+                error
+            ))
+            .and_then(|data| {
+                // anything that is not a PPM is expected to be a PNG
+                let decoded = if file_name.ends_with(".ppm") {
+                    Canvas::from_ppm(&data)
+                } else {
+                    Canvas::from_png(&data)
+                };
+                decoded.map_err(|error| anyhow!(
+                    "Could not decode image file '{}' at {}: {}",
+                    file_name,
+                    self.path.to_string(),
+                    error
+                ))
+            });
+        self.path.pop();
+        let texture = if colors { Texture::from_srgb(result?) } else { Texture::new(result?) };
+        self.textures.insert((file_name, colors), texture.clone());
+        Ok(texture)
+    }
+
     fn visit_light(&mut self, light: &yaml::Yaml) -> ParserResult<PointLight> {
         let light_hash = self.value_to_hash(light)?;
         let light_type = self.hash_value_to_string(light_hash, "type")?;
@@ -300,6 +636,18 @@ impl<'a> YamlParser<'a> {
             let light_intensity = self.visit_color(light_intensity_value)?;
             self.path.pop();
 
+            if light_hash.contains_key(key!("keyframes")) {
+                let track = self.visit_keyframes(light_hash, |parser, keyframe_hash| {
+                    let at_value = parser.get_value_from_hash(keyframe_hash, "at")?;
+                    parser.path.push(Segment::Key("at".into()));
+                    let at = parser.visit_point(at_value);
+                    parser.path.pop();
+                    at
+                })?;
+                // the light is about to be pushed
+                self.animation.lights.push((self.lights.len(), track));
+            }
+
             Ok(PointLight::new(light_at, light_intensity))
         } else {
             Err(anyhow!(
@@ -361,7 +709,7 @@ impl<'a> YamlParser<'a> {
         Ok(Color::new(r, g, b))
     }
 
-    fn visit_pattern(&mut self, pattern: &yaml::Yaml) -> ParserResult<Pattern> {
+    fn visit_pattern(&mut self, pattern: &yaml::Yaml, colors: bool) -> ParserResult<Pattern> {
         let pattern_hash = self.value_to_hash(pattern)?;
         let pattern_type = self.hash_value_to_string(pattern_hash, "type")?;
 
@@ -370,6 +718,10 @@ impl<'a> YamlParser<'a> {
             "gradient" => self.visit_gradient_pattern(pattern_hash),
             "ring" => self.visit_ring_pattern(pattern_hash),
             "checker" => self.visit_checker_pattern(pattern_hash),
+            "uv_checker" => self.visit_uv_checker_pattern(pattern_hash),
+            "marble" => self.visit_marble_pattern(pattern_hash),
+            "wood" => self.visit_wood_pattern(pattern_hash),
+            "image" => self.visit_image_pattern(pattern_hash, colors),
             _ => Err(anyhow!(
                 "Unknown Pattern type '{}' found at {}",
                 pattern_type.as_ref(),
@@ -474,6 +826,133 @@ impl<'a> YamlParser<'a> {
             .with_transform(transform),
         ))
       }
+      fn visit_uv_checker_pattern(&mut self, pattern_hash: &yaml::Hash) -> ParserResult<Pattern> {
+        let mut checker = UvChecker::default();
+        let color_a_value = self.get_value_from_hash(pattern_hash, "colorA")?;
+        self.path.push(Segment::Key("colorA".into()));
+        let color_a = self.visit_color(color_a_value)?;
+        self.path.pop();
+        let color_b_value = self.get_value_from_hash(pattern_hash, "colorB")?;
+        self.path.push(Segment::Key("colorB".into()));
+        let color_b = self.visit_color(color_b_value)?;
+        self.path.pop();
+        checker = checker.with_colors(color_a, color_b);
+
+        if pattern_hash.contains_key(key!("width")) || pattern_hash.contains_key(key!("height")) {
+          let width = self.hash_value_to_float(pattern_hash, "width")?;
+          let height = self.hash_value_to_float(pattern_hash, "height")?;
+          checker = checker.with_size(width, height);
+        }
+
+        if pattern_hash.contains_key(key!("transforms")) {
+          let transforms_value = self.get_value_from_hash(pattern_hash, "transforms")?;
+          self.path.push(Segment::Key("transform".into()));
+          let transform = self.visit_transforms(transforms_value)?;
+          self.path.pop();
+          checker = checker.with_transform(transform);
+        }
+
+        Ok(Pattern::from(checker))
+      }
+      fn visit_image_pattern(&mut self, pattern_hash: &yaml::Hash, colors: bool) -> ParserResult<Pattern> {
+        let texture = self.visit_texture(pattern_hash, "file", colors)?;
+        let mut image = ImageTexture::new(texture);
+
+        if pattern_hash.contains_key(key!("transforms")) {
+          let transforms_value = self.get_value_from_hash(pattern_hash, "transforms")?;
+          self.path.push(Segment::Key("transform".into()));
+          let transform = self.visit_transforms(transforms_value)?;
+          self.path.pop();
+          image = image.with_transform(transform);
+        }
+
+        Ok(Pattern::from(image))
+      }
+      fn visit_marble_pattern(&mut self, pattern_hash: &yaml::Hash) -> ParserResult<Pattern> {
+        let mut marble = Marble::default();
+        if pattern_hash.contains_key(key!("colorA")) || pattern_hash.contains_key(key!("colorB")) {
+          let color_a_value = self.get_value_from_hash(pattern_hash, "colorA")?;
+          self.path.push(Segment::Key("colorA".into()));
+          let color_a = self.visit_color(color_a_value)?;
+          self.path.pop();
+          let color_b_value = self.get_value_from_hash(pattern_hash, "colorB")?;
+          self.path.push(Segment::Key("colorB".into()));
+          let color_b = self.visit_color(color_b_value)?;
+          self.path.pop();
+          marble = marble.with_colors(color_a, color_b);
+        }
+        if pattern_hash.contains_key(key!("frequency")) {
+          let frequency = self.hash_value_to_float(pattern_hash, "frequency")?;
+          marble = marble.with_frequency(frequency);
+        }
+        if pattern_hash.contains_key(key!("turbulence")) {
+          let turbulence = self.hash_value_to_float(pattern_hash, "turbulence")?;
+          let octaves = self.visit_octaves(pattern_hash, 6)?;
+          marble = marble.with_turbulence(turbulence, octaves);
+        } else if pattern_hash.contains_key(key!("octaves")) {
+          let octaves = self.visit_octaves(pattern_hash, 6)?;
+          marble = marble.with_octaves(octaves);
+        }
+        if pattern_hash.contains_key(key!("transforms")) {
+          let transforms_value = self.get_value_from_hash(pattern_hash, "transforms")?;
+          self.path.push(Segment::Key("transform".into()));
+          let transform = self.visit_transforms(transforms_value)?;
+          self.path.pop();
+          marble = marble.with_transform(transform);
+        }
+
+        Ok(Pattern::from(marble))
+      }
+      fn visit_wood_pattern(&mut self, pattern_hash: &yaml::Hash) -> ParserResult<Pattern> {
+        let mut wood = Wood::default();
+        if pattern_hash.contains_key(key!("colorA")) || pattern_hash.contains_key(key!("colorB")) {
+          let color_a_value = self.get_value_from_hash(pattern_hash, "colorA")?;
+          self.path.push(Segment::Key("colorA".into()));
+          let color_a = self.visit_color(color_a_value)?;
+          self.path.pop();
+          let color_b_value = self.get_value_from_hash(pattern_hash, "colorB")?;
+          self.path.push(Segment::Key("colorB".into()));
+          let color_b = self.visit_color(color_b_value)?;
+          self.path.pop();
+          wood = wood.with_colors(color_a, color_b);
+        }
+        if pattern_hash.contains_key(key!("rings")) {
+          let rings = self.hash_value_to_float(pattern_hash, "rings")?;
+          wood = wood.with_rings(rings);
+        }
+        if pattern_hash.contains_key(key!("turbulence")) {
+          let turbulence = self.hash_value_to_float(pattern_hash, "turbulence")?;
+          let octaves = self.visit_octaves(pattern_hash, 3)?;
+          wood = wood.with_turbulence(turbulence, octaves);
+        } else if pattern_hash.contains_key(key!("octaves")) {
+          let octaves = self.visit_octaves(pattern_hash, 3)?;
+          wood = wood.with_octaves(octaves);
+        }
+        if pattern_hash.contains_key(key!("transforms")) {
+          let transforms_value = self.get_value_from_hash(pattern_hash, "transforms")?;
+          self.path.push(Segment::Key("transform".into()));
+          let transform = self.visit_transforms(transforms_value)?;
+          self.path.pop();
+          wood = wood.with_transform(transform);
+        }
+
+        Ok(Pattern::from(wood))
+      }
+      fn visit_octaves(&mut self, pattern_hash: &yaml::Hash, default: usize) -> ParserResult<usize> {
+        if pattern_hash.contains_key(key!("octaves")) {
+          let octaves = self.hash_value_to_int(pattern_hash, "octaves")?;
+          if octaves < 0 {
+            return Err(anyhow!(
+              "Noise octaves must not be negative, got {} at {}",
+              octaves,
+              self.path.to_string()
+            ));
+          }
+          Ok(octaves as usize)
+        } else {
+          Ok(default)
+        }
+      }
 
     fn visit_body(&mut self, body: &yaml::Yaml) -> ParserResult<Body> {
         let mut material = Material::default();
@@ -496,14 +975,58 @@ impl<'a> YamlParser<'a> {
             self.path.pop();
         }
 
-        match body_type.as_ref() {
-            "sphere" => Ok(Body::from(Sphere::new(material, transform))),
-            "plane" => Ok(Body::from(Plane::new(material, transform))),
-            _ => Err(anyhow!(
-                "Unknown body type '{}' found at {}",
-                body_type.as_ref(),
-                self.path.to_string()
-            )),
+        if body_hash.contains_key(key!("keyframes")) {
+            let track = self.visit_keyframes(body_hash, |parser, keyframe_hash| {
+                let transforms_value = parser.get_value_from_hash(keyframe_hash, "transforms")?;
+                parser.path.push(Segment::Key("transforms".into()));
+                let steps = parser.visit_transform_steps(transforms_value);
+                parser.path.pop();
+                steps
+            })?;
+            if !body_hash.contains_key(key!("transforms")) {
+                transform = compose(&track.value_at(track.first_frame() as F));
+            }
+            // the body is about to be pushed
+            self.animation.bodies.push((self.bodies.len(), track));
+        }
+
+        let mut end_transform = None;
+        if body_hash.contains_key(key!("end_transforms")) {
+            let end_transforms_value = self.get_value_from_hash(body_hash, "end_transforms")?;
+            self.path.push(Segment::Key("end_transforms".into()));
+            end_transform = Some(self.visit_transforms(end_transforms_value)?);
+            self.path.pop();
+        }
+
+        let mut medium = None;
+        if body_hash.contains_key(key!("medium")) {
+            if body_type.as_ref() != "sphere" {
+                return Err(anyhow!(
+                    "Only spheres can be filled with a medium, found a {} at {}",
+                    body_type.as_ref(),
+                    self.path.to_string()
+                ));
+            }
+            let medium_value = self.get_value_from_hash(body_hash, "medium")?;
+            self.path.push(Segment::Key("medium".into()));
+            medium = Some(self.visit_medium(medium_value)?);
+            self.path.pop();
+        }
+
+        let body = match body_type.as_ref() {
+            "sphere" => Body::from(Sphere { medium, ..Sphere::new(material, transform) }),
+            "plane" => Body::from(Plane::new(material, transform)),
+            _ => {
+                return Err(anyhow!(
+                    "Unknown body type '{}' found at {}",
+                    body_type.as_ref(),
+                    self.path.to_string()
+                ))
+            }
+        };
+        match end_transform {
+            Some(end_transform) => Ok(body.with_end_transform(end_transform)),
+            None => Ok(body),
         }
     }
 
@@ -511,65 +1034,214 @@ impl<'a> YamlParser<'a> {
         let material_hash = self.value_to_hash(material)?;
         let material_type = self.hash_value_to_string(material_hash, "type")?;
 
-        if material_type.as_ref() == "phong" {
-            let mut phong_material = Phong::default();
-
-            if material_hash.contains_key(key!("color")) {
-                let color_value = self.get_value_from_hash(material_hash, "color")?;
-                self.path.push(Segment::Key("color".into()));
-                let material_color = self.visit_color(color_value)?;
-                self.path.pop();
-                phong_material = phong_material.with_color(material_color);
+        match material_type.as_ref() {
+            "phong" => {
+                let phong_material = self.visit_material_fields(material_hash, Phong::default())?;
+                let phong_material = self.visit_diffuse_fields(material_hash, phong_material)?;
+                let phong_material = self.visit_specular_fields(material_hash, phong_material)?;
+                let phong_material = self.visit_perturbed_fields(material_hash, phong_material)?;
+                Ok(Material::from(phong_material))
             }
-            if material_hash.contains_key(key!("pattern")) {
-                let pattern_value = self.get_value_from_hash(material_hash, "pattern")?;
-                self.path.push(Segment::Key("patter".into()));
-                let pattern = self.visit_pattern(pattern_value)?;
-                self.path.pop();
-                phong_material = phong_material.with_pattern(pattern);
-            }
-            if material_hash.contains_key(key!("diffuse")) {
-                let material_diffuse = self.hash_value_to_float(material_hash, "diffuse")?;
-                phong_material = phong_material.with_diffuse(material_diffuse);
+            "toon" => {
+                let toon_material = self.visit_material_fields(material_hash, Toon::default())?;
+                let toon_material = self.visit_diffuse_fields(material_hash, toon_material)?;
+                let mut toon_material = self.visit_specular_fields(material_hash, toon_material)?;
+                if material_hash.contains_key(key!("bands")) {
+                    let material_bands = self.hash_value_to_int(material_hash, "bands")?;
+                    if material_bands < 1 {
+                        return Err(anyhow!(
+                            "Toon materials need at least one band, got {} at {}",
+                            material_bands,
+                            self.path.to_string()
+                        ));
+                    }
+                    toon_material = toon_material.with_bands(material_bands as usize);
+                }
+                Ok(Material::from(toon_material))
             }
-            if material_hash.contains_key(key!("ambient")) {
-                let material_ambient = self.hash_value_to_float(material_hash, "ambient")?;
-                phong_material = phong_material.with_ambient(material_ambient);
+            "lambert" => {
+                let lambert_material = self.visit_material_fields(material_hash, Lambert::default())?;
+                let lambert_material = self.visit_diffuse_fields(material_hash, lambert_material)?;
+                let lambert_material = self.visit_perturbed_fields(material_hash, lambert_material)?;
+                Ok(Material::from(lambert_material))
             }
-            if material_hash.contains_key(key!("specular")) {
-                let material_specular = self.hash_value_to_float(material_hash, "specular")?;
-                phong_material = phong_material.with_specular(material_specular);
+            "blinn_phong" => {
+                let blinn_phong_material = self.visit_material_fields(material_hash, BlinnPhong::default())?;
+                let blinn_phong_material = self.visit_diffuse_fields(material_hash, blinn_phong_material)?;
+                let blinn_phong_material = self.visit_specular_fields(material_hash, blinn_phong_material)?;
+                let blinn_phong_material = self.visit_perturbed_fields(material_hash, blinn_phong_material)?;
+                Ok(Material::from(blinn_phong_material))
             }
-            if material_hash.contains_key(key!("shininess")) {
-                let material_shininess = self.hash_value_to_float(material_hash, "shininess")?;
-                phong_material = phong_material.with_shininess(material_shininess);
+            "pbr" => {
+                let pbr_material = self.visit_material_fields(material_hash, Pbr::default())?;
+                let mut pbr_material = self.visit_perturbed_fields(material_hash, pbr_material)?;
+                if material_hash.contains_key(key!("color_pattern")) {
+                    let pattern = self.visit_material_pattern(material_hash, "color_pattern", true)?;
+                    pbr_material = pbr_material.with_color_pattern(pattern);
+                }
+                if material_hash.contains_key(key!("metallic")) {
+                    let material_metallic = self.hash_value_to_float(material_hash, "metallic")?;
+                    pbr_material = pbr_material.with_metallic(material_metallic);
+                }
+                if material_hash.contains_key(key!("roughness_pattern")) {
+                    let pattern = self.visit_material_pattern(material_hash, "roughness_pattern", false)?;
+                    pbr_material = pbr_material.with_roughness_pattern(pattern);
+                }
+                Ok(Material::from(pbr_material))
             }
-            if material_hash.contains_key(key!("reflectiveness")) {
-                let material_reflectiveness = self.hash_value_to_float(material_hash, "reflectiveness")?;
-                phong_material = phong_material.with_reflective(material_reflectiveness);
-            }
-
-            Ok(Material::from(phong_material))
-        } else {
-            Err(anyhow!(
+            _ => Err(anyhow!(
                 "Unknown material type '{}' found at {}",
                 material_type.as_ref(),
                 self.path.to_string()
-            ))
+            )),
+        }
+    }
+
+    // fields every material type has
+    fn visit_material_fields<M: MaterialFields>(&mut self, material_hash: &yaml::Hash, material: M) -> ParserResult<M> {
+        let mut material = material;
+        if material_hash.contains_key(key!("color")) {
+            let material_color = self.visit_material_color(material_hash, "color")?;
+            material = material.with_color(material_color);
+        }
+        if material_hash.contains_key(key!("ambient")) {
+            let material_ambient = self.hash_value_to_float(material_hash, "ambient")?;
+            material = material.with_ambient(material_ambient);
+        }
+        if material_hash.contains_key(key!("reflectiveness")) {
+            let material_reflectiveness = self.hash_value_to_float(material_hash, "reflectiveness")?;
+            material = material.with_reflective(material_reflectiveness);
+        }
+        if material_hash.contains_key(key!("fresnel")) {
+            let material_fresnel = self.hash_value_to_bool(material_hash, "fresnel")?;
+            material = material.with_fresnel(material_fresnel);
+        }
+        if material_hash.contains_key(key!("roughness")) {
+            let material_roughness = self.hash_value_to_float(material_hash, "roughness")?;
+            material = material.with_roughness(material_roughness);
+        }
+        if material_hash.contains_key(key!("emission")) {
+            let emission = self.visit_material_color(material_hash, "emission")?;
+            material = material.with_emission(emission);
+        }
+        Ok(material)
+    }
+
+    fn visit_diffuse_fields<M: DiffuseFields>(&mut self, material_hash: &yaml::Hash, material: M) -> ParserResult<M> {
+        let mut material = material;
+        if material_hash.contains_key(key!("pattern")) {
+            let pattern = self.visit_material_pattern(material_hash, "pattern", true)?;
+            material = material.with_pattern(pattern);
+        }
+        if material_hash.contains_key(key!("diffuse")) {
+            let material_diffuse = self.hash_value_to_float(material_hash, "diffuse")?;
+            material = material.with_diffuse(material_diffuse);
+        }
+        Ok(material)
+    }
+
+    fn visit_specular_fields<M: SpecularFields>(&mut self, material_hash: &yaml::Hash, material: M) -> ParserResult<M> {
+        let mut material = material;
+        if material_hash.contains_key(key!("specular")) {
+            let material_specular = self.hash_value_to_float(material_hash, "specular")?;
+            material = material.with_specular(material_specular);
+        }
+        if material_hash.contains_key(key!("shininess")) {
+            let material_shininess = self.hash_value_to_float(material_hash, "shininess")?;
+            material = material.with_shininess(material_shininess);
+        }
+        Ok(material)
+    }
+
+    fn visit_perturbed_fields<M: PerturbedFields>(&mut self, material_hash: &yaml::Hash, material: M) -> ParserResult<M> {
+        let mut material = material;
+        if material_hash.contains_key(key!("bump")) {
+            let bump_value = self.get_value_from_hash(material_hash, "bump")?;
+            self.path.push(Segment::Key("bump".into()));
+            let bump = self.visit_bump(bump_value)?;
+            self.path.pop();
+            material = material.with_bump(bump);
+        }
+        if material_hash.contains_key(key!("normal_map")) {
+            let normal_map_value = self.get_value_from_hash(material_hash, "normal_map")?;
+            self.path.push(Segment::Key("normal_map".into()));
+            let normal_map = self.visit_normal_map(normal_map_value)?;
+            self.path.pop();
+            material = material.with_normal_map(normal_map);
+        }
+        Ok(material)
+    }
+
+    fn visit_material_color(&mut self, material_hash: &yaml::Hash, key: &str) -> ParserResult<Color> {
+        let color_value = self.get_value_from_hash(material_hash, key)?;
+        self.path.push(Segment::Key(key.into()));
+        let color = self.visit_color(color_value)?;
+        self.path.pop();
+        Ok(color)
+    }
+
+    // colors tells whether the pattern gives colors or other data, e.g. roughness
+    fn visit_material_pattern(&mut self, material_hash: &yaml::Hash, key: &str, colors: bool) -> ParserResult<Pattern> {
+        let pattern_value = self.get_value_from_hash(material_hash, key)?;
+        self.path.push(Segment::Key(key.into()));
+        let pattern = self.visit_pattern(pattern_value, colors)?;
+        self.path.pop();
+        Ok(pattern)
+    }
+
+    fn visit_bump(&mut self, bump: &yaml::Yaml) -> ParserResult<Bump> {
+        let bump_hash = self.value_to_hash(bump)?;
+        let bump_type = self.hash_value_to_string(bump_hash, "type")?;
+
+        let kind = match bump_type.as_ref() {
+            "noise" => BumpKind::Noise,
+            "ripples" => BumpKind::Ripples,
+            _ => {
+                return Err(anyhow!(
+                    "Unknown bump type '{}' found at {}",
+                    bump_type.as_ref(),
+                    self.path.to_string()
+                ))
+            }
+        };
+        let mut bump = Bump::default().with_kind(kind);
+        if bump_hash.contains_key(key!("strength")) {
+            let strength = self.hash_value_to_float(bump_hash, "strength")?;
+            bump = bump.with_strength(strength);
+        }
+        if bump_hash.contains_key(key!("scale")) {
+            let scale = self.hash_value_to_float(bump_hash, "scale")?;
+            bump = bump.with_scale(scale);
+        }
+        Ok(bump)
+    }
+
+    fn visit_normal_map(&mut self, normal_map: &yaml::Yaml) -> ParserResult<NormalMap> {
+        let normal_map_hash = self.value_to_hash(normal_map)?;
+        let texture = self.visit_texture(normal_map_hash, "file", false)?;
+        let mut normal_map = NormalMap::new(texture);
+        if normal_map_hash.contains_key(key!("strength")) {
+            let strength = self.hash_value_to_float(normal_map_hash, "strength")?;
+            normal_map = normal_map.with_strength(strength);
         }
+        Ok(normal_map)
     }
 
     fn visit_transforms(&mut self, transforms: &yaml::Yaml) -> ParserResult<Matrix<4>> {
+        let steps = self.visit_transform_steps(transforms)?;
+        Ok(compose(&steps))
+    }
+
+    fn visit_transform_steps(&mut self, transforms: &yaml::Yaml) -> ParserResult<Vec<TransformStep>> {
         let transforms_array = self.value_to_array(transforms)?;
-        let mut combined_transform = Matrix::identity();
-        for (index, transform) in transforms_array.iter().enumerate().rev() {
+        let mut steps = Vec::new();
+        for (index, transform) in transforms_array.iter().enumerate() {
             self.path.push(Segment::Index(index));
-            let next_transform = self.visit_transform(transform)?;
-            combined_transform = combined_transform * next_transform;
+            steps.push(self.visit_transform(transform)?);
             self.path.pop();
         }
 
-        Ok(combined_transform)
+        Ok(steps)
     }
 
     fn visit_radians_or_degrees(&mut self, transform_hash: &yaml::Hash) -> Result<f64> {
@@ -586,7 +1258,7 @@ impl<'a> YamlParser<'a> {
         }
     }
 
-    fn visit_transform(&mut self, transform: &yaml::Yaml) -> ParserResult<Matrix<4>> {
+    fn visit_transform(&mut self, transform: &yaml::Yaml) -> ParserResult<TransformStep> {
         let transform_hash = self.value_to_hash(transform)?;
         let transform_type = self.hash_value_to_string(transform_hash, "type")?;
 
@@ -595,23 +1267,23 @@ impl<'a> YamlParser<'a> {
             self.path.push(Segment::Key("to".into()));
             let v = self.visit_vector(to_value)?;
             self.path.pop();
-            Ok(Matrix::translation(v.x, v.y, v.z))
+            Ok(TransformStep::Translate(v))
         } else if transform_type.as_ref() == "scale" {
             let to_value = self.get_value_from_hash(transform_hash, "to")?;
             self.path.push(Segment::Key("to".into()));
             let v = self.visit_vector(to_value)?;
             self.path.pop();
-            Ok(Matrix::scaling(v.x, v.y, v.z))
+            Ok(TransformStep::Scale(v))
         } else if transform_type.as_ref() == "rotate_x" {
             // let radians = self.hash_value_to_float(transform_hash, "radians")?;
             let radians = self.visit_radians_or_degrees(transform_hash)?;
-            Ok(Matrix::rotation_x(radians))
+            Ok(TransformStep::RotateX(radians))
         } else if transform_type.as_ref() == "rotate_y" {
             let radians = self.visit_radians_or_degrees(transform_hash)?;
-            Ok(Matrix::rotation_y(radians))
+            Ok(TransformStep::RotateY(radians))
         } else if transform_type.as_ref() == "rotate_z" {
             let radians = self.visit_radians_or_degrees(transform_hash)?;
-            Ok(Matrix::rotation_z(radians))
+            Ok(TransformStep::RotateZ(radians))
         } else {
             Err(anyhow!(
                 "Unknown transform type '{}' found at {}",
@@ -639,33 +1311,453 @@ impl<'a> YamlParser<'a> {
         self.path.push(Segment::Key("up".into()));
         let up = self.visit_vector(up_value)?;
         self.path.pop();
+        // the camera would have no direction to look in
+        if from == to {
+            return Err(anyhow!(
+                "Camera 'from' and 'to' must be different points at {}",
+                self.path.to_string()
+            ));
+        }
 
-        let camera = Camera::new(width.abs() as usize, height.abs() as usize, fov)
+        let mut camera = Camera::new(width.abs() as usize, height.abs() as usize, fov)
             .view_transform(from, to, up);
+        if camera_hash.contains_key(key!("keyframes")) {
+            // anything a keyframe leaves out stays where the camera is
+            let view = CameraView { from, to, up };
+            let track = self.visit_keyframes(camera_hash, |parser, keyframe_hash| {
+                let mut keyframe_view = view;
+                for (key, value) in [
+                    ("from", &mut keyframe_view.from),
+                    ("to", &mut keyframe_view.to),
+                ] {
+                    if keyframe_hash.contains_key(key!(key)) {
+                        let point_value = parser.get_value_from_hash(keyframe_hash, key)?;
+                        parser.path.push(Segment::Key(key.into()));
+                        *value = parser.visit_point(point_value)?;
+                        parser.path.pop();
+                    }
+                }
+                if keyframe_hash.contains_key(key!("up")) {
+                    let up_value = parser.get_value_from_hash(keyframe_hash, "up")?;
+                    parser.path.push(Segment::Key("up".into()));
+                    keyframe_view.up = parser.visit_vector(up_value)?;
+                    parser.path.pop();
+                }
+                Ok(keyframe_view)
+            })?;
+            self.animation
+                .cameras
+                .insert(camera_name.as_ref().to_string(), track);
+        }
+        if camera_hash.contains_key(key!("tone_mapping")) {
+            let tone_map_value = self.get_value_from_hash(camera_hash, "tone_mapping")?;
+            self.path.push(Segment::Key("tone_mapping".into()));
+            let tone_map = self.visit_tone_map(tone_map_value)?;
+            self.path.pop();
+            camera = camera.with_tone_map(tone_map);
+        }
+        if camera_hash.contains_key(key!("filters")) {
+            let filters_value = self.get_value_from_hash(camera_hash, "filters")?;
+            self.path.push(Segment::Key("filters".into()));
+            let filters_array = self.value_to_array(filters_value)?;
+            for (index, filter) in filters_array.iter().enumerate() {
+                self.path.push(Segment::Index(index));
+                camera = camera.with_filter(self.visit_filter(filter)?);
+                self.path.pop();
+            }
+            self.path.pop();
+        }
+        if camera_hash.contains_key(key!("stereo")) {
+            let stereo_value = self.get_value_from_hash(camera_hash, "stereo")?;
+            self.path.push(Segment::Key("stereo".into()));
+            // converging on the look at point by default
+            let stereo = self.visit_stereo(stereo_value, (to - from).magnitude())?;
+            self.path.pop();
+            camera = camera.with_stereo(stereo);
+        }
+        if camera_hash.contains_key(key!("outline")) {
+            let outline_value = self.get_value_from_hash(camera_hash, "outline")?;
+            self.path.push(Segment::Key("outline".into()));
+            let outline = self.visit_outline(outline_value)?;
+            self.path.pop();
+            camera = camera.with_outline(outline);
+        }
+        if camera_hash.contains_key(key!("motion_blur")) {
+            let motion_blur_value = self.get_value_from_hash(camera_hash, "motion_blur")?;
+            self.path.push(Segment::Key("motion_blur".into()));
+            let motion_blur = self.visit_motion_blur(motion_blur_value)?;
+            self.path.pop();
+            camera = camera.with_motion_blur(motion_blur);
+        }
+        if camera_hash.contains_key(key!("aovs")) {
+            let aovs_value = self.get_value_from_hash(camera_hash, "aovs")?;
+            self.path.push(Segment::Key("aovs".into()));
+            let aovs_array = self.value_to_array(aovs_value)?;
+            for (index, aov) in aovs_array.iter().enumerate() {
+                self.path.push(Segment::Index(index));
+                let name = self.value_to_string(aov)?;
+                let aov = name
+                    .as_ref()
+                    .parse::<Aov>()
+                    .map_err(|error| anyhow!("{} at {}", error, self.path.to_string()))?;
+                camera = camera.with_aov(aov);
+                self.path.pop();
+            }
+            self.path.pop();
+        }
+        if camera_hash.contains_key(key!("integrator")) {
+            let integrator_value = self.get_value_from_hash(camera_hash, "integrator")?;
+            self.path.push(Segment::Key("integrator".into()));
+            let integrator = self.visit_integrator(integrator_value)?;
+            self.path.pop();
+            camera = camera.with_integrator(integrator);
+        }
         Ok((camera_name.as_ref().into(), camera))
     }
-}
 
-#[derive(Default)]
-pub struct Loader {}
-impl WorldLoader for Loader {
-    fn load_world<T: AsRef<str>>(&self, source: T) -> LoaderResult {
-        let mut parser = YamlParser::new(source.as_ref());
-        parser.parse_yaml()
+    fn visit_stereo(&mut self, stereo: &yaml::Yaml, default_convergence: F) -> ParserResult<Stereo> {
+        let stereo_hash = self.value_to_hash(stereo)?;
+        let interocular = self.hash_value_to_float(stereo_hash, "interocular")?;
+        let mut convergence = default_convergence;
+        if stereo_hash.contains_key(key!("convergence")) {
+            convergence = self.hash_value_to_float(stereo_hash, "convergence")?;
+        }
+        // the eyes are shifted by interocular / convergence
+        if convergence <= 0.0 {
+            return Err(anyhow!(
+                "Stereo convergence must be positive, got {} at {}",
+                convergence,
+                self.path.to_string()
+            ));
+        }
+        let mut stereo = Stereo::new(interocular, convergence);
+        if stereo_hash.contains_key(key!("layout")) {
+            let layout = self.hash_value_to_string(stereo_hash, "layout")?;
+            let layout = match layout.as_ref() {
+                "side_by_side" => StereoLayout::SideBySide,
+                "top_bottom" => StereoLayout::TopBottom,
+                "anaglyph" => StereoLayout::Anaglyph,
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown stereo layout '{}' found at {}",
+                        layout.as_ref(),
+                        self.path.to_string()
+                    ))
+                }
+            };
+            stereo = stereo.with_layout(layout);
+        }
+        Ok(stereo)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::f64::consts::PI;
+    fn visit_motion_blur(&mut self, motion_blur: &yaml::Yaml) -> ParserResult<MotionBlur> {
+        let motion_blur_hash = self.value_to_hash(motion_blur)?;
+        let mut motion_blur = MotionBlur::default();
+        if motion_blur_hash.contains_key(key!("samples")) {
+            let samples = self.hash_value_to_int(motion_blur_hash, "samples")?;
+            if samples < 1 {
+                return Err(anyhow!(
+                    "Motion blur needs at least one sample, got {} at {}",
+                    samples,
+                    self.path.to_string()
+                ));
+            }
+            motion_blur = motion_blur.with_samples(samples as usize);
+        }
+        if motion_blur_hash.contains_key(key!("shutter")) {
+            let shutter_value = self.get_value_from_hash(motion_blur_hash, "shutter")?;
+            self.path.push(Segment::Key("shutter".into()));
+            let shutter_array = self.value_to_array(shutter_value)?;
+            let open_value = self.get_index_from_array(shutter_array, 0)?;
+            let open = self.value_to_float(open_value)?;
+            let close_value = self.get_index_from_array(shutter_array, 1)?;
+            let close = self.value_to_float(close_value)?;
+            self.path.pop();
+            motion_blur = motion_blur.with_shutter(open, close);
+        }
+        Ok(motion_blur)
+    }
 
-    use crate::assert_fuzzy_eq;
-    use crate::body::Body;
-    use crate::body::Intersectable;
+    fn visit_ambient_occlusion(&mut self, ambient_occlusion: &yaml::Yaml) -> ParserResult<AmbientOcclusion> {
+        let ambient_occlusion_hash = self.value_to_hash(ambient_occlusion)?;
+        let mut ambient_occlusion = AmbientOcclusion::default();
+        if ambient_occlusion_hash.contains_key(key!("samples")) {
+            let samples = self.hash_value_to_int(ambient_occlusion_hash, "samples")?;
+            if samples < 1 {
+                return Err(anyhow!(
+                    "Ambient occlusion needs at least one sample, got {} at {}",
+                    samples,
+                    self.path.to_string()
+                ));
+            }
+            ambient_occlusion = ambient_occlusion.with_samples(samples as usize);
+        }
+        if ambient_occlusion_hash.contains_key(key!("radius")) {
+            let radius = self.hash_value_to_float(ambient_occlusion_hash, "radius")?;
+            ambient_occlusion = ambient_occlusion.with_radius(radius);
+        }
+        Ok(ambient_occlusion)
+    }
+
+    fn visit_fog(&mut self, fog: &yaml::Yaml) -> ParserResult<Fog> {
+        let fog_hash = self.value_to_hash(fog)?;
+        let mut fog = Fog::default();
+        if fog_hash.contains_key(key!("color")) {
+            let color_value = self.get_value_from_hash(fog_hash, "color")?;
+            self.path.push(Segment::Key("color".into()));
+            let color = self.visit_color(color_value)?;
+            self.path.pop();
+            fog = fog.with_color(color);
+        }
+        if fog_hash.contains_key(key!("density")) {
+            let density = self.hash_value_to_float(fog_hash, "density")?;
+            fog = fog.with_density(self.non_negative_density(density)?);
+        }
+        Ok(fog)
+    }
+
+    fn visit_medium(&mut self, medium: &yaml::Yaml) -> ParserResult<Medium> {
+        let medium_hash = self.value_to_hash(medium)?;
+        let mut medium = Medium::default();
+        if medium_hash.contains_key(key!("density")) {
+            let density = self.hash_value_to_float(medium_hash, "density")?;
+            medium = medium.with_density(self.non_negative_density(density)?);
+        }
+        if medium_hash.contains_key(key!("color")) {
+            let color_value = self.get_value_from_hash(medium_hash, "color")?;
+            self.path.push(Segment::Key("color".into()));
+            let color = self.visit_color(color_value)?;
+            self.path.pop();
+            medium = medium.with_color(color);
+        }
+        if medium_hash.contains_key(key!("steps")) {
+            let steps = self.hash_value_to_int(medium_hash, "steps")?;
+            if steps < 1 {
+                return Err(anyhow!(
+                    "Media need at least one step, got {} at {}",
+                    steps,
+                    self.path.to_string()
+                ));
+            }
+            medium = medium.with_steps(steps as usize);
+        }
+        Ok(medium)
+    }
+
+    fn non_negative_density(&self, density: F) -> ParserResult<F> {
+        if density < 0.0 {
+            return Err(anyhow!(
+                "Expected a density of 0 or more, got {} at {}",
+                density,
+                self.path.to_string()
+            ));
+        }
+        Ok(density)
+    }
+
+    fn visit_caustics(&mut self, caustics: &yaml::Yaml) -> ParserResult<Caustics> {
+        let caustics_hash = self.value_to_hash(caustics)?;
+        let mut caustics = Caustics::default();
+        if caustics_hash.contains_key(key!("photons")) {
+            let photons = self.hash_value_to_int(caustics_hash, "photons")?;
+            if photons < 0 {
+                return Err(anyhow!(
+                    "Caustics photons must not be negative, got {} at {}",
+                    photons,
+                    self.path.to_string()
+                ));
+            }
+            caustics = caustics.with_photons(photons as usize);
+        }
+        if caustics_hash.contains_key(key!("radius")) {
+            let radius = self.hash_value_to_float(caustics_hash, "radius")?;
+            if radius <= 0.0 {
+                return Err(anyhow!(
+                    "Caustics need a positive gather radius, got {} at {}",
+                    radius,
+                    self.path.to_string()
+                ));
+            }
+            caustics = caustics.with_radius(radius);
+        }
+        Ok(caustics)
+    }
+
+    fn visit_integrator(&mut self, integrator: &yaml::Yaml) -> ParserResult<Integrator> {
+        let integrator_hash = self.value_to_hash(integrator)?;
+        let integrator_type = self.hash_value_to_string(integrator_hash, "type")?;
+        match integrator_type.as_ref() {
+            "whitted" => Ok(Integrator::Whitted),
+            "ambient_occlusion" => Ok(Integrator::from(self.visit_ambient_occlusion(integrator)?)),
+            "path_tracer" => {
+                let mut path_tracer = PathTracer::default();
+                if integrator_hash.contains_key(key!("samples")) {
+                    let samples = self.hash_value_to_int(integrator_hash, "samples")?;
+                    if samples < 1 {
+                        return Err(anyhow!(
+                            "Path tracing needs at least one sample, got {} at {}",
+                            samples,
+                            self.path.to_string()
+                        ));
+                    }
+                    path_tracer = path_tracer.with_samples(samples as usize);
+                }
+                let mut min_bounces = path_tracer.min_bounces as i64;
+                if integrator_hash.contains_key(key!("min_bounces")) {
+                    min_bounces = self.hash_value_to_int(integrator_hash, "min_bounces")?;
+                }
+                let mut max_bounces = path_tracer.max_bounces as i64;
+                if integrator_hash.contains_key(key!("max_bounces")) {
+                    max_bounces = self.hash_value_to_int(integrator_hash, "max_bounces")?;
+                }
+                if min_bounces < 0 || max_bounces < 1 {
+                    return Err(anyhow!(
+                        "Path tracing needs at least one bounce, got {} to {} at {}",
+                        min_bounces,
+                        max_bounces,
+                        self.path.to_string()
+                    ));
+                }
+                Ok(Integrator::from(path_tracer.with_bounces(min_bounces as usize, max_bounces as usize)))
+            }
+            _ => Err(anyhow!(
+                "Unknown integrator type '{}' found at {}",
+                integrator_type.as_ref(),
+                self.path.to_string()
+            )),
+        }
+    }
+
+    fn visit_outline(&mut self, outline: &yaml::Yaml) -> ParserResult<Outline> {
+        let outline_hash = self.value_to_hash(outline)?;
+        let mut outline = Outline::default();
+        if outline_hash.contains_key(key!("color")) {
+            let color_value = self.get_value_from_hash(outline_hash, "color")?;
+            self.path.push(Segment::Key("color".into()));
+            let color = self.visit_color(color_value)?;
+            self.path.pop();
+            outline = outline.with_color(color);
+        }
+        if outline_hash.contains_key(key!("depth_threshold")) {
+            let depth_threshold = self.hash_value_to_float(outline_hash, "depth_threshold")?;
+            outline = outline.with_depth_threshold(depth_threshold);
+        }
+        if outline_hash.contains_key(key!("normal_threshold")) {
+            let normal_threshold = self.hash_value_to_float(outline_hash, "normal_threshold")?;
+            outline = outline.with_normal_threshold(normal_threshold);
+        }
+        Ok(outline)
+    }
+
+    fn visit_filter(&mut self, filter: &yaml::Yaml) -> ParserResult<Filter> {
+        let filter_hash = self.value_to_hash(filter)?;
+        let filter_type = self.hash_value_to_string(filter_hash, "type")?;
+
+        match filter_type.as_ref() {
+            "grayscale" => Ok(Filter::Grayscale),
+            "sepia" => Ok(Filter::Sepia),
+            "invert" => Ok(Filter::Invert),
+            "posterize" => {
+                let levels = self.hash_value_to_int(filter_hash, "levels")?;
+                Ok(Filter::Posterize(levels.max(2) as usize))
+            }
+            "color_matrix" => {
+                let matrix_value = self.get_value_from_hash(filter_hash, "matrix")?;
+                self.path.push(Segment::Key("matrix".into()));
+                let rows = self.value_to_array(matrix_value)?;
+                let mut matrix = [[0.0; 3]; 3];
+                for (row_index, row) in matrix.iter_mut().enumerate() {
+                    let row_value = self.get_index_from_array(rows, row_index)?;
+                    self.path.push(Segment::Index(row_index));
+                    let row_array = self.value_to_array(row_value)?;
+                    for (column_index, cell) in row.iter_mut().enumerate() {
+                        let cell_value = self.get_index_from_array(row_array, column_index)?;
+                        *cell = self.value_to_float(cell_value)?;
+                    }
+                    self.path.pop();
+                }
+                self.path.pop();
+                Ok(Filter::ColorMatrix(matrix))
+            }
+            "adjust" => {
+                let mut values = [0.0, 1.0, 1.0];
+                for (value, key) in values.iter_mut().zip(["brightness", "contrast", "saturation"]) {
+                    if filter_hash.contains_key(key!(key)) {
+                        *value = self.hash_value_to_float(filter_hash, key)?;
+                    }
+                }
+                Ok(Filter::Adjust {
+                    brightness: values[0],
+                    contrast: values[1],
+                    saturation: values[2],
+                })
+            }
+            _ => Err(anyhow!(
+                "Unknown filter type '{}' found at {}",
+                filter_type.as_ref(),
+                self.path.to_string()
+            )),
+        }
+    }
+
+    fn visit_tone_map(&mut self, tone_map: &yaml::Yaml) -> ParserResult<ToneMap> {
+        let tone_map_hash = self.value_to_hash(tone_map)?;
+        let mut tone_map = ToneMap::default();
+        if tone_map_hash.contains_key(key!("operator")) {
+            let operator = self.hash_value_to_string(tone_map_hash, "operator")?;
+            let operator = match operator.as_ref() {
+                "clamp" => ToneMapping::Clamp,
+                "reinhard" => ToneMapping::Reinhard,
+                "filmic" => ToneMapping::Filmic,
+                "aces" => ToneMapping::Aces,
+                _ => {
+                    return Err(anyhow!(
+                        "Unknown tone mapping operator '{}' found at {}",
+                        operator.as_ref(),
+                        self.path.to_string()
+                    ))
+                }
+            };
+            tone_map = tone_map.with_operator(operator);
+        }
+        if tone_map_hash.contains_key(key!("exposure")) {
+            let exposure = self.hash_value_to_float(tone_map_hash, "exposure")?;
+            tone_map = tone_map.with_exposure(exposure);
+        }
+        if tone_map_hash.contains_key(key!("srgb")) {
+            let srgb = self.hash_value_to_bool(tone_map_hash, "srgb")?;
+            tone_map = tone_map.with_srgb(srgb);
+        }
+        Ok(tone_map)
+    }
+}
+
+#[derive(Default)]
+pub struct Loader {}
+impl WorldLoader for Loader {
+    fn load_animation<T: AsRef<str>>(&self, source: T) -> AnimationLoaderResult {
+        let mut parser = YamlParser::new(source.as_ref());
+        let (world, cameras) = parser.parse_yaml()?;
+        Ok((world, cameras, parser.animation))
+    }
+    fn load_world<T: AsRef<str>>(&self, source: T) -> LoaderResult {
+        let mut parser = YamlParser::new(source.as_ref());
+        parser.parse_yaml()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::assert_fuzzy_eq;
+    use crate::body::Body;
+    use crate::body::Intersectable;
     use crate::camera::Camera;
     use crate::material::Material;
     use crate::material::Phong;
     use crate::matrix::Matrix;
+    use crate::ray::Ray;
     use crate::sphere::Sphere;
     use crate::tuple::Tuple;
 
@@ -1386,7 +2478,7 @@ mod tests {
     let (world, _camera_hash) = result.unwrap();
 
     assert_eq!(1, world.bodies.len());
-    let body = world.bodies[0];
+    let body = world.bodies[0].clone();
 
     let expected_transform = Matrix::rotation_z(423.0 / 180.0 * PI)
       * Matrix::rotation_y(90.0 / 180.0 * PI)
@@ -1480,4 +2572,1104 @@ mod tests {
     assert_eq!(1, loaded_world.bodies.len());
     assert_eq!(body, loaded_world.bodies[0]);
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn uv_checker_pattern_in_body_is_parsed() {
+    let source = r##"
+---
+- body:
+    type: sphere
+    material:
+      type: phong
+      pattern:
+        type: uv_checker
+        width: 16
+        height: 8
+        colorA: [0.1,0.2,0.3]
+        colorB: [0.4,0.5,0.6]
+"##;
+
+    let pattern = Pattern::from(
+      UvChecker::default()
+        .with_size(16.0, 8.0)
+        .with_colors(Color::new(0.1, 0.2, 0.3), Color::new(0.4, 0.5, 0.6)),
+    );
+    let material = Material::from(Phong::default().with_pattern(pattern));
+    let body = Body::from(Sphere::default().with_material(material));
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(1, loaded_world.bodies.len());
+    assert_eq!(body, loaded_world.bodies[0]);
+  }
+
+  #[test]
+  fn world_background_is_parsed() {
+    let source = r##"
+---
+- world:
+    background:
+      type: gradient
+      bottom: [1, 1, 1]
+      top: [0.2, 0.4, 0.8]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(
+      loaded_world.background,
+      Background::Gradient {
+        bottom: Color::new(1.0, 1.0, 1.0),
+        top: Color::new(0.2, 0.4, 0.8),
+      }
+    );
+  }
+
+  #[test]
+  fn solid_and_default_world_background() {
+    let source = r##"
+---
+- world:
+    background:
+      type: solid
+      color: [0.1, 0.2, 0.3]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(loaded_world.background, Background::Solid(Color::new(0.1, 0.2, 0.3)));
+    let (empty_world, _) = yaml_loader.load_world("---\n- world: {}\n").unwrap();
+    assert_fuzzy_eq!(empty_world.background, Background::Solid(Color::black()));
+  }
+
+  #[test]
+  fn missing_background_image() {
+    let source = r##"
+---
+- world:
+    background:
+      type: equirectangular
+      file: does/not/exist.png
+"##;
+
+    let yaml_loader = Loader::default();
+    let result = yaml_loader.load_world(source);
+    assert!(result.is_err());
+    let actual = result.unwrap_err().to_string();
+    assert!(actual.starts_with(
+      "Could not read image file 'does/not/exist.png' at .document[0].item[0].world.background.file"
+    ));
+  }
+
+  #[test]
+  fn unknown_background_type() {
+    let source = r##"
+---
+- world:
+    background:
+      type: starry_night
+"##;
+
+    let yaml_loader = Loader::default();
+    let result = yaml_loader.load_world(source);
+    assert!(result.is_err());
+    let actual = result.unwrap_err();
+    let expected = anyhow!(
+      "Unknown background type 'starry_night' found at .document[0].item[0].world.background"
+    );
+    assert_eq!(actual.to_string(), expected.to_string());
+  }
+
+  #[test]
+  fn material_bump_is_parsed() {
+    let source = r##"
+---
+- body:
+    type: plane
+    material:
+      type: phong
+      reflectiveness: 0.6
+      bump:
+        type: ripples
+        strength: 0.2
+        scale: 8
+"##;
+
+    let bump = Bump::default()
+      .with_kind(BumpKind::Ripples)
+      .with_strength(0.2)
+      .with_scale(8.0);
+    let material = Material::from(Phong::default().with_reflective(0.6).with_bump(bump));
+    let body = Body::from(Plane::default().with_material(material));
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(1, loaded_world.bodies.len());
+    assert_eq!(body, loaded_world.bodies[0]);
+  }
+
+  #[test]
+  fn unknown_bump_type() {
+    let source = r##"
+---
+- body:
+    type: plane
+    material:
+      type: phong
+      bump:
+        type: bubbles
+"##;
+
+    let yaml_loader = Loader::default();
+    let result = yaml_loader.load_world(source);
+    assert!(result.is_err());
+    let actual = result.unwrap_err();
+    let expected = anyhow!(
+      "Unknown bump type 'bubbles' found at .document[0].item[0].body.material.bump"
+    );
+    assert_eq!(actual.to_string(), expected.to_string());
+  }
+
+  #[test]
+  fn material_normal_map_is_parsed() {
+    let mut canvas = Canvas::new(2, 2);
+    canvas.write_pixel(0, 0, Color::new(0.5, 0.5, 1.0));
+    let file_name = std::env::temp_dir().join("rustic_rt_normal_map_is_parsed.png");
+    std::fs::write(&file_name, crate::canvas::to_png::ToPNG::to_png(&canvas)).unwrap();
+    let source = format!(
+      r##"
+---
+- body:
+    type: sphere
+    material:
+      type: phong
+      normal_map:
+        file: {}
+        strength: 0.5
+"##,
+      file_name.display()
+    );
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    let normal_map = match loaded_world.bodies[0].material() {
+      Material::Phong(phong) => phong.normal_map.unwrap(),
+      _ => panic!("Expected a phong material"),
+    };
+    assert_eq!(normal_map.strength, 0.5);
+    assert_eq!(normal_map.texture.width(), 2);
+    assert_eq!(normal_map.texture.color_at_uv(0.0, 1.0), Color::new(128.0 / 255.0, 128.0 / 255.0, 1.0));
+  }
+
+  #[test]
+  fn camera_tone_mapping_is_parsed() {
+    let source = r##"
+---
+- camera:
+    name: graded
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    tone_mapping:
+      operator: aces
+      exposure: 1.5
+      srgb: false
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (_, loaded_cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(
+      loaded_cameras["graded"].tone_map,
+      ToneMap::default()
+        .with_operator(ToneMapping::Aces)
+        .with_exposure(1.5)
+        .with_srgb(false)
+    );
+  }
+
+  #[test]
+  fn camera_filters_are_parsed_in_order() {
+    let source = r##"
+---
+- camera:
+    name: graded
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    filters:
+      - type: sepia
+      - type: posterize
+        levels: 4
+      - type: adjust
+        contrast: 1.5
+      - type: color_matrix
+        matrix:
+          - [0, 0, 1]
+          - [0, 1, 0]
+          - [1, 0, 0]
+      - type: invert
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (_, loaded_cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(
+      loaded_cameras["graded"].filters,
+      vec![
+        Filter::Sepia,
+        Filter::Posterize(4),
+        Filter::Adjust {
+          brightness: 0.0,
+          contrast: 1.5,
+          saturation: 1.0
+        },
+        Filter::ColorMatrix([[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]),
+        Filter::Invert,
+      ]
+    );
+  }
+
+  #[test]
+  fn indexing_one_past_the_end_of_an_array_is_an_error() {
+    let source = r##"
+---
+- camera:
+    name: short
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0]
+    up: [0.0, 1.0, 0.0]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let error = yaml_loader.load_world(source).unwrap_err();
+    assert!(error.to_string().contains("Tried to get value with index 2"));
+    assert!(error.to_string().contains("Index not found (Array length = 2)"));
+  }
+
+  #[test]
+  fn short_color_matrix_row_is_an_error() {
+    let source = r##"
+---
+- camera:
+    name: graded
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    filters:
+      - type: color_matrix
+        matrix:
+          - [1, 0, 0]
+          - [0, 1]
+          - [0, 0, 1]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let error = yaml_loader.load_world(source).unwrap_err();
+    assert!(error.to_string().contains("Index not found"));
+  }
+
+  #[test]
+  fn camera_outline_is_parsed() {
+    let source = r##"
+---
+- camera:
+    name: cartoon
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    outline:
+      color: [0.1, 0.1, 0.2]
+      normal_threshold: 0.5
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (_, loaded_cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(
+      loaded_cameras["cartoon"].outline,
+      Some(
+        Outline::default()
+          .with_color(Color::new(0.1, 0.1, 0.2))
+          .with_normal_threshold(0.5)
+      )
+    );
+  }
+
+  #[test]
+  fn camera_stereo_is_parsed() {
+    let source = r##"
+---
+- camera:
+    name: left_right
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    stereo:
+      interocular: 0.2
+- camera:
+    name: glasses
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    stereo:
+      interocular: 0.2
+      convergence: 3.0
+      layout: anaglyph
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (_, loaded_cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(loaded_cameras["left_right"].stereo, Some(Stereo::new(0.2, 5.0)));
+    assert_eq!(
+      loaded_cameras["glasses"].stereo,
+      Some(Stereo::new(0.2, 3.0).with_layout(StereoLayout::Anaglyph))
+    );
+  }
+
+  #[test]
+  fn non_positive_stereo_convergence_is_an_error() {
+    let camera = |convergence: &str, from: &str| {
+      format!(
+        r##"
+---
+- camera:
+    name: converging
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: {}
+    up: [0.0, 1.0, 0.0]
+    stereo:
+      interocular: 0.2
+{}
+"##,
+        from, convergence
+      )
+    };
+
+    let yaml_loader = Loader::default();
+
+    let error = yaml_loader.load_world(camera("      convergence: 0", "[0.0, 0.0, -5.0]")).unwrap_err();
+    assert!(error.to_string().contains("Stereo convergence must be positive, got 0 at .document[0].item[0].camera.stereo"));
+    let error = yaml_loader.load_world(camera("      convergence: -1.0", "[0.0, 0.0, -5.0]")).unwrap_err();
+    assert!(error.to_string().contains("Stereo convergence must be positive, got -1"));
+    // converging on the look at point by default, which is the eye itself
+    let error = yaml_loader.load_world(camera("", "[0.0, 0.0, 0.0]")).unwrap_err();
+    assert!(error.to_string().contains("Camera 'from' and 'to' must be different points at .document[0].item[0].camera"));
+  }
+
+  #[test]
+  fn unknown_tone_mapping_operator_is_an_error() {
+    let source = r##"
+---
+- camera:
+    name: graded
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    to: [0.0, 0.0, 0.0]
+    from: [0.0, 0.0, -5.0]
+    up: [0.0, 1.0, 0.0]
+    tone_mapping:
+      operator: sepia
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let error = yaml_loader.load_world(source).unwrap_err();
+    assert!(error.to_string().contains("Unknown tone mapping operator 'sepia'"));
+  }
+
+  #[test]
+  fn ppm_textures_are_parsed() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas.write_pixel(1, 0, Color::new(1.0, 0.0, 0.0));
+    let file_name = std::env::temp_dir().join("rustic_rt_ppm_textures_are_parsed.ppm");
+    std::fs::write(&file_name, crate::canvas::to_ppm::ToPPM::to_binary_ppm(&canvas)).unwrap();
+    let source = format!(
+      r##"
+---
+- world:
+    background:
+      type: equirectangular
+      file: {}
+"##,
+      file_name.display()
+    );
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    match loaded_world.background {
+      Background::Equirectangular(texture) => {
+        assert_eq!(texture.width(), 2);
+        assert_eq!(texture.color_at_uv(0.75, 0.5), Color::new(1.0, 0.0, 0.0));
+      }
+      _ => panic!("Expected an equirectangular background"),
+    }
+  }
+
+  #[test]
+  fn color_textures_are_decoded_from_srgb() {
+    let mut canvas = Canvas::new(1, 1);
+    canvas.write_pixel(0, 0, Color::new(0.5, 0.5, 0.5));
+    let file_name = std::env::temp_dir().join("rustic_rt_color_textures_are_decoded_from_srgb.ppm");
+    std::fs::write(&file_name, crate::canvas::to_ppm::ToPPM::to_binary_ppm(&canvas)).unwrap();
+    let source = format!(
+      r##"
+---
+- world:
+    background:
+      type: equirectangular
+      file: {}
+"##,
+      file_name.display()
+    );
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    match loaded_world.background {
+      Background::Equirectangular(texture) => {
+        let texel = texture.color_at_uv(0.5, 0.5);
+        assert!((texel.red - 0.2158).abs() < 0.001);
+        let displayed = crate::tone_mapping::ToneMap::default().map(texel);
+        assert!((displayed.red - 0.5).abs() < 1.0 / 255.0);
+      }
+      _ => panic!("Expected an equirectangular background"),
+    }
+  }
+
+  #[test]
+  fn images_used_twice_are_read_once() {
+    let canvas = Canvas::new(2, 2);
+    let file_name = std::env::temp_dir().join("rustic_rt_images_used_twice_are_read_once.ppm");
+    std::fs::write(&file_name, crate::canvas::to_ppm::ToPPM::to_binary_ppm(&canvas)).unwrap();
+    let source = format!(
+      r##"
+---
+- world:
+    background:
+      type: cube_map
+      left: {0}
+      front: {0}
+      right: {0}
+      back: {0}
+      up: {0}
+      down: {0}
+"##,
+      file_name.display()
+    );
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    match loaded_world.background {
+      Background::CubeMap(cube_map) => {
+        assert_eq!(cube_map.left, cube_map.front);
+        assert_eq!(cube_map.left, cube_map.down);
+      }
+      _ => panic!("Expected a cube map background"),
+    }
+  }
+
+  #[test]
+  fn toon_material_is_parsed() {
+    let source = r##"
+---
+- body:
+    type: sphere
+    material:
+      type: toon
+      color: [1.0, 0.5, 0.0]
+      bands: 4
+      shininess: 50.0
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(
+      loaded_world.bodies[0].material(),
+      Material::from(
+        Toon::default()
+          .with_color(Color::new(1.0, 0.5, 0.0))
+          .with_bands(4)
+          .with_shininess(50.0)
+      )
+    );
+
+    let err = yaml_loader.load_world(source.replace("bands: 4", "bands: 0")).unwrap_err();
+    assert!(err.to_string().contains("Toon materials need at least one band, got 0 at .document[0].item[0].body.material"));
+  }
+
+  #[test]
+  fn marble_and_wood_patterns_are_parsed() {
+    let source = r##"
+---
+- body:
+    type: plane
+    material:
+      type: phong
+      pattern:
+        type: wood
+        colorA: [0.8, 0.6, 0.4]
+        colorB: [0.4, 0.2, 0.1]
+        rings: 12
+        turbulence: 0.2
+        octaves: 4
+- body:
+    type: sphere
+    material:
+      type: phong
+      pattern:
+        type: marble
+        frequency: 3
+        turbulence: 6
+"##;
+
+    let wood = Pattern::from(
+      Wood::default()
+        .with_colors(Color::new(0.8, 0.6, 0.4), Color::new(0.4, 0.2, 0.1))
+        .with_rings(12.0)
+        .with_turbulence(0.2, 4),
+    );
+    let marble = Pattern::from(Marble::default().with_frequency(3.0).with_turbulence(6.0, 6));
+    let plane = Body::from(Plane::default().with_material(Material::from(Phong::default().with_pattern(wood))));
+    let sphere = Body::from(Sphere::default().with_material(Material::from(Phong::default().with_pattern(marble))));
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(2, loaded_world.bodies.len());
+    assert_eq!(plane, loaded_world.bodies[0]);
+    assert_eq!(sphere, loaded_world.bodies[1]);
+  }
+
+  #[test]
+  fn noise_octaves_apply_without_turbulence() {
+    let source = r##"
+---
+- body:
+    type: sphere
+    material:
+      type: phong
+      pattern:
+        type: marble
+        octaves: 2
+"##;
+
+    let marble = Pattern::from(Marble::default().with_octaves(2));
+    let sphere = Body::from(Sphere::default().with_material(Material::from(Phong::default().with_pattern(marble))));
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(sphere, loaded_world.bodies[0]);
+  }
+
+  #[test]
+  fn negative_noise_octaves_are_an_error() {
+    let source = r##"
+---
+- body:
+    type: plane
+    material:
+      type: phong
+      pattern:
+        type: wood
+        turbulence: 0.2
+        octaves: -4
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let error = yaml_loader.load_world(source).unwrap_err();
+    assert!(error.to_string().contains("Noise octaves must not be negative, got -4 at .document[0].item[0].body.material"));
+  }
+
+  #[test]
+  fn keyframes_and_animation_are_parsed() {
+    let source = r##"
+---
+- animation:
+    frames: [1, 10]
+- camera:
+    name: main
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+    keyframes:
+      - frame: 1
+      - frame: 10
+        from: [5, 0, 0]
+        easing: smooth
+- light:
+    type: point_light
+    at: [0, 10, 0]
+    intensity: [1, 1, 1]
+    keyframes:
+      - frame: 1
+        at: [0, 10, 0]
+      - frame: 5
+        at: [4, 10, 0]
+- body:
+    type: sphere
+    keyframes:
+      - frame: 1
+        transforms:
+          - type: translate
+            to: [0, 0, 0]
+      - frame: 3
+        transforms:
+          - type: translate
+            to: [2, 0, 0]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (world, cameras, animation) = yaml_loader.load_animation(source).unwrap();
+    assert_eq!(animation.frame_range(), Some(1..=10));
+    assert_eq!(world.bodies[0].transform(), Matrix::translation(0.0, 0.0, 0.0));
+
+    let world_at_2 = animation.world_at(&world, 2.0);
+    assert_fuzzy_eq!(world_at_2.bodies[0].transform(), Matrix::translation(1.0, 0.0, 0.0));
+    assert_fuzzy_eq!(world_at_2.lights[0].position, Tuple::point(1.0, 10.0, 0.0));
+
+    let camera = animation.camera_at("main", &cameras["main"], 10.0);
+    let expected = Camera::new(10, 10, 1.0).view_transform(
+      Tuple::point(5.0, 0.0, 0.0),
+      Tuple::point(0.0, 0.0, 0.0),
+      Tuple::vector(0.0, 1.0, 0.0),
+    );
+    assert_fuzzy_eq!(camera, expected);
+  }
+
+  #[test]
+  fn keyframes_with_different_transforms_are_rejected() {
+    let source = r##"
+---
+- body:
+    type: sphere
+    keyframes:
+      - frame: 1
+        transforms:
+          - type: translate
+            to: [0, 0, 0]
+      - frame: 3
+        transforms:
+          - type: scale
+            to: [2, 2, 2]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let error = yaml_loader.load_animation(source).unwrap_err();
+    assert!(error.to_string().contains("cannot be interpolated"));
+    assert!(error.to_string().contains("body.keyframes"));
+  }
+
+  #[test]
+  fn motion_blur_and_end_transforms_are_parsed() {
+    let source = r##"
+---
+- camera:
+    name: blurred
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+    motion_blur:
+      samples: 16
+      shutter: [0.25, 0.75]
+- body:
+    type: sphere
+    transforms:
+      - type: translate
+        to: [-1, 0, 0]
+    end_transforms:
+      - type: translate
+        to: [1, 0, 0]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, cameras) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(
+      cameras["blurred"].motion_blur,
+      Some(MotionBlur::default().with_samples(16).with_shutter(0.25, 0.75))
+    );
+    assert_eq!(
+      loaded_world.bodies[0],
+      Body::from(
+        Sphere::default()
+          .with_transform(Matrix::translation(-1.0, 0.0, 0.0))
+          .with_end_transform(Matrix::translation(1.0, 0.0, 0.0))
+      )
+    );
+  }
+
+  #[test]
+  fn lambert_and_blinn_phong_materials_are_parsed() {
+    let source = r##"
+---
+- body:
+    type: sphere
+    material:
+      type: lambert
+      color: [0.2, 0.4, 0.6]
+      diffuse: 0.8
+- body:
+    type: plane
+    material:
+      type: blinn_phong
+      specular: 0.5
+      shininess: 800
+      reflectiveness: 0.3
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(
+      loaded_world.bodies[0].material(),
+      Material::from(Lambert::default().with_color(Color::new(0.2, 0.4, 0.6)).with_diffuse(0.8))
+    );
+    assert_fuzzy_eq!(
+      loaded_world.bodies[1].material(),
+      Material::from(
+        BlinnPhong::default()
+          .with_specular(0.5)
+          .with_shininess(800.0)
+          .with_reflective(0.3)
+      )
+    );
+  }
+
+  #[test]
+  fn pbr_material_with_an_image_pattern_is_parsed() {
+    let mut canvas = Canvas::new(2, 2);
+    canvas.write_pixel(0, 1, Color::new(0.8, 0.8, 0.8));
+    let file_name = std::env::temp_dir().join("rustic_rt_pbr_roughness_map.ppm");
+    std::fs::write(&file_name, crate::canvas::to_ppm::ToPPM::to_binary_ppm(&canvas)).unwrap();
+    let source = format!(
+      r##"
+---
+- body:
+    type: sphere
+    material:
+      type: pbr
+      color: [0.9, 0.6, 0.2]
+      metallic: 1
+      roughness_pattern:
+        type: image
+        file: {}
+        transforms:
+          - type: scale
+            to: [0.5, 0.5, 1]
+"##,
+      file_name.display()
+    );
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    match loaded_world.bodies[0].material() {
+      Material::Pbr(pbr) => {
+        assert_fuzzy_eq!(pbr.color, Color::new(0.9, 0.6, 0.2));
+        assert_eq!(pbr.metallic, 1.0);
+        assert!(pbr.color_pattern.is_none());
+        // the bottom left texel, on the south pole of the sphere
+        let body = loaded_world.bodies[0].clone();
+        assert_fuzzy_eq!(pbr.roughness_at(Tuple::point(0.0, -1.0, 0.0), &body), 0.8);
+      }
+      _ => panic!("Expected a pbr material"),
+    }
+  }
+
+  #[test]
+  fn emission_and_emitter_samples_are_parsed() {
+    let source = r##"
+---
+- world:
+    emitter_samples: 24
+- body:
+    type: sphere
+    material:
+      type: toon
+      emission: [2, 1.5, 0.5]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(loaded_world.emitter_samples, 24);
+    assert_fuzzy_eq!(
+      loaded_world.bodies[0].material(),
+      Material::from(Toon::default().with_emission(Color::new(2.0, 1.5, 0.5)))
+    );
+
+    let err = yaml_loader.load_world(source.replace("24", "-1")).unwrap_err();
+    assert!(err.to_string().contains("Emitter samples must not be negative, got -1 at .document[0].item[0].world"));
+  }
+
+  #[test]
+  fn fresnel_reflections_are_parsed() {
+    let source = r##"
+---
+- body:
+    type: plane
+    material:
+      type: blinn_phong
+      reflectiveness: 0.02
+      fresnel: true
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(
+      loaded_world.bodies[0].material(),
+      Material::from(BlinnPhong::default().with_reflective(0.02).with_fresnel(true))
+    );
+  }
+
+  #[test]
+  fn glossy_reflections_are_parsed() {
+    let source = r##"
+---
+- world:
+    reflection_samples: 16
+- body:
+    type: plane
+    material:
+      type: phong
+      reflectiveness: 0.8
+      roughness: 0.3
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(loaded_world.reflection_samples, 16);
+    assert_fuzzy_eq!(
+      loaded_world.bodies[0].material(),
+      Material::from(Phong::default().with_reflective(0.8).with_roughness(0.3))
+    );
+
+    let err = yaml_loader.load_world(source.replace("16", "0")).unwrap_err();
+    assert!(err.to_string().contains("Glossy reflections need at least one sample, got 0 at .document[0].item[0].world"));
+  }
+
+  #[test]
+  fn integrators_are_parsed() {
+    let source = r##"
+---
+- camera:
+    name: path_traced
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+    integrator:
+      type: path_tracer
+      samples: 64
+      max_bounces: 8
+- camera:
+    name: whitted
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (_, cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(
+      cameras["path_traced"].integrator,
+      Integrator::from(PathTracer::default().with_samples(64).with_bounces(3, 8))
+    );
+    assert_eq!(cameras["whitted"].integrator, Integrator::Whitted);
+
+    let unknown = source.replace("path_tracer", "photon_mapper");
+    let err = yaml_loader.load_world(unknown).unwrap_err();
+    assert!(err.to_string().contains("Unknown integrator type 'photon_mapper'"));
+  }
+
+  #[test]
+  fn caustics_are_parsed() {
+    let source = r##"
+---
+- world:
+    caustics:
+      photons: 20000
+      radius: 0.25
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(loaded_world.caustics, Some(Caustics::default().with_photons(20000).with_radius(0.25)));
+    assert_eq!(Loader::default().load_world("- body:\n    type: sphere").unwrap().0.caustics, None);
+
+    let err = yaml_loader.load_world(source.replace("0.25", "0")).unwrap_err();
+    assert!(err.to_string().contains("Caustics need a positive gather radius"));
+    let err = yaml_loader.load_world(source.replace("20000", "-1")).unwrap_err();
+    assert!(err.to_string().contains("Caustics photons must not be negative, got -1 at .document[0].item[0].world.caustics"));
+  }
+
+  #[test]
+  fn fog_and_media_are_parsed() {
+    let source = r##"
+---
+- world:
+    fog:
+      color: [0.6, 0.7, 0.8]
+      density: 0.05
+- body:
+    type: sphere
+    medium:
+      density: 0.8
+      color: [0.9, 0.9, 1]
+      steps: 32
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    assert_fuzzy_eq!(
+      loaded_world.fog.unwrap(),
+      Fog::default().with_color(Color::new(0.6, 0.7, 0.8)).with_density(0.05)
+    );
+    assert_fuzzy_eq!(
+      loaded_world.bodies[0].medium().unwrap(),
+      Medium::default().with_density(0.8).with_color(Color::new(0.9, 0.9, 1.0)).with_steps(32)
+    );
+
+    let err = yaml_loader.load_world(source.replace("sphere", "plane")).unwrap_err();
+    assert!(err.to_string().contains("Only spheres can be filled with a medium"));
+    let err = yaml_loader.load_world(source.replace("0.05", "-1")).unwrap_err();
+    assert!(err.to_string().contains("Expected a density of 0 or more"));
+    let err = yaml_loader.load_world(source.replace("32", "0")).unwrap_err();
+    assert!(err.to_string().contains("Media need at least one step, got 0 at .document[0].item[1].body.medium"));
+  }
+
+  #[test]
+  fn fog_without_density_hides_nothing() {
+    let source = r##"
+---
+- world:
+    background:
+      type: solid
+      color: [0.2, 0.4, 0.6]
+    fog:
+      color: [1, 1, 1]
+      density: 0
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, _) = yaml_loader.load_world(source).unwrap();
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert_eq!(loaded_world.color_at(r), Color::new(0.2, 0.4, 0.6));
+  }
+
+  #[test]
+  fn ambient_occlusion_is_parsed() {
+    let source = r##"
+---
+- world:
+    ambient_occlusion:
+      samples: 24
+      radius: 0.5
+- camera:
+    name: occlusion
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+    integrator:
+      type: ambient_occlusion
+      radius: 2
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (loaded_world, cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(
+      loaded_world.ambient_occlusion,
+      Some(AmbientOcclusion::default().with_samples(24).with_radius(0.5))
+    );
+    assert_eq!(
+      cameras["occlusion"].integrator,
+      Integrator::from(AmbientOcclusion::default().with_radius(2.0))
+    );
+  }
+
+  #[test]
+  fn auxiliary_outputs_are_parsed() {
+    let source = r##"
+---
+- camera:
+    name: passes
+    width: 10
+    height: 10
+    field_of_view: 1.0
+    from: [0, 0, -5]
+    to: [0, 0, 0]
+    up: [0, 1, 0]
+    aovs: [depth, normal, object_id]
+"##;
+
+    let yaml_loader = Loader::default();
+
+    let (_, cameras) = yaml_loader.load_world(source).unwrap();
+    assert_eq!(cameras["passes"].aovs, vec![Aov::Depth, Aov::Normal, Aov::ObjectId]);
+
+    let err = yaml_loader.load_world(source.replace("normal", "specular")).unwrap_err();
+    assert!(err.to_string().contains("Unknown output 'specular'"));
+  }
+}
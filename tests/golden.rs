@@ -0,0 +1,108 @@
+// Renders every scene under world/ at a small size and compares each camera
+// against the reference images in tests/golden/.
+//
+// After an intended change of the shading, regenerate the references with
+//     UPDATE_GOLDEN=1 cargo test --test golden
+// and look at the new images before committing them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustic_rt::canvas::compare::Compare;
+use rustic_rt::canvas::from_png::FromPNG;
+use rustic_rt::canvas::to_png::ToPNG;
+use rustic_rt::canvas::Canvas;
+use rustic_rt::render::render;
+use rustic_rt::world_loader::yaml;
+use rustic_rt::world_loader::WorldLoader;
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 90;
+// a few pixels may flip on silhouettes and shadow edges between platforms
+const MAX_MEAN_ERROR: f64 = 0.002;
+const MIN_PSNR: f64 = 35.0;
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn scenes() -> Vec<PathBuf> {
+    let mut scenes: Vec<PathBuf> = fs::read_dir(root().join("world"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "yaml"))
+        .collect();
+    scenes.sort();
+    scenes
+}
+
+// goes through PNG so the render is quantized exactly like the reference
+fn render_cameras(scene: &Path) -> Vec<(String, Canvas)> {
+    let source = fs::read_to_string(scene).unwrap();
+    let (world, cameras) = yaml::Loader::default().load_world(source).unwrap();
+    let mut images: Vec<(String, Canvas)> = cameras
+        .iter()
+        .map(|(name, camera)| {
+            let camera = camera.clone().with_size(WIDTH, HEIGHT);
+            let image = camera.post_process(&render(&world, &camera));
+            (name.clone(), Canvas::from_png(&image.to_png()).unwrap())
+        })
+        .collect();
+    images.sort_by(|(a, _), (b, _)| a.cmp(b));
+    images
+}
+
+#[test]
+fn scenes_match_golden_images() {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|value| value == "1");
+    let golden_directory = root().join("tests").join("golden");
+    let failure_directory = root().join("target").join("golden-failures");
+    let mut failures = Vec::new();
+
+    for scene in scenes() {
+        let scene_name = scene.file_stem().unwrap().to_string_lossy().to_string();
+        for (camera_name, image) in render_cameras(&scene) {
+            let file_name = format!("{}_{}.png", scene_name, camera_name);
+            let golden_path = golden_directory.join(&file_name);
+            if update {
+                fs::write(&golden_path, image.to_png()).unwrap();
+                continue;
+            }
+            let golden = match fs::read(&golden_path) {
+                Ok(data) => Canvas::from_png(&data).unwrap(),
+                Err(_) => {
+                    failures.push(format!("{}: no reference image", file_name));
+                    continue;
+                }
+            };
+            let comparison = match image.compare(&golden) {
+                Ok(comparison) => comparison,
+                Err(error) => {
+                    failures.push(format!("{}: {}", file_name, error));
+                    continue;
+                }
+            };
+            if comparison.mean_error > MAX_MEAN_ERROR || comparison.psnr < MIN_PSNR {
+                fs::create_dir_all(&failure_directory).unwrap();
+                fs::write(failure_directory.join(&file_name), image.to_png()).unwrap();
+                let heat_map = image.heat_map(&golden).unwrap();
+                fs::write(
+                    failure_directory.join(format!("{}_{}_diff.png", scene_name, camera_name)),
+                    heat_map.to_png(),
+                )
+                .unwrap();
+                failures.push(format!(
+                    "{}: mean error {:.5}, max error {:.5}, PSNR {:.2} dB",
+                    file_name, comparison.mean_error, comparison.max_error, comparison.psnr
+                ));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Renders differ from the golden images (renders and heat maps written to {}):\n{}",
+        failure_directory.display(),
+        failures.join("\n")
+    );
+}